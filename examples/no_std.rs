@@ -0,0 +1,46 @@
+//! Verifies that the crate can be used without the standard library.
+//!
+//! This example is built as a library with its own panic handler. If `spinning_top` (or one of
+//! its dependencies) accidentally depended on `std`, the panic handler would conflict with the
+//! one of `std` and building the example would fail with a "duplicate lang item" error. Since
+//! examples are built by `cargo test`, this catches such regressions without a `no_std` target.
+//!
+//! Features that require `std` disable the check. So does the `critical-section` feature,
+//! because its tests link `std` through the `std` implementation of the `critical-section` dev
+//! dependency.
+
+#![no_std]
+
+use lock_api::RawMutex;
+use spinning_top::{RawSpinlock, Spinlock};
+
+static COUNTER: Spinlock<u32> = Spinlock::new(0);
+
+/// Exercises the raw lock, which only uses stack data.
+pub fn raw_lock() -> bool {
+    let lock = RawSpinlock::INIT;
+    lock.lock();
+    let locked_twice = lock.try_lock();
+    unsafe { lock.unlock() };
+    !locked_twice && lock.try_lock()
+}
+
+/// Exercises locking a `Spinlock` in a static and on the stack.
+pub fn spinlock() -> u32 {
+    *COUNTER.lock() += 1;
+
+    let spinlock = Spinlock::new([0u8; 16]);
+    spinlock.lock()[0] = 1;
+    if let Some(mut guard) = spinlock.try_lock() {
+        guard[1] = 2;
+    }
+    let data = spinlock.into_inner();
+
+    *COUNTER.lock() + u32::from(data[0]) + u32::from(data[1])
+}
+
+#[cfg(not(any(feature = "std", feature = "owning_ref", feature = "critical-section")))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
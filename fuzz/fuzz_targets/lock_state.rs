@@ -0,0 +1,98 @@
+//! Drives a `Spinlock` through arbitrary sequences of operations of simulated threads.
+//!
+//! Every input byte schedules one step of one simulated thread, so the fuzzer controls the
+//! interleaving like a deterministic scheduler. A model of the expected lock state is updated
+//! alongside and the lock is checked against it after every step.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lock_api::RawMutex;
+use spinning_top::{Spinlock, SpinlockExt, SpinlockGuard};
+
+const THREADS: usize = 4;
+
+enum Thread<'a> {
+    Idle,
+    /// Called `lock` and spins until the lock is free; retries on every scheduled step.
+    Spinning,
+    Holding(SpinlockGuard<'a, u64>),
+}
+
+enum Op {
+    TryLock,
+    Lock,
+    Unlock,
+    Write,
+}
+
+impl Op {
+    fn decode(byte: u8) -> (usize, Op) {
+        let thread = usize::from(byte) % THREADS;
+        let op = match usize::from(byte) / THREADS % 4 {
+            0 => Op::TryLock,
+            1 => Op::Lock,
+            2 => Op::Unlock,
+            _ => Op::Write,
+        };
+        (thread, op)
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let lock = Spinlock::new(0u64);
+    let mut threads: Vec<Thread<'_>> = (0..THREADS).map(|_| Thread::Idle).collect();
+    let mut writes = 0;
+
+    for &byte in data {
+        let (index, op) = Op::decode(byte);
+        let held_before = threads.iter().any(|t| matches!(t, Thread::Holding(_)));
+
+        let thread = &mut threads[index];
+        match (&mut *thread, op) {
+            // a spinning thread makes one attempt per step, whatever the operation
+            (Thread::Spinning, _) => {
+                if let Some(guard) = lock.try_lock() {
+                    assert!(!held_before, "acquired a lock that is held");
+                    *thread = Thread::Holding(guard);
+                }
+            }
+            (Thread::Idle, Op::TryLock) => match lock.try_lock() {
+                Some(guard) => {
+                    assert!(!held_before, "acquired a lock that is held");
+                    *thread = Thread::Holding(guard);
+                }
+                None => assert!(held_before, "failed to acquire a free lock"),
+            },
+            (Thread::Idle, Op::Lock) => *thread = Thread::Spinning,
+            // nothing to unlock or write without holding the lock
+            (Thread::Idle, Op::Unlock) | (Thread::Idle, Op::Write) => {}
+            (Thread::Holding(_), Op::TryLock) | (Thread::Holding(_), Op::Lock) => {
+                // locking again would deadlock, so only check that it's impossible
+                assert!(lock.try_lock().is_none(), "acquired a lock twice");
+            }
+            (Thread::Holding(_), Op::Unlock) => *thread = Thread::Idle,
+            (Thread::Holding(guard), Op::Write) => {
+                **guard += 1;
+                writes += 1;
+            }
+        }
+
+        // invariants after every step
+        let holders = threads
+            .iter()
+            .filter(|t| matches!(t, Thread::Holding(_)))
+            .count();
+        assert!(holders <= 1, "{} threads hold the lock", holders);
+        assert_eq!(lock.is_locked(), holders == 1);
+        // SAFETY: the raw lock is only inspected
+        assert_eq!(unsafe { lock.raw() }.is_locked(), holders == 1);
+        if let Some(live) = lock.live_guard_count() {
+            assert_eq!(live, holders, "guard count out of sync");
+        }
+    }
+
+    drop(threads);
+    assert!(!lock.is_locked());
+    assert_eq!(lock.into_inner(), writes);
+});
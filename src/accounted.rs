@@ -0,0 +1,87 @@
+use crate::SpinlockGuard;
+use core::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A [`SpinlockGuard`] that is accounted in a user-provided counter.
+///
+/// Created by [`SpinlockExt::lock_accounted`](crate::SpinlockExt::lock_accounted). The counter
+/// is incremented when the lock is acquired and decremented after the lock is released again.
+/// This makes it possible to assert that no locks are held at a certain point, e.g. by checking
+/// that a global "critical nesting" counter is zero.
+///
+/// The counter is updated with `Relaxed` operations, so it's only precise for the thread that
+/// acquired the locks.
+pub struct AccountedGuard<'a, T: ?Sized> {
+    guard: ManuallyDrop<SpinlockGuard<'a, T>>,
+    counter: &'a AtomicUsize,
+}
+
+impl<'a, T: ?Sized> AccountedGuard<'a, T> {
+    pub(crate) fn new(guard: SpinlockGuard<'a, T>, counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self {
+            guard: ManuallyDrop::new(guard),
+            counter,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for AccountedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for AccountedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for AccountedGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard is not used after this point
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        self.counter.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for AccountedGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Spinlock, SpinlockExt};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn counts_held_locks() {
+        let counter = AtomicUsize::new(0);
+        let a = Spinlock::new(1);
+        let b = Spinlock::new(2);
+
+        let mut guard_a = a.lock_accounted(&counter);
+        *guard_a += 1;
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        {
+            let guard_b = b.lock_accounted(&counter);
+            assert_eq!(*guard_b, 2);
+            assert_eq!(counter.load(Ordering::Relaxed), 2);
+        }
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        assert!(!b.is_locked());
+
+        drop(guard_a);
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+        assert_eq!(*a.lock(), 2);
+    }
+}
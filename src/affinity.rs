@@ -0,0 +1,178 @@
+//! Pinning the current thread to its core while holding a lock, enabled by the `pin-core`
+//! feature on Linux.
+
+use crate::SpinlockGuard;
+use core::{
+    convert::TryFrom,
+    fmt,
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+};
+
+/// Mirrors the layout of `cpu_set_t` of glibc and musl, a bit mask of 1024 CPUs.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub(crate) struct CpuSet {
+    bits: [usize; 1024 / usize::BITS as usize],
+}
+
+impl CpuSet {
+    fn single(cpu: usize) -> Option<Self> {
+        let mut set = Self {
+            bits: [0; 1024 / usize::BITS as usize],
+        };
+        let bits = usize::BITS as usize;
+        *set.bits.get_mut(cpu / bits)? |= 1 << (cpu % bits);
+        Some(set)
+    }
+
+    /// Returns the affinity mask of the current thread.
+    pub(crate) fn current() -> Option<Self> {
+        let mut set = Self {
+            bits: [0; 1024 / usize::BITS as usize],
+        };
+        // SAFETY: the pointer is valid for writes of `size_of::<CpuSet>()` bytes; pid 0 refers
+        // to the calling thread
+        let ret = unsafe { sched_getaffinity(0, mem::size_of::<CpuSet>(), &mut set) };
+        if ret == 0 {
+            Some(set)
+        } else {
+            None
+        }
+    }
+
+    /// Sets the affinity mask of the current thread, returning whether it succeeded.
+    fn apply(&self) -> bool {
+        // SAFETY: the pointer is valid for reads of `size_of::<CpuSet>()` bytes; pid 0 refers
+        // to the calling thread
+        unsafe { sched_setaffinity(0, mem::size_of::<CpuSet>(), self) == 0 }
+    }
+}
+
+impl fmt::Debug for CpuSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = usize::BITS as usize;
+        f.debug_set()
+            .entries((0..1024).filter(|cpu| self.bits[cpu / bits] & (1 << (cpu % bits)) != 0))
+            .finish()
+    }
+}
+
+extern "C" {
+    fn sched_getcpu() -> i32;
+    fn sched_getaffinity(pid: i32, cpusetsize: usize, mask: *mut CpuSet) -> i32;
+    fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const CpuSet) -> i32;
+}
+
+/// Pins the current thread to the core it is running on.
+///
+/// Returns the previous affinity mask, or `None` if the thread could not be pinned.
+fn pin_current_core() -> Option<CpuSet> {
+    let previous = CpuSet::current()?;
+    // SAFETY: `sched_getcpu` has no preconditions
+    let cpu = usize::try_from(unsafe { sched_getcpu() }).ok()?;
+    if CpuSet::single(cpu)?.apply() {
+        Some(previous)
+    } else {
+        None
+    }
+}
+
+/// A [`SpinlockGuard`] that keeps the current thread on its core while the lock is held.
+///
+/// Created by [`SpinlockExt::lock_pinned_core`](crate::SpinlockExt::lock_pinned_core). When the
+/// guard is dropped, the lock is released and the previous affinity mask of the thread is
+/// restored.
+///
+/// Requires the `pin-core` feature and Linux.
+pub struct PinnedSpinlockGuard<'a, T: ?Sized> {
+    guard: ManuallyDrop<SpinlockGuard<'a, T>>,
+    /// The affinity mask to restore, or `None` if pinning failed.
+    previous: Option<CpuSet>,
+}
+
+impl<'a, T: ?Sized> PinnedSpinlockGuard<'a, T> {
+    /// Pins the current thread, then acquires the lock through `lock`.
+    ///
+    /// Pinning happens first so that the thread doesn't migrate between acquiring the lock and
+    /// pinning.
+    pub(crate) fn new(lock: impl FnOnce() -> SpinlockGuard<'a, T>) -> Self {
+        let previous = pin_current_core();
+        Self {
+            guard: ManuallyDrop::new(lock()),
+            previous,
+        }
+    }
+
+    /// Returns whether the current thread was successfully pinned to its core.
+    ///
+    /// Pinning fails if the thread runs on a CPU outside of the supported range of 1024 CPUs or
+    /// if the affinity mask can't be changed, e.g. because of a seccomp filter. The lock is
+    /// still held in this case.
+    pub fn is_pinned(this: &Self) -> bool {
+        this.previous.is_some()
+    }
+}
+
+impl<T: ?Sized> Deref for PinnedSpinlockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for PinnedSpinlockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for PinnedSpinlockGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard is not used after this point
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if let Some(previous) = &self.previous {
+            // there's nothing sensible to do on failure, the thread just stays pinned
+            let _ = previous.apply();
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PinnedSpinlockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Spinlock, SpinlockExt};
+
+    #[test]
+    fn pins_and_restores_affinity() {
+        let before = CpuSet::current().unwrap();
+
+        let spinlock = Spinlock::new(0);
+        let mut guard = spinlock.lock_pinned_core();
+        *guard += 1;
+        if PinnedSpinlockGuard::is_pinned(&guard) {
+            let cpu = usize::try_from(unsafe { sched_getcpu() }).unwrap();
+            assert_eq!(CpuSet::current(), CpuSet::single(cpu));
+        }
+        drop(guard);
+
+        assert!(!spinlock.is_locked());
+        assert_eq!(CpuSet::current().unwrap(), before);
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[test]
+    fn cpu_set_bits() {
+        assert_eq!(mem::size_of::<CpuSet>(), 128);
+        assert!(CpuSet::single(1024).is_none());
+        let set = CpuSet::single(65).unwrap();
+        assert_eq!(std::format!("{:?}", set), "{65}");
+    }
+}
@@ -0,0 +1,224 @@
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Types that can be stored in an [`AtomicSpinlock`].
+///
+/// ## Safety
+///
+/// Implementors must satisfy the following requirements:
+///
+/// - The size of the type must not exceed the size of `usize`.
+/// - The type must not contain padding bytes or other uninitialized bytes, since the bytes of
+///   the value are copied into an integer.
+/// - Values are compared bitwise by [`AtomicSpinlock::compare_exchange`], so this must be a
+///   meaningful comparison for the type. For example, the implementation for `f32` treats
+///   `0.0` and `-0.0` as different values.
+pub unsafe trait SmallCopy: Copy {}
+
+macro_rules! impl_small_copy {
+    ($($t:ty),*) => {
+        $(unsafe impl SmallCopy for $t {})*
+    };
+}
+
+impl_small_copy!(
+    u8,
+    u16,
+    u32,
+    usize,
+    i8,
+    i16,
+    i32,
+    isize,
+    bool,
+    char,
+    f32,
+    ()
+);
+#[cfg(target_pointer_width = "64")]
+impl_small_copy!(u64, i64, f64);
+
+/// A replacement for a [`Spinlock`](crate::Spinlock) of a small `Copy` value that uses native
+/// atomic operations instead of a lock.
+///
+/// The value is stored in an [`AtomicUsize`], so [`load`](Self::load), [`store`](Self::store),
+/// and [`compare_exchange`](Self::compare_exchange) are genuine atomic operations that never
+/// spin on a lock. Compound operations are expressed through
+/// [`fetch_update`](Self::fetch_update), a compare-and-swap loop that retries if the value was
+/// changed concurrently, so no update is ever lost.
+///
+/// There is intentionally no guard-based `lock` method: a guard would have to work on a copy of
+/// the value and store it back on release, overwriting any concurrent atomic update.
+///
+/// The type `T` must implement [`SmallCopy`], which ensures that it fits into a `usize` and
+/// has no padding bytes.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::AtomicSpinlock;
+///
+/// let lock = AtomicSpinlock::new(1u32);
+/// lock.store(2);
+/// assert_eq!(lock.compare_exchange(2, 3), Ok(2));
+/// assert_eq!(lock.compare_exchange(2, 4), Err(3));
+///
+/// assert_eq!(lock.fetch_update(|v| v + 1), 3);
+/// assert_eq!(lock.load(), 4);
+/// ```
+pub struct AtomicSpinlock<T: SmallCopy> {
+    bits: AtomicUsize,
+    _value: PhantomData<T>,
+}
+
+impl<T: SmallCopy> AtomicSpinlock<T> {
+    /// Creates a new `AtomicSpinlock`.
+    pub fn new(val: T) -> Self {
+        Self {
+            bits: AtomicUsize::new(Self::to_bits(val)),
+            _value: PhantomData,
+        }
+    }
+
+    fn to_bits(val: T) -> usize {
+        assert!(mem::size_of::<T>() <= mem::size_of::<usize>());
+        let mut bits = 0usize;
+        // SAFETY: `T` fits into a `usize` and has no uninitialized bytes
+        unsafe {
+            ptr::copy_nonoverlapping(
+                &val as *const T as *const u8,
+                &mut bits as *mut usize as *mut u8,
+                mem::size_of::<T>(),
+            );
+        }
+        bits
+    }
+
+    fn from_bits(bits: usize) -> T {
+        let mut val = MaybeUninit::<T>::uninit();
+        // SAFETY: all bits stored in the lock were created by `to_bits` from a valid `T`
+        unsafe {
+            ptr::copy_nonoverlapping(
+                &bits as *const usize as *const u8,
+                val.as_mut_ptr() as *mut u8,
+                mem::size_of::<T>(),
+            );
+            val.assume_init()
+        }
+    }
+
+    /// Atomically loads the value.
+    pub fn load(&self) -> T {
+        Self::from_bits(self.bits.load(Ordering::Acquire))
+    }
+
+    /// Atomically stores a new value.
+    pub fn store(&self, val: T) {
+        self.bits.store(Self::to_bits(val), Ordering::Release);
+    }
+
+    /// Atomically replaces the value with `new` if it is bitwise equal to `current`.
+    ///
+    /// Returns `Ok` with the previous value on success or `Err` with the current value
+    /// otherwise.
+    pub fn compare_exchange(&self, current: T, new: T) -> Result<T, T> {
+        self.bits
+            .compare_exchange(
+                Self::to_bits(current),
+                Self::to_bits(new),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .map(Self::from_bits)
+            .map_err(Self::from_bits)
+    }
+
+    /// Atomically replaces the value with the result of `f`, retrying if the value was changed
+    /// concurrently.
+    ///
+    /// Returns the previous value. `f` may be called multiple times.
+    pub fn fetch_update(&self, mut f: impl FnMut(T) -> T) -> T {
+        let mut current = self.load();
+        loop {
+            match self.compare_exchange(current, f(current)) {
+                Ok(previous) => return previous,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Consumes the lock, returning the value.
+    pub fn into_inner(self) -> T {
+        Self::from_bits(self.bits.into_inner())
+    }
+}
+
+impl<T: SmallCopy + fmt::Debug> fmt::Debug for AtomicSpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtomicSpinlock")
+            .field("value", &self.load())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread, vec::Vec};
+
+    #[test]
+    fn atomic_ops() {
+        let lock = AtomicSpinlock::new(1i16);
+        assert_eq!(lock.load(), 1);
+        lock.store(2);
+        assert_eq!(lock.compare_exchange(2, 3), Ok(2));
+        assert_eq!(lock.compare_exchange(2, 4), Err(3));
+        assert_eq!(lock.fetch_update(|v| v * 2), 3);
+        assert_eq!(lock.into_inner(), 6);
+    }
+
+    #[test]
+    fn small_types() {
+        let lock = AtomicSpinlock::new('a');
+        assert_eq!(lock.compare_exchange('a', '€'), Ok('a'));
+        assert_eq!(lock.load(), '€');
+
+        let lock = AtomicSpinlock::new(-0.0f32);
+        assert_eq!(lock.compare_exchange(0.0, 1.0), Err(-0.0));
+        assert_eq!(lock.compare_exchange(-0.0, 1.5), Ok(-0.0));
+        assert_eq!(lock.load(), 1.5);
+
+        let lock = AtomicSpinlock::new(true);
+        assert!(lock.fetch_update(|v| !v));
+        assert!(!lock.load());
+    }
+
+    #[test]
+    fn concurrent_fetch_update() {
+        const THREADS: usize = 4;
+        const INCREMENTS: u32 = 1000;
+
+        let lock = Arc::new(AtomicSpinlock::new(0u32));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    for _ in 0..INCREMENTS {
+                        lock.fetch_update(|v| v + 1);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(lock.load(), THREADS as u32 * INCREMENTS);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{relax::relax, Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    ops::Deref,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A [`Spinlock`] with a runtime-configurable limit on the number of spin iterations.
+///
+/// The limit is stored in the lock and can be adjusted at any time through
+/// [`set_max_spins`](Self::set_max_spins), e.g. to tune the same binary for different
+/// deployment environments. It is read with a single `Relaxed` load at the start of every
+/// bounded acquisition.
+///
+/// Two acquisition methods respect the limit: [`try_lock_bounded`](Self::try_lock_bounded)
+/// returns `None` when the limit is exceeded, while [`lock_bounded`](Self::lock_bounded) panics.
+/// The wrapper dereferences to the inner [`Spinlock`], so the unbounded `lock` and `try_lock`
+/// methods are available too.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::BoundedSpinlock;
+///
+/// let spinlock = BoundedSpinlock::new(1000, 0);
+/// *spinlock.lock_bounded() += 1;
+///
+/// let _guard = spinlock.lock();
+/// spinlock.set_max_spins(10);
+/// assert!(spinlock.try_lock_bounded().is_none());
+/// ```
+pub struct BoundedSpinlock<T: ?Sized> {
+    max_spins: AtomicUsize,
+    lock: Spinlock<T>,
+}
+
+impl<T> BoundedSpinlock<T> {
+    /// Creates a new unlocked `BoundedSpinlock` that spins at most `max_spins` times.
+    pub const fn new(max_spins: usize, val: T) -> Self {
+        Self {
+            max_spins: AtomicUsize::new(max_spins),
+            lock: Spinlock::new(val),
+        }
+    }
+
+    /// Consumes the wrapper, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: ?Sized> BoundedSpinlock<T> {
+    /// Returns the current spin limit.
+    pub fn max_spins(&self) -> usize {
+        self.max_spins.load(Ordering::Relaxed)
+    }
+
+    /// Sets the spin limit for subsequent acquisitions.
+    pub fn set_max_spins(&self, max_spins: usize) {
+        self.max_spins.store(max_spins, Ordering::Relaxed);
+    }
+
+    /// Tries to acquire the lock, spinning at most [`max_spins`](Self::max_spins) times.
+    ///
+    /// Returns `None` if the lock could not be acquired within the limit.
+    pub fn try_lock_bounded(&self) -> Option<SpinlockGuard<'_, T>> {
+        let max_spins = self.max_spins();
+        let mut spins = 0;
+        loop {
+            if let Some(guard) = self.lock.try_lock() {
+                return Some(guard);
+            }
+            while self.lock.is_locked() {
+                if spins >= max_spins {
+                    return None;
+                }
+                spins += 1;
+                relax();
+            }
+        }
+    }
+
+    /// Acquires the lock, spinning at most [`max_spins`](Self::max_spins) times.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the lock could not be acquired within the limit.
+    pub fn lock_bounded(&self) -> SpinlockGuard<'_, T> {
+        self.try_lock_bounded()
+            .expect("BoundedSpinlock exceeded its spin limit")
+    }
+}
+
+impl<T: ?Sized> Deref for BoundedSpinlock<T> {
+    type Target = Spinlock<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lock
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for BoundedSpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoundedSpinlock")
+            .field("max_spins", &self.max_spins())
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{
+        sync::{Arc, Barrier},
+        thread,
+    };
+
+    #[test]
+    fn uncontended() {
+        let spinlock = BoundedSpinlock::new(0, 1);
+        *spinlock.try_lock_bounded().unwrap() += 1;
+        *spinlock.lock_bounded() += 1;
+        assert_eq!(spinlock.into_inner(), 3);
+    }
+
+    #[test]
+    fn limit_exceeded() {
+        let spinlock = BoundedSpinlock::new(usize::MAX, 1);
+        let _guard = spinlock.lock();
+        spinlock.set_max_spins(100);
+        assert_eq!(spinlock.max_spins(), 100);
+        assert!(spinlock.try_lock_bounded().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeded its spin limit")]
+    fn lock_bounded_panics() {
+        let spinlock = BoundedSpinlock::new(100, 1);
+        let _guard = spinlock.lock();
+        drop(spinlock.lock_bounded());
+    }
+
+    #[test]
+    fn waits_within_limit() {
+        let spinlock = Arc::new(BoundedSpinlock::new(usize::MAX, 1));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let mut guard = spinlock.lock();
+                barrier.wait();
+                *guard += 1;
+            })
+        };
+
+        barrier.wait();
+        assert_eq!(*spinlock.lock_bounded(), 2);
+        holder.join().unwrap();
+    }
+}
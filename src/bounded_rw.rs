@@ -0,0 +1,337 @@
+//! A read-write spinlock with a limited number of concurrent readers.
+
+use crate::{relax::relax, spinlock::DefaultGuardMarker};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lock_api::RawRwLock;
+
+/// The bit of the state that is set while a writer holds the lock.
+const WRITER: usize = 1 << (usize::BITS - 1);
+/// The bit of the state that is set while [`RawBoundedRwSpinlock::lock_draining`] waits for the
+/// active readers to finish. New readers and writers are held off while it is set.
+const WRITER_WAITING: usize = 1 << (usize::BITS - 2);
+
+/// A raw read-write spinlock that admits at most `MAX` concurrent readers.
+///
+/// A shared acquisition spins while a writer holds the lock or while `MAX` readers are already
+/// active. An exclusive acquisition spins until there are no readers or writers. With the
+/// exclusive mode unused, the lock is thus a counting semaphore with `MAX` permits.
+///
+/// The exclusive mode of the [`RawRwLock`] implementation doesn't prioritize writers, so a
+/// steady stream of readers can starve writers. Use [`lock_draining`](Self::lock_draining)
+/// instead to keep new readers out while waiting. `MAX` must be greater than zero, otherwise
+/// shared acquisitions never succeed.
+#[derive(Debug)]
+pub struct RawBoundedRwSpinlock<const MAX: usize> {
+    /// The number of active readers, or [`WRITER`] if a writer holds the lock, optionally
+    /// combined with [`WRITER_WAITING`].
+    state: AtomicUsize,
+}
+
+impl<const MAX: usize> RawBoundedRwSpinlock<MAX> {
+    /// Creates a raw lock with the given state, i.e. a reader count or [`WRITER`].
+    ///
+    /// Only available in tests, e.g. for starting at the reader limit.
+    #[cfg(test)]
+    const fn with_state(state: usize) -> Self {
+        Self {
+            state: AtomicUsize::new(state),
+        }
+    }
+
+    /// Acquires the lock exclusively, keeping new readers out while waiting.
+    ///
+    /// Marks a writer as waiting first, which makes all subsequent shared and exclusive
+    /// acquisitions fail, and then spins until the active readers released their permits. Only
+    /// one thread can wait this way at a time, others spin until the lock is released again.
+    ///
+    /// Release the lock through [`RawRwLock::unlock_exclusive`].
+    pub fn lock_draining(&self) {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & WRITER_WAITING != 0 {
+                relax();
+                state = self.state.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                state | WRITER_WAITING,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => state = current,
+            }
+        }
+        // the remaining readers or a writer that was already active clear their bits on unlock
+        while self
+            .state
+            .compare_exchange_weak(WRITER_WAITING, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            relax();
+        }
+    }
+}
+
+unsafe impl<const MAX: usize> RawRwLock for RawBoundedRwSpinlock<MAX> {
+    const INIT: Self = Self {
+        state: AtomicUsize::new(0),
+    };
+
+    // A spinlock guard can be sent to another thread and unlocked there, unless the
+    // `no-send-guards` feature is enabled
+    type GuardMarker = DefaultGuardMarker;
+
+    fn lock_shared(&self) {
+        while !self.try_lock_shared() {
+            relax();
+        }
+    }
+
+    fn try_lock_shared(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        // retry as long as the reason for a failed CAS was a concurrent change of the reader
+        // count and there is still room for another reader
+        while state & (WRITER | WRITER_WAITING) == 0 && state < MAX {
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(current) => state = current,
+            }
+        }
+        false
+    }
+
+    unsafe fn unlock_shared(&self) {
+        self.state.fetch_sub(1, Ordering::Release);
+    }
+
+    fn lock_exclusive(&self) {
+        while !self.try_lock_exclusive() {
+            // Wait until the lock looks unlocked before retrying
+            while self.state.load(Ordering::Relaxed) != 0 {
+                relax();
+            }
+        }
+    }
+
+    fn try_lock_exclusive(&self) -> bool {
+        self.state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    unsafe fn unlock_exclusive(&self) {
+        // keep the bit of a thread that waits in `lock_draining`
+        self.state.fetch_and(!WRITER, Ordering::Release);
+    }
+
+    fn is_locked(&self) -> bool {
+        // Relaxed is sufficient because this operation does not provide synchronization, only atomicity.
+        self.state.load(Ordering::Relaxed) & !WRITER_WAITING != 0
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & WRITER != 0
+    }
+}
+
+/// A read-write lock based on [`RawBoundedRwSpinlock`], with at most `MAX` concurrent readers.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::BoundedRwSpinlock;
+///
+/// let lock = BoundedRwSpinlock::<_, 2>::new(0);
+/// let reader_1 = lock.read();
+/// let reader_2 = lock.read();
+/// assert!(lock.try_read().is_none()); // all permits are taken
+///
+/// drop((reader_1, reader_2));
+/// *lock.write() += 1;
+/// assert_eq!(*lock.read(), 1);
+/// ```
+pub type BoundedRwSpinlock<T, const MAX: usize> = lock_api::RwLock<RawBoundedRwSpinlock<MAX>, T>;
+
+/// A RAII guard returned by [`BoundedRwSpinlock::read`](lock_api::RwLock::read).
+pub type BoundedRwSpinlockReadGuard<'a, T, const MAX: usize> =
+    lock_api::RwLockReadGuard<'a, RawBoundedRwSpinlock<MAX>, T>;
+
+/// A RAII guard returned by [`BoundedRwSpinlock::write`](lock_api::RwLock::write).
+pub type BoundedRwSpinlockWriteGuard<'a, T, const MAX: usize> =
+    lock_api::RwLockWriteGuard<'a, RawBoundedRwSpinlock<MAX>, T>;
+
+/// Extension methods for [`BoundedRwSpinlock`].
+pub trait BoundedRwSpinlockExt<T: ?Sized> {
+    /// Waits until all permits are free, then runs `f` with exclusive access to the data.
+    ///
+    /// This is the "drain then mutate" pattern of pools: new readers and writers are held off
+    /// as soon as this method starts waiting, so it completes once the currently active readers
+    /// finish, even under a steady stream of new readers. The lock is released when `f`
+    /// returns or panics.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{BoundedRwSpinlock, BoundedRwSpinlockExt};
+    ///
+    /// let pool = BoundedRwSpinlock::<_, 4>::new(vec![1, 2]);
+    /// let len = pool.with_exclusive(|connections| {
+    ///     connections.push(3);
+    ///     connections.len()
+    /// });
+    /// assert_eq!(len, 3);
+    /// assert_eq!(pool.read().len(), 3);
+    /// ```
+    fn with_exclusive<R>(&self, f: impl FnOnce(&mut T) -> R) -> R;
+}
+
+impl<T: ?Sized, const MAX: usize> BoundedRwSpinlockExt<T> for BoundedRwSpinlock<T, MAX> {
+    fn with_exclusive<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        // SAFETY: the guard is created right after acquiring the lock exclusively
+        let mut guard = unsafe {
+            self.raw().lock_draining();
+            self.make_write_guard_unchecked()
+        };
+        f(&mut guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+    use std::{sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn edge_states() {
+        let lock = RawBoundedRwSpinlock::<3>::with_state(3);
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock_shared());
+        assert!(!lock.try_lock_exclusive());
+        unsafe { lock.unlock_shared() };
+        assert!(lock.try_lock_shared());
+
+        let lock = RawBoundedRwSpinlock::<3>::with_state(WRITER);
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock_shared());
+        unsafe { lock.unlock_exclusive() };
+        assert!(!lock.is_locked());
+        assert!(lock.try_lock_exclusive());
+
+        let lock = RawBoundedRwSpinlock::<3>::with_state(1 | WRITER_WAITING);
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock_shared());
+        assert!(!lock.try_lock_exclusive());
+        unsafe { lock.unlock_shared() };
+        assert!(!lock.is_locked());
+        assert!(!lock.try_lock_shared());
+    }
+
+    #[test]
+    fn unlock_exclusive_keeps_waiting_bit() {
+        let lock = RawBoundedRwSpinlock::<3>::with_state(WRITER | WRITER_WAITING);
+        unsafe { lock.unlock_exclusive() };
+        assert!(!lock.try_lock_shared());
+        lock.state.store(0, Ordering::Relaxed);
+        lock.lock_draining();
+        assert!(lock.is_locked_exclusive());
+    }
+
+    #[test]
+    fn reader_limit() {
+        let lock = BoundedRwSpinlock::<_, 3>::new(1);
+        let readers = [lock.read(), lock.read(), lock.read()];
+        assert!(lock.try_read().is_none());
+        assert!(lock.try_write().is_none());
+        drop(readers);
+
+        let writer = lock.write();
+        assert!(lock.try_read().is_none());
+        drop(writer);
+        assert_eq!(*lock.read(), 1);
+    }
+
+    #[test]
+    fn extra_reader_waits_for_release() {
+        let lock = Arc::new(BoundedRwSpinlock::<_, 2>::new(1));
+        let reader_1 = lock.read();
+        let reader_2 = lock.read();
+
+        let acquired = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let lock = lock.clone();
+            let acquired = acquired.clone();
+            thread::spawn(move || {
+                let reader = lock.read();
+                acquired.store(true, Ordering::Relaxed);
+                *reader
+            })
+        };
+
+        thread::sleep(Duration::from_millis(10));
+        assert!(!acquired.load(Ordering::Relaxed));
+        drop(reader_1);
+        assert_eq!(waiter.join().unwrap(), 1);
+        assert!(acquired.load(Ordering::Relaxed));
+        drop(reader_2);
+        assert!(!lock.is_locked());
+    }
+
+    #[test]
+    fn with_exclusive_drains_readers() {
+        let lock = Arc::new(BoundedRwSpinlock::<_, 2>::new(1));
+        let reader = lock.read();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let drainer = {
+            let lock = lock.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                lock.with_exclusive(|value| *value += 1);
+                done.store(true, Ordering::Relaxed);
+            })
+        };
+
+        // wait until the drainer holds off new readers
+        while lock.try_read().is_some() {
+            thread::yield_now();
+        }
+        assert!(!done.load(Ordering::Relaxed));
+        assert!(lock.try_write().is_none());
+
+        let late_reader = {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.read())
+        };
+        thread::sleep(Duration::from_millis(10));
+        assert!(!done.load(Ordering::Relaxed));
+        assert_eq!(*reader, 1);
+
+        drop(reader);
+        drainer.join().unwrap();
+        assert!(done.load(Ordering::Relaxed));
+        // the late reader only got in after the update
+        assert_eq!(late_reader.join().unwrap(), 2);
+        assert!(!lock.is_locked());
+    }
+
+    #[test]
+    fn with_exclusive_panic_releases_lock() {
+        let lock = BoundedRwSpinlock::<_, 2>::new(1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            lock.with_exclusive(|_| panic!("with_exclusive"))
+        }));
+        assert!(result.is_err());
+        assert!(!lock.is_locked());
+        assert!(lock.try_read().is_some());
+    }
+}
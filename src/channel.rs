@@ -0,0 +1,198 @@
+use crate::{relax::relax, Spinlock};
+use core::{fmt, mem::MaybeUninit};
+
+/// A bounded single-producer single-consumer channel protected by a spinlock.
+///
+/// The channel stores up to `N` values in an inline ring buffer, so it doesn't require an
+/// allocator and can be placed in a `static`. All accesses to the buffer are serialized through
+/// a [`RawSpinlock`](crate::RawSpinlock), which keeps the implementation simple and also makes
+/// the channel safe to use with multiple producers or consumers (at the cost of more contention).
+///
+/// The capacity `N` must be greater than zero, otherwise [`send`](Self::send) never returns.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::SpinChannel;
+///
+/// static CHANNEL: SpinChannel<u32, 4> = SpinChannel::new();
+///
+/// CHANNEL.send(1);
+/// assert_eq!(CHANNEL.try_send(2), Ok(()));
+/// assert_eq!(CHANNEL.recv(), 1);
+/// assert_eq!(CHANNEL.try_recv(), Some(2));
+/// assert_eq!(CHANNEL.try_recv(), None);
+/// ```
+pub struct SpinChannel<T, const N: usize> {
+    ring: Spinlock<Ring<T, N>>,
+}
+
+struct Ring<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    /// Index of the oldest value.
+    head: usize,
+    /// Number of initialized values, starting at `head`.
+    len: usize,
+}
+
+impl<T, const N: usize> SpinChannel<T, N> {
+    /// Creates a new empty channel.
+    pub const fn new() -> Self {
+        Self {
+            ring: Spinlock::new(Ring {
+                // SAFETY: an array of `MaybeUninit` requires no initialization
+                buf: unsafe { MaybeUninit::<[MaybeUninit<T>; N]>::uninit().assume_init() },
+                head: 0,
+                len: 0,
+            }),
+        }
+    }
+
+    /// Tries to send a value without blocking.
+    ///
+    /// Returns the value back as `Err` if the channel is full.
+    pub fn try_send(&self, value: T) -> Result<(), T> {
+        let mut ring = self.ring.lock();
+        if ring.len == N {
+            return Err(value);
+        }
+        let tail = (ring.head + ring.len) % N;
+        ring.buf[tail] = MaybeUninit::new(value);
+        ring.len += 1;
+        Ok(())
+    }
+
+    /// Tries to receive a value without blocking.
+    ///
+    /// Returns `None` if the channel is empty.
+    pub fn try_recv(&self) -> Option<T> {
+        let mut ring = self.ring.lock();
+        if ring.len == 0 {
+            return None;
+        }
+        let head = ring.head;
+        // SAFETY: the `len` values starting at `head` are initialized, and we move this one out
+        // by advancing `head` below
+        let value = unsafe { ring.buf[head].as_ptr().read() };
+        ring.head = (head + 1) % N;
+        ring.len -= 1;
+        Some(value)
+    }
+
+    /// Sends a value, spinning until there is space in the channel.
+    pub fn send(&self, mut value: T) {
+        loop {
+            match self.try_send(value) {
+                Ok(()) => return,
+                Err(v) => value = v,
+            }
+            relax();
+        }
+    }
+
+    /// Receives a value, spinning until one is available.
+    pub fn recv(&self) -> T {
+        loop {
+            if let Some(value) = self.try_recv() {
+                return value;
+            }
+            relax();
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SpinChannel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Ring<T, N> {
+    fn drop(&mut self) {
+        for i in 0..self.len {
+            let index = (self.head + i) % N;
+            // SAFETY: the `len` values starting at `head` are initialized
+            unsafe { self.buf[index].as_mut_ptr().drop_in_place() };
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for SpinChannel<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SpinChannel");
+        match self.ring.try_lock() {
+            Some(ring) => d.field("len", &ring.len),
+            None => d.field("len", &format_args!("<locked>")),
+        };
+        d.field("capacity", &N).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{rc::Rc, sync::Arc, thread};
+
+    #[test]
+    fn fill_to_capacity() {
+        let channel = SpinChannel::<u32, 3>::new();
+        assert_eq!(channel.try_send(1), Ok(()));
+        assert_eq!(channel.try_send(2), Ok(()));
+        assert_eq!(channel.try_send(3), Ok(()));
+        assert_eq!(channel.try_send(4), Err(4));
+
+        assert_eq!(channel.try_recv(), Some(1));
+        assert_eq!(channel.try_send(4), Ok(()));
+        assert_eq!(channel.try_recv(), Some(2));
+        assert_eq!(channel.try_recv(), Some(3));
+        assert_eq!(channel.try_recv(), Some(4));
+        assert_eq!(channel.try_recv(), None);
+    }
+
+    #[test]
+    fn wrap_around() {
+        let channel = SpinChannel::<usize, 2>::new();
+        for i in 0..10 {
+            channel.send(i);
+            channel.send(i + 100);
+            assert_eq!(channel.recv(), i);
+            assert_eq!(channel.recv(), i + 100);
+        }
+        assert_eq!(channel.try_recv(), None);
+    }
+
+    #[test]
+    fn drops_remaining_values() {
+        let value = Rc::new(());
+        let channel = SpinChannel::<_, 4>::new();
+        channel.send(value.clone());
+        channel.send(value.clone());
+        drop(channel.recv());
+        assert_eq!(Rc::strong_count(&value), 2);
+        drop(channel);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn producer_consumer() {
+        const COUNT: usize = 100;
+
+        let channel = Arc::new(SpinChannel::<usize, 8>::new());
+        let producer = {
+            let channel = channel.clone();
+            thread::spawn(move || {
+                for i in 0..COUNT {
+                    channel.send(i);
+                }
+            })
+        };
+
+        for i in 0..COUNT {
+            assert_eq!(channel.recv(), i);
+        }
+        producer.join().unwrap();
+        assert_eq!(channel.try_recv(), None);
+    }
+}
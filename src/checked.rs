@@ -0,0 +1,162 @@
+//! Construction of spinlocks whose data is asserted to have no interior mutability.
+
+use crate::{const_spinlock, Spinlock};
+
+/// Marker for types without interior mutability, i.e. without `Cell`, atomics, or similar
+/// types that allow changing the data through a shared reference.
+///
+/// Data protected by a [`Spinlock`] is only supposed to change while the lock is held. This
+/// invariant can be circumvented if the data contains interior mutability that is reachable
+/// through a shared reference that escaped the critical section. For safety-critical code, the
+/// [`checked_spinlock`] constructor only accepts types implementing this trait, which documents
+/// and enforces the invariant at construction.
+///
+/// The trait is implemented for primitive types and for arrays, slices, tuples, and `Option`s of
+/// implementing types. Implement it for your own types after checking that none of their fields
+/// has interior mutability:
+///
+/// ```rust
+/// use spinning_top::{checked_spinlock, NoInteriorMutability};
+///
+/// struct Reading {
+///     sensor: u8,
+///     value: [i16; 4],
+/// }
+///
+/// impl NoInteriorMutability for Reading {}
+///
+/// let reading = checked_spinlock(Reading { sensor: 1, value: [0; 4] });
+/// reading.lock().value[0] = 42;
+/// ```
+///
+/// The compiler can't verify the assertion, so implementing this trait for a type with interior
+/// mutability is not unsafe, but defeats its purpose.
+pub trait NoInteriorMutability {}
+
+macro_rules! impl_no_interior_mutability {
+    ($($ty:ty),* $(,)?) => {
+        $(impl NoInteriorMutability for $ty {})*
+    };
+}
+
+impl_no_interior_mutability!(
+    (),
+    bool,
+    char,
+    str,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+);
+
+impl<T: NoInteriorMutability, const N: usize> NoInteriorMutability for [T; N] {}
+impl<T: NoInteriorMutability> NoInteriorMutability for [T] {}
+impl<T: NoInteriorMutability> NoInteriorMutability for Option<T> {}
+impl<A: NoInteriorMutability, B: NoInteriorMutability> NoInteriorMutability for (A, B) {}
+impl<A: NoInteriorMutability, B: NoInteriorMutability, C: NoInteriorMutability> NoInteriorMutability
+    for (A, B, C)
+{
+}
+
+/// Creates an unlocked `Spinlock` for data that is asserted to have no interior mutability.
+///
+/// This behaves exactly like [`Spinlock::new`](lock_api::Mutex::new) and [`const_spinlock`],
+/// but only accepts types implementing [`NoInteriorMutability`].
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{checked_spinlock, Spinlock};
+///
+/// static CONFIG: Spinlock<(u32, bool)> = checked_spinlock((1000, true));
+/// ```
+///
+/// ## Rejected Types
+///
+/// Each of the following examples differs from an accepted call only in the type of the data.
+///
+/// A `Cell`:
+///
+/// ```rust,compile_fail
+/// use core::cell::Cell;
+/// use spinning_top::checked_spinlock;
+///
+/// checked_spinlock(Cell::new(0u32));
+/// ```
+///
+/// An atomic:
+///
+/// ```rust,compile_fail
+/// use core::sync::atomic::AtomicU32;
+/// use spinning_top::checked_spinlock;
+///
+/// checked_spinlock(AtomicU32::new(0));
+/// ```
+///
+/// A tuple that contains interior mutability next to accepted types:
+///
+/// ```rust,compile_fail
+/// use core::cell::Cell;
+/// use spinning_top::checked_spinlock;
+///
+/// checked_spinlock((1u32, Cell::new(true)));
+/// ```
+///
+/// A type that is not marked, even if it has no interior mutability:
+///
+/// ```rust,compile_fail
+/// use spinning_top::checked_spinlock;
+///
+/// struct Unmarked(u32);
+///
+/// checked_spinlock(Unmarked(0));
+/// ```
+pub const fn checked_spinlock<T: NoInteriorMutability>(val: T) -> Spinlock<T> {
+    const_spinlock(val)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Plain {
+        id: u32,
+        data: [(u8, Option<bool>); 2],
+    }
+
+    impl NoInteriorMutability for Plain {}
+
+    #[test]
+    fn accepts_controls_of_rejected_types() {
+        // the accepted counterparts of the `compile_fail` examples of `checked_spinlock`
+        checked_spinlock(0u32);
+        checked_spinlock((1u32, true));
+    }
+
+    #[test]
+    fn accepts_marked_types() {
+        let spinlock = checked_spinlock(Plain {
+            id: 1,
+            data: [(0, None); 2],
+        });
+        {
+            let mut guard = spinlock.lock();
+            guard.id += 1;
+            guard.data[1] = (3, Some(true));
+        }
+        let plain = spinlock.into_inner();
+        assert_eq!(plain.id, 2);
+        assert_eq!(plain.data[1], (3, Some(true)));
+    }
+}
@@ -0,0 +1,25 @@
+//! Minimal traits for format-agnostic binary (de)serialization of locked data.
+
+/// Writes a value to a byte buffer in a user-defined binary format.
+///
+/// Used by [`SpinlockExt::encode_into`](crate::SpinlockExt::encode_into), which holds the lock
+/// while the value is encoded. The format is up to the implementation, so it can match any wire
+/// format without requiring an allocator.
+pub trait Encode {
+    /// Encodes `self` into the beginning of `buf` and returns the number of bytes written.
+    ///
+    /// Implementations decide how to handle a buffer that is too small, e.g. by panicking or by
+    /// encoding only a prefix.
+    fn encode(&self, buf: &mut [u8]) -> usize;
+}
+
+/// Reads a value from a byte buffer in a user-defined binary format.
+///
+/// Used by [`SpinlockExt::decode_from`](crate::SpinlockExt::decode_from), which holds the lock
+/// while the value is decoded. Decoding happens in place, so it also works for unsized types
+/// and for types that reuse existing buffers.
+pub trait Decode {
+    /// Overwrites `self` with the value encoded at the beginning of `buf` and returns the number
+    /// of bytes read.
+    fn decode(&mut self, buf: &[u8]) -> usize;
+}
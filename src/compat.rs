@@ -0,0 +1,151 @@
+//! A spinlock with the locking API of [`std::sync::Mutex`], enabled by the `std` feature.
+
+use crate::{Spinlock, SpinlockGuard};
+use core::fmt;
+use std::sync::{LockResult, TryLockError, TryLockResult};
+
+/// A [`Spinlock`] whose methods mirror the signatures of [`std::sync::Mutex`].
+///
+/// The `lock` and `try_lock` methods return [`LockResult`] and [`TryLockResult`] like their
+/// `std` counterparts, so code that uses `?` or `unwrap` on the result of `lock()` can switch
+/// between the two types without changes. The returned [`SpinlockGuard`] dereferences to the
+/// protected data just like a [`std::sync::MutexGuard`].
+///
+/// ## Differences to `std::sync::Mutex`
+///
+/// - Waiting threads spin instead of being parked by the operating system, so the lock should
+///   only be held for short critical sections.
+/// - The lock is never poisoned. A panic while the lock is held releases the lock, and all
+///   methods always return `Ok`. Consequently, [`is_poisoned`](Self::is_poisoned) always
+///   returns `false`.
+///
+/// Requires the `std` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::StdCompatSpinlock;
+/// use std::sync::PoisonError;
+///
+/// fn increment(counter: &StdCompatSpinlock<u32>) -> Result<(), PoisonError<impl Sized + '_>> {
+///     *counter.lock()? += 1;
+///     Ok(())
+/// }
+///
+/// let counter = StdCompatSpinlock::new(0);
+/// increment(&counter).unwrap();
+/// assert_eq!(*counter.lock().unwrap(), 1);
+/// ```
+pub struct StdCompatSpinlock<T: ?Sized> {
+    lock: Spinlock<T>,
+}
+
+impl<T> StdCompatSpinlock<T> {
+    /// Creates a new unlocked `StdCompatSpinlock`.
+    pub const fn new(val: T) -> Self {
+        Self {
+            lock: Spinlock::new(val),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    ///
+    /// Mirrors [`std::sync::Mutex::into_inner`], but never returns an error.
+    pub fn into_inner(self) -> LockResult<T> {
+        Ok(self.lock.into_inner())
+    }
+}
+
+impl<T: ?Sized> StdCompatSpinlock<T> {
+    /// Acquires the lock, spinning until it is available.
+    ///
+    /// Mirrors [`std::sync::Mutex::lock`], but never returns an error.
+    pub fn lock(&self) -> LockResult<SpinlockGuard<'_, T>> {
+        Ok(self.lock.lock())
+    }
+
+    /// Tries to acquire the lock without spinning.
+    ///
+    /// Mirrors [`std::sync::Mutex::try_lock`]. Returns [`TryLockError::WouldBlock`] if the lock
+    /// is currently held.
+    pub fn try_lock(&self) -> TryLockResult<SpinlockGuard<'_, T>> {
+        self.lock.try_lock().ok_or(TryLockError::WouldBlock)
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Mirrors [`std::sync::Mutex::get_mut`], but never returns an error.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        Ok(self.lock.get_mut())
+    }
+
+    /// Always returns `false`, since this lock is never poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        false
+    }
+}
+
+impl<T: Default> Default for StdCompatSpinlock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for StdCompatSpinlock<T> {
+    fn from(val: T) -> Self {
+        Self::new(val)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for StdCompatSpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StdCompatSpinlock")
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{boxed::Box, error::Error, sync::Mutex};
+
+    // instantiates the same code for both `std::sync::Mutex` and `StdCompatSpinlock`
+    macro_rules! against_mutex_api {
+        ($name:ident, $lock:ident) => {
+            fn $name(lock: &$lock<u32>) -> Result<u32, Box<dyn Error + '_>> {
+                *lock.lock()? += 1;
+                match lock.try_lock() {
+                    Ok(_) => {}
+                    Err(TryLockError::WouldBlock) => unreachable!(),
+                    Err(TryLockError::Poisoned(err)) => return Err(err.into()),
+                }
+                let guard = lock.lock()?;
+                assert!(matches!(lock.try_lock(), Err(TryLockError::WouldBlock)));
+                Ok(*guard)
+            }
+        };
+    }
+
+    against_mutex_api!(with_mutex, Mutex);
+    against_mutex_api!(with_spinlock, StdCompatSpinlock);
+
+    #[test]
+    fn std_mutex_compatible() {
+        assert_eq!(with_mutex(&Mutex::new(1)).unwrap(), 2);
+        assert_eq!(with_spinlock(&StdCompatSpinlock::new(1)).unwrap(), 2);
+    }
+
+    #[test]
+    fn never_poisoned() {
+        let mut lock = StdCompatSpinlock::new(1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = lock.lock().unwrap();
+            panic!("oops");
+        }));
+        assert!(result.is_err());
+        assert!(!lock.is_poisoned());
+        *lock.get_mut().unwrap() += 1;
+        assert_eq!(lock.into_inner().unwrap(), 2);
+    }
+}
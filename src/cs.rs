@@ -0,0 +1,138 @@
+//! A spinlock that also holds a critical section, enabled by the `critical-section` feature.
+
+use crate::RawSpinlock;
+use core::cell::UnsafeCell;
+use critical_section::RestoreState;
+use lock_api::{GuardNoSend, RawMutex};
+
+/// A [`RawSpinlock`] that enters a critical section of the [`critical_section`] crate while it
+/// is held.
+///
+/// The critical section is acquired before the spinlock and released after it, so interrupts
+/// (or whatever the registered `critical-section` implementation masks) can't preempt the lock
+/// holder. This prevents deadlocks when the lock is shared with interrupt handlers, and it is
+/// portable across all targets with a `critical-section` implementation instead of relying on
+/// architecture-specific code.
+///
+/// Critical sections must be released in the reverse order of acquisition on the same core, so
+/// the guards of this lock can't be sent to other threads, and nested guards must be dropped in
+/// reverse order of acquisition.
+///
+/// Requires the `critical-section` feature.
+///
+/// [`critical_section`]: https://docs.rs/critical-section/
+pub struct RawCsSpinlock {
+    inner: RawSpinlock,
+    /// The state to restore when releasing the critical section, only accessed while the lock
+    /// is held.
+    restore_state: UnsafeCell<RestoreState>,
+}
+
+// SAFETY: `restore_state` is only accessed by the lock holder
+unsafe impl Sync for RawCsSpinlock {}
+
+unsafe impl RawMutex for RawCsSpinlock {
+    const INIT: Self = Self {
+        inner: RawSpinlock::INIT,
+        restore_state: UnsafeCell::new(RestoreState::invalid()),
+    };
+
+    // The critical section must be released on the core that acquired it
+    type GuardMarker = GuardNoSend;
+
+    fn lock(&self) {
+        // SAFETY: the critical section is released in `unlock`
+        let restore_state = unsafe { critical_section::acquire() };
+        self.inner.lock();
+        // SAFETY: the lock is held
+        unsafe { *self.restore_state.get() = restore_state };
+    }
+
+    fn try_lock(&self) -> bool {
+        // SAFETY: the critical section is released below or in `unlock`
+        let restore_state = unsafe { critical_section::acquire() };
+        if self.inner.try_lock() {
+            // SAFETY: the lock is held
+            unsafe { *self.restore_state.get() = restore_state };
+            true
+        } else {
+            // SAFETY: acquired above and no other critical section was entered since
+            unsafe { critical_section::release(restore_state) };
+            false
+        }
+    }
+
+    unsafe fn unlock(&self) {
+        // read before unlocking, since the next lock holder overwrites it
+        let restore_state = *self.restore_state.get();
+        self.inner.unlock();
+        critical_section::release(restore_state);
+    }
+
+    fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+}
+
+impl core::fmt::Debug for RawCsSpinlock {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RawCsSpinlock")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// A spinlock that holds a critical section of the `critical-section` crate while it is locked.
+///
+/// See [`RawCsSpinlock`] for details. Requires the `critical-section` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::CsSpinlock;
+///
+/// static EVENTS: CsSpinlock<u32> = CsSpinlock::new(0);
+///
+/// // e.g. in an interrupt handler and in the main loop
+/// *EVENTS.lock() += 1;
+/// assert_eq!(*EVENTS.lock(), 1);
+/// ```
+pub type CsSpinlock<T> = lock_api::Mutex<RawCsSpinlock, T>;
+
+/// A RAII guard returned by [`CsSpinlock::lock`](lock_api::Mutex::lock).
+pub type CsSpinlockGuard<'a, T> = lock_api::MutexGuard<'a, RawCsSpinlock, T>;
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use std::{sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn critical_section_held_by_guard() {
+        let spinlock = CsSpinlock::new(0);
+        let guard = spinlock.lock();
+
+        // the `std` implementation of `critical-section` is a global mutex, so other threads
+        // can only enter a critical section after the guard was dropped
+        let entered = Arc::new(AtomicBool::new(false));
+        let other = {
+            let entered = entered.clone();
+            thread::spawn(move || critical_section::with(|_| entered.store(true, Ordering::SeqCst)))
+        };
+        thread::sleep(Duration::from_millis(10));
+        assert!(!entered.load(Ordering::SeqCst));
+
+        // a failed attempt keeps the critical section of the guard
+        assert!(spinlock.try_lock().is_none());
+        thread::sleep(Duration::from_millis(10));
+        assert!(!entered.load(Ordering::SeqCst));
+
+        drop(guard);
+        other.join().unwrap();
+        assert!(entered.load(Ordering::SeqCst));
+        assert!(!spinlock.is_locked());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::SpinlockGuard;
+use alloc::collections::VecDeque;
+use core::{fmt, iter::FusedIterator};
+
+/// An iterator that removes the elements of a locked [`VecDeque`] from the front.
+///
+/// Created by [`VecDequeSpinlockExt::drain_all`](crate::VecDequeSpinlockExt::drain_all). The
+/// lock is held until the iterator is dropped. If it is dropped before all elements were
+/// yielded, e.g. because processing an element failed, the remaining elements stay in the
+/// queue.
+///
+/// Requires the `alloc` feature.
+pub struct DrainGuard<'a, T> {
+    guard: SpinlockGuard<'a, VecDeque<T>>,
+}
+
+impl<'a, T> DrainGuard<'a, T> {
+    pub(crate) fn new(guard: SpinlockGuard<'a, VecDeque<T>>) -> Self {
+        Self { guard }
+    }
+}
+
+impl<T> Iterator for DrainGuard<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.guard.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.guard.len(), Some(self.guard.len()))
+    }
+}
+
+impl<T> ExactSizeIterator for DrainGuard<'_, T> {}
+
+impl<T> FusedIterator for DrainGuard<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for DrainGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DrainGuard").field(&*self.guard).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use crate::{Spinlock, VecDequeSpinlockExt};
+    use alloc::collections::VecDeque;
+    use std::vec::Vec;
+
+    #[test]
+    fn drain_populated() {
+        let queue = Spinlock::new(VecDeque::from([1, 2, 3]));
+        let drain = queue.drain_all();
+        assert!(queue.is_locked());
+        assert_eq!(drain.len(), 3);
+        assert_eq!(drain.collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(!queue.is_locked());
+        assert!(queue.lock().is_empty());
+    }
+
+    #[test]
+    fn dropped_early() {
+        let queue = Spinlock::new(VecDeque::from([1, 2, 3]));
+        let mut drain = queue.drain_all();
+        assert_eq!(drain.next(), Some(1));
+        drop(drain);
+        assert!(!queue.is_locked());
+        assert_eq!(*queue.lock(), [2, 3]);
+    }
+}
@@ -0,0 +1,142 @@
+//! An experimental spinlock with hardware lock elision on x86.
+
+use crate::{relax::relax, spinlock::DefaultGuardMarker};
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicU8, Ordering},
+};
+use lock_api::RawMutex;
+
+/// A raw spinlock that uses Hardware Lock Elision (HLE) on supported x86 CPUs.
+///
+/// The lock is acquired with an `XACQUIRE`-prefixed `lock cmpxchg` and released with an
+/// `XRELEASE`-prefixed store. On CPUs with Intel TSX, this allows the critical section to run
+/// as a hardware transaction without actually writing the lock word, so non-conflicting critical
+/// sections on different cores can run in parallel. If the transaction aborts (e.g. because of a
+/// conflicting memory access), the CPU transparently re-executes the critical section with the
+/// lock acquired normally.
+///
+/// CPUs without HLE support ignore the prefixes, so the lock behaves like a plain
+/// [`RawSpinlock`](crate::RawSpinlock) there. Note that many recent CPUs have HLE disabled by
+/// microcode updates.
+///
+/// Requires the `tsx` feature and is only available on `x86` and `x86_64`.
+#[derive(Debug)]
+pub struct RawElidedSpinlock {
+    /// Whether the spinlock is locked (`1`) or not (`0`).
+    locked: AtomicU8,
+}
+
+unsafe impl RawMutex for RawElidedSpinlock {
+    const INIT: RawElidedSpinlock = RawElidedSpinlock {
+        locked: AtomicU8::new(0),
+    };
+
+    // A spinlock guard can be sent to another thread and unlocked there, unless the
+    // `no-send-guards` feature is enabled
+    type GuardMarker = DefaultGuardMarker;
+
+    fn lock(&self) {
+        while !self.try_lock() {
+            // Spin on a plain load to avoid aborting the transactions of other cores
+            while self.is_locked() {
+                relax();
+            }
+        }
+    }
+
+    fn try_lock(&self) -> bool {
+        let previous: u8;
+        // SAFETY: `lock cmpxchg` atomically compares the lock byte with `al` (zero) and stores
+        // one on success. It also acts as a full memory barrier, which is at least as strong as
+        // the `Acquire` ordering required here. The `xacquire` prefix is ignored on CPUs without
+        // HLE support.
+        unsafe {
+            asm!(
+                "xacquire lock cmpxchg byte ptr [{lock}], {new}",
+                lock = in(reg) self.locked.as_ptr(),
+                new = in(reg_byte) 1u8,
+                inout("al") 0u8 => previous,
+                options(nostack),
+            );
+        }
+        previous == 0
+    }
+
+    unsafe fn unlock(&self) {
+        // SAFETY: stores on x86 have release semantics. The store restores the lock byte to the
+        // value it had before `xacquire`, which is required for committing the transaction.
+        asm!(
+            "xrelease mov byte ptr [{lock}], 0",
+            lock = in(reg) self.locked.as_ptr(),
+            options(nostack),
+        );
+    }
+
+    fn is_locked(&self) -> bool {
+        // Relaxed is sufficient because this operation does not provide synchronization, only atomicity.
+        self.locked.load(Ordering::Relaxed) != 0
+    }
+}
+
+/// A mutual exclusion type based on [`RawElidedSpinlock`].
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::ElidedSpinlock;
+///
+/// let spinlock = ElidedSpinlock::new(0);
+/// *spinlock.lock() += 1;
+/// assert_eq!(*spinlock.lock(), 1);
+/// ```
+pub type ElidedSpinlock<T> = lock_api::Mutex<RawElidedSpinlock, T>;
+
+/// A RAII guard returned by [`ElidedSpinlock::lock`](lock_api::Mutex::lock).
+pub type ElidedSpinlockGuard<'a, T> = lock_api::MutexGuard<'a, RawElidedSpinlock, T>;
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread, vec::Vec};
+
+    #[test]
+    fn try_lock() {
+        let lock = RawElidedSpinlock::INIT;
+        assert!(lock.try_lock());
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock());
+        unsafe { lock.unlock() };
+        assert!(!lock.is_locked());
+        assert!(lock.try_lock());
+    }
+
+    #[test]
+    fn mutual_exclusion() {
+        const THREADS: usize = 4;
+        const INCREMENTS: usize = 1000;
+
+        let spinlock = Arc::new(ElidedSpinlock::new(0));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let spinlock = spinlock.clone();
+                thread::spawn(move || {
+                    for _ in 0..INCREMENTS {
+                        let mut guard = spinlock.lock();
+                        // a non-atomic read-modify-write that would lose updates without
+                        // mutual exclusion
+                        let value = *guard;
+                        *guard = value + 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(*spinlock.lock(), THREADS * INCREMENTS);
+    }
+}
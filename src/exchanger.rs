@@ -0,0 +1,126 @@
+use crate::{relax::relax, Spinlock};
+use core::{fmt, mem};
+
+/// A rendezvous point where two threads swap values.
+///
+/// Each thread calls [`exchange`](Self::exchange) with a value and spins until a partner thread
+/// arrives, then both threads return the value of the other. The exchanger can be reused for any
+/// number of rounds. If more than two threads call `exchange` concurrently, they are paired up
+/// in the order in which they acquire the internal [`RawSpinlock`](crate::RawSpinlock).
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::SpinExchanger;
+/// use std::{sync::Arc, thread};
+///
+/// let exchanger = Arc::new(SpinExchanger::new());
+/// let partner = {
+///     let exchanger = exchanger.clone();
+///     thread::spawn(move || exchanger.exchange("from partner"))
+/// };
+///
+/// assert_eq!(exchanger.exchange("from main"), "from partner");
+/// assert_eq!(partner.join().unwrap(), "from main");
+/// ```
+pub struct SpinExchanger<T> {
+    slot: Spinlock<Slot<T>>,
+}
+
+enum Slot<T> {
+    /// No exchange is in progress.
+    Empty,
+    /// The first thread of a pair has arrived and is waiting for a partner.
+    Waiting(T),
+    /// The second thread of a pair has arrived and left its value for the first thread.
+    ///
+    /// No new exchange can start until the first thread has taken the value and reset the slot
+    /// to `Empty`.
+    Done(T),
+}
+
+impl<T> SpinExchanger<T> {
+    /// Creates a new exchanger.
+    pub const fn new() -> Self {
+        Self {
+            slot: Spinlock::new(Slot::Empty),
+        }
+    }
+
+    /// Offers `value` to a partner thread and returns the partner's value.
+    ///
+    /// Spins until another thread calls `exchange` on the same exchanger.
+    pub fn exchange(&self, value: T) -> T {
+        // wait until we are either the first or the second thread of a pair
+        loop {
+            let mut slot = self.slot.lock();
+            match mem::replace(&mut *slot, Slot::Empty) {
+                Slot::Empty => {
+                    *slot = Slot::Waiting(value);
+                    break;
+                }
+                Slot::Waiting(other) => {
+                    *slot = Slot::Done(value);
+                    return other;
+                }
+                done @ Slot::Done(_) => {
+                    // the previous pair has not finished yet
+                    *slot = done;
+                }
+            }
+            drop(slot);
+            relax();
+        }
+
+        // we are the first thread, so wait for the partner to leave its value
+        loop {
+            let mut slot = self.slot.lock();
+            match mem::replace(&mut *slot, Slot::Empty) {
+                Slot::Done(other) => return other,
+                waiting => *slot = waiting,
+            }
+            drop(slot);
+            relax();
+        }
+    }
+}
+
+impl<T> Default for SpinExchanger<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for SpinExchanger<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpinExchanger").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn exchange_rounds() {
+        const ROUNDS: usize = 100;
+
+        let exchanger = Arc::new(SpinExchanger::new());
+        let partner = {
+            let exchanger = exchanger.clone();
+            thread::spawn(move || {
+                for round in 0..ROUNDS {
+                    assert_eq!(exchanger.exchange((1, round)), (0, round));
+                }
+            })
+        };
+
+        for round in 0..ROUNDS {
+            assert_eq!(exchanger.exchange((0, round)), (1, round));
+        }
+        partner.join().unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+//! A common interface for the exclusive mode of the locks of this crate.
+
+use core::ops::DerefMut;
+use lock_api::{Mutex, MutexGuard, RawMutex, RawRwLock, RwLock, RwLockWriteGuard};
+
+/// A lock that can be acquired exclusively, implemented for every [`lock_api::Mutex`] and
+/// [`lock_api::RwLock`], including [`Spinlock`](crate::Spinlock).
+///
+/// This allows code to be generic over "some exclusive lock", e.g. to accept both a
+/// [`Spinlock`](crate::Spinlock) and a [`BoundedRwSpinlock`](crate::BoundedRwSpinlock). Read-write
+/// locks are acquired through their write lock. Since the implementations are based on
+/// `lock_api`, they also cover locks of other crates that use it.
+///
+/// Lock types of this crate that wrap a lock instead of being a `lock_api` alias, e.g.
+/// [`PrioritySpinlock`](crate::PrioritySpinlock), `GenerationSpinlock`, or `VersionedSpinlock`,
+/// don't implement this trait.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{ExclusiveLock, ScalableRwSpinlock, Spinlock};
+///
+/// fn increment<L: ExclusiveLock<u32>>(lock: &L) {
+///     *lock.exclusive() += 1;
+/// }
+///
+/// let spinlock = Spinlock::new(0);
+/// let rw_spinlock = ScalableRwSpinlock::new(0);
+/// increment(&spinlock);
+/// increment(&rw_spinlock);
+/// assert_eq!(*spinlock.lock() + *rw_spinlock.read(), 2);
+/// ```
+pub trait ExclusiveLock<T: ?Sized> {
+    /// The guard that gives exclusive access to the data and releases the lock when dropped.
+    type Guard<'a>: DerefMut<Target = T>
+    where
+        Self: 'a;
+
+    /// Acquires the lock exclusively, spinning until it is available.
+    fn exclusive(&self) -> Self::Guard<'_>;
+
+    /// Tries to acquire the lock exclusively without spinning.
+    fn try_exclusive(&self) -> Option<Self::Guard<'_>>;
+}
+
+impl<R: RawMutex, T: ?Sized> ExclusiveLock<T> for Mutex<R, T> {
+    type Guard<'a>
+        = MutexGuard<'a, R, T>
+    where
+        Self: 'a;
+
+    fn exclusive(&self) -> Self::Guard<'_> {
+        self.lock()
+    }
+
+    fn try_exclusive(&self) -> Option<Self::Guard<'_>> {
+        self.try_lock()
+    }
+}
+
+impl<R: RawRwLock, T: ?Sized> ExclusiveLock<T> for RwLock<R, T> {
+    type Guard<'a>
+        = RwLockWriteGuard<'a, R, T>
+    where
+        Self: 'a;
+
+    fn exclusive(&self) -> Self::Guard<'_> {
+        self.write()
+    }
+
+    fn try_exclusive(&self) -> Option<Self::Guard<'_>> {
+        self.try_write()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundedRwSpinlock, ScalableRwSpinlock, Spinlock};
+
+    fn use_it<L: ExclusiveLock<i32>>(lock: &L) -> i32 {
+        let mut guard = lock.exclusive();
+        assert!(lock.try_exclusive().is_none());
+        *guard += 1;
+        *guard
+    }
+
+    #[test]
+    fn generic_over_lock_types() {
+        let spinlock = Spinlock::new(1);
+        assert_eq!(use_it(&spinlock), 2);
+        assert!(!spinlock.is_locked());
+
+        let rw_spinlock = ScalableRwSpinlock::new(10);
+        assert_eq!(use_it(&rw_spinlock), 11);
+        assert!(!rw_spinlock.is_locked());
+
+        let bounded = BoundedRwSpinlock::<_, 2>::new(20);
+        let reader = bounded.read();
+        assert!(bounded.try_exclusive().is_none());
+        drop(reader);
+        assert_eq!(use_it(&bounded), 21);
+    }
+}
@@ -0,0 +1,2759 @@
+#[cfg(feature = "contention-hint")]
+use crate::ContentionLevel;
+#[cfg(feature = "alloc")]
+use crate::DrainGuard;
+#[cfg(all(feature = "pin-core", target_os = "linux"))]
+use crate::PinnedSpinlockGuard;
+#[cfg(feature = "timing")]
+use crate::TimedSpinlockGuard;
+use crate::{
+    map_split, relax::relax, AccountedGuard, Decode, Encode, GuardedSpinlockGuard,
+    MappedSpinlockGuard, RawSpinlock, Spinlock, SpinlockGuard, SplitSpinlockGuard,
+};
+#[cfg(feature = "alloc")]
+use alloc::{collections::VecDeque, vec::Vec};
+#[cfg(feature = "track-caller")]
+use core::panic::Location;
+use core::{
+    fmt, mem,
+    ops::ControlFlow,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::Poll,
+};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// Additional methods for [`Spinlock`].
+///
+/// Since [`Spinlock`] is a type alias for [`lock_api::Mutex`], it's not possible to add inherent
+/// methods to it. Instead, this trait provides convenience methods on top of the `lock_api`
+/// interface. Import it to use them:
+///
+/// ```rust
+/// use spinning_top::{Spinlock, SpinlockExt};
+/// ```
+///
+/// ## Panics in Closures
+///
+/// If a closure passed to one of these methods panics, the guard is dropped while unwinding,
+/// so the lock is released. Spinlocks are not poisoned, so the data may be left in the state
+/// the closure was in when it panicked.
+pub trait SpinlockExt<T: ?Sized> {
+    /// Tries to acquire the lock, spinning for at most `nanos` nanoseconds.
+    ///
+    /// Returns `None` if the lock could not be acquired within the given budget. Reading the
+    /// clock is much more expensive than a spin iteration, so the clock is only checked every
+    /// 64 attempts. Thus, the actual time spent can exceed the budget by the duration of these
+    /// attempts, and at least one round of attempts is performed even for a zero budget.
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    fn try_lock_nanos(&self, nanos: u64) -> Option<SpinlockGuard<'_, T>>;
+
+    /// Runs `f_locked` on the data if the lock is free, otherwise runs `f_busy`.
+    ///
+    /// This method never blocks: it performs a single `try_lock` attempt. If the lock is
+    /// acquired, the guard is dropped before this method returns.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(Vec::new());
+    /// let pushed = spinlock.lock_or_else(|v| { v.push(1); true }, || false);
+    /// assert!(pushed);
+    ///
+    /// let _guard = spinlock.lock();
+    /// let pushed = spinlock.lock_or_else(|v| { v.push(2); true }, || false);
+    /// assert!(!pushed);
+    /// ```
+    fn lock_or_else<R>(&self, f_locked: impl FnOnce(&mut T) -> R, f_busy: impl FnOnce() -> R) -> R;
+
+    /// Locks the spinlock and runs `f` on the data, returning its result.
+    ///
+    /// The lock is released before this method returns.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(vec![1, 2]);
+    /// let len = spinlock.update(|v| {
+    ///     v.push(3);
+    ///     v.len()
+    /// });
+    /// assert_eq!(len, 3);
+    /// ```
+    fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R;
+
+    /// Locks the spinlock, replaces the data with the value returned by `f`, and returns the
+    /// previous value.
+    ///
+    /// The lock is released before this method returns.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(1);
+    /// assert_eq!(spinlock.fetch_update(|v| v * 10), 1);
+    /// assert_eq!(*spinlock.lock(), 10);
+    /// ```
+    fn fetch_update(&self, f: impl FnOnce(&T) -> T) -> T
+    where
+        T: Sized;
+
+    /// Locks the spinlock and runs the fallible update `f` on the data, returning its result.
+    ///
+    /// The lock is released before this method returns, both on `Ok` and on `Err`. This method
+    /// doesn't undo any changes: if `f` returns `Err`, it is expected to have left the data
+    /// unchanged, or the caller has to accept the partial modification. Use
+    /// [`try_update_rollback`](Self::try_update_rollback) to restore the previous value on errors
+    /// instead.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let balance = Spinlock::new(10u32);
+    /// let withdraw = |amount| {
+    ///     balance.try_update(|b| {
+    ///         *b = b.checked_sub(amount).ok_or("insufficient funds")?;
+    ///         Ok(*b)
+    ///     })
+    /// };
+    /// assert_eq!(withdraw(3), Ok(7));
+    /// assert_eq!(withdraw(8), Err("insufficient funds"));
+    /// assert_eq!(*balance.lock(), 7);
+    /// ```
+    fn try_update<R, E>(&self, f: impl FnOnce(&mut T) -> Result<R, E>) -> Result<R, E>;
+
+    /// Like [`try_update`](Self::try_update), but restores the previous value if `f` returns
+    /// `Err`.
+    ///
+    /// The data is cloned before `f` is run, so every call pays for a clone, also on success.
+    /// The previous value is written back before the lock is released, so other threads never
+    /// observe the partial modification. If `f` panics, the data is not restored.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(vec![1, 2]);
+    /// let result: Result<(), &str> = spinlock.try_update_rollback(|v| {
+    ///     v.push(3);
+    ///     Err("validation failed")
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(*spinlock.lock(), [1, 2]);
+    /// ```
+    fn try_update_rollback<R, E>(&self, f: impl FnOnce(&mut T) -> Result<R, E>) -> Result<R, E>
+    where
+        T: Clone;
+
+    /// Locks the spinlock and runs `body` on the data until it returns [`ControlFlow::Break`].
+    ///
+    /// If `body` returns [`ControlFlow::Continue`], e.g. because a hardware register indicates
+    /// that the operation should be tried again later, the lock is released, the thread spins
+    /// briefly, and then the lock is acquired again for the next attempt. Releasing the lock
+    /// between attempts allows other threads to make progress, including the thread that will
+    /// clear the transient condition. There is no limit on the number of attempts.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::ops::ControlFlow;
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let fifo = Spinlock::new(vec![None, Some(42)]);
+    /// let value = fifo.lock_retry(|fifo| match fifo.pop() {
+    ///     Some(Some(value)) => ControlFlow::Break(value),
+    ///     _ => ControlFlow::Continue(()),
+    /// });
+    /// assert_eq!(value, 42);
+    /// ```
+    fn lock_retry<R>(&self, body: impl FnMut(&mut T) -> ControlFlow<R>) -> R;
+
+    /// Locks the spinlock and returns a guard that measures how long the lock is held.
+    ///
+    /// The hold duration is reported to the function registered through
+    /// [`set_hold_time_sink`](crate::set_hold_time_sink) when the guard is dropped.
+    ///
+    /// Requires the `timing` feature.
+    #[cfg(feature = "timing")]
+    fn lock_timed(&self) -> TimedSpinlockGuard<'_, T>;
+
+    /// Locks the spinlock and returns a guard that panics if the lock is held for longer than
+    /// `max`.
+    ///
+    /// This helps to catch accidentally long critical sections during development. The check is
+    /// performed when the guard is dropped and only if debug assertions are enabled. No check
+    /// happens if the guard is leaked, e.g. through [`core::mem::forget`]. Like for
+    /// [`lock_timed`](Self::lock_timed), the hold duration is also reported to the function
+    /// registered through [`set_hold_time_sink`](crate::set_hold_time_sink).
+    ///
+    /// Requires the `timing` feature.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    /// use std::time::Duration;
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// *spinlock.lock_max_hold(Duration::from_secs(1)) += 1;
+    /// ```
+    #[cfg(feature = "timing")]
+    fn lock_max_hold(&self, max: Duration) -> TimedSpinlockGuard<'_, T>;
+
+    /// Pins the current thread to the core it is running on and locks the spinlock.
+    ///
+    /// The thread stays on its core while the returned guard is held, and its previous affinity
+    /// mask is restored when the guard is dropped. On NUMA systems, this prevents the lock
+    /// holder from migrating to a remote node in the middle of a short critical section, which
+    /// would make the protected data change nodes and slow down the next acquirer.
+    ///
+    /// Pinning costs two system calls per acquisition, so it only pays off for contended locks
+    /// whose data is sensitive to remote memory access. If pinning fails, e.g. because of a
+    /// seccomp filter, the lock is acquired anyway; use
+    /// [`PinnedSpinlockGuard::is_pinned`](crate::PinnedSpinlockGuard::is_pinned) to check.
+    ///
+    /// ## Platform Support
+    ///
+    /// Requires the `pin-core` feature and is only available on Linux, where it is based on
+    /// `sched_setaffinity`. Only the first 1024 CPUs are supported.
+    #[cfg(all(feature = "pin-core", target_os = "linux"))]
+    fn lock_pinned_core(&self) -> PinnedSpinlockGuard<'_, T>;
+
+    /// Locks the spinlock and accounts the returned guard in `counter`.
+    ///
+    /// The counter is incremented when the lock is acquired and decremented when the returned
+    /// guard is dropped. See [`AccountedGuard`] for details.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::sync::atomic::{AtomicUsize, Ordering};
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// static LOCKS_HELD: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let guard = spinlock.lock_accounted(&LOCKS_HELD);
+    /// assert_eq!(LOCKS_HELD.load(Ordering::Relaxed), 1);
+    /// drop(guard);
+    /// assert_eq!(LOCKS_HELD.load(Ordering::Relaxed), 0);
+    /// ```
+    fn lock_accounted<'a>(&'a self, counter: &'a AtomicUsize) -> AccountedGuard<'a, T>;
+
+    /// Locks the spinlock and returns a guard that calls `on_release` after releasing the lock.
+    ///
+    /// `on_release` runs when the guard is dropped, also while unwinding from a panic in the
+    /// critical section. The lock is released before `on_release` is called, so it may lock the
+    /// spinlock again. See [`GuardedSpinlockGuard`] for details.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let buffer = Spinlock::new(Vec::new());
+    /// let flushed = Spinlock::new(Vec::new());
+    /// {
+    ///     let mut guard = buffer.lock_guarded(|| flushed.lock().append(&mut buffer.lock()));
+    ///     guard.push(1);
+    /// }
+    /// assert!(buffer.lock().is_empty());
+    /// assert_eq!(*flushed.lock(), [1]);
+    /// ```
+    fn lock_guarded<F: FnOnce()>(&self, on_release: F) -> GuardedSpinlockGuard<'_, T, F>;
+
+    /// Locks the spinlock, replaces the data with `value`, and returns the previous value.
+    ///
+    /// This is useful for object pools, e.g. to check out a value by swapping in a placeholder.
+    /// The lock is released before this method returns.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let slot = Spinlock::new(Some(Vec::<u8>::with_capacity(4096)));
+    /// let buffer = slot.swap(None).unwrap();
+    /// assert!(buffer.capacity() >= 4096);
+    /// assert_eq!(slot.swap(Some(buffer)), None);
+    /// ```
+    fn swap(&self, value: T) -> T
+    where
+        T: Sized;
+
+    /// Locks the spinlock and replaces the data with `value`, dropping the previous value.
+    ///
+    /// This is [`swap`](Self::swap) for callers that don't need the previous value, e.g. when
+    /// resetting pooled objects for reuse. The previous value is dropped after the lock was
+    /// released, so an expensive `Drop` implementation doesn't extend the critical section and
+    /// may even lock the spinlock again.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let connection = Spinlock::new(String::from("stale state"));
+    /// connection.reset(String::new());
+    /// assert!(connection.lock().is_empty());
+    /// ```
+    fn reset(&self, value: T)
+    where
+        T: Sized;
+
+    /// Returns a consistent point-in-time copy of the protected value.
+    ///
+    /// The lock is only held while the value is cloned, so the critical section is as short as
+    /// the clone allows. Since the clone happens under the lock, an expensive `Clone`
+    /// implementation, e.g. of a large collection, delays all other users of the lock. The
+    /// returned copy is independent of later changes to the protected value.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let config = Spinlock::new(vec!["a"]);
+    /// let snapshot = config.snapshot();
+    /// config.lock().push("b");
+    /// assert_eq!(snapshot, ["a"]);
+    /// ```
+    fn snapshot(&self) -> T
+    where
+        T: Clone;
+
+    /// Sets the protected value to `new` if it is equal to `expected`.
+    ///
+    /// Returns `Ok(())` if the value was replaced, or `Err(current)` with the unchanged current
+    /// value otherwise. This provides compare-and-set semantics for `Copy` data that is too large
+    /// for a native atomic. The lock is released before this method returns.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new((1, 2));
+    /// assert_eq!(spinlock.compare_and_set((1, 2), (3, 4)), Ok(()));
+    /// assert_eq!(spinlock.compare_and_set((1, 2), (5, 6)), Err((3, 4)));
+    /// ```
+    fn compare_and_set(&self, expected: T, new: T) -> Result<(), T>
+    where
+        T: Copy + Eq;
+
+    /// Locks the spinlock and encodes the protected value into `buf`.
+    ///
+    /// Returns the number of bytes written by [`Encode::encode`]. The lock is held while the
+    /// value is encoded, so the encoding is never interleaved with a concurrent update. The
+    /// wire format is entirely up to the `Encode` implementation of `T`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Decode, Encode, Spinlock, SpinlockExt};
+    ///
+    /// struct Counter(u32);
+    ///
+    /// impl Encode for Counter {
+    ///     fn encode(&self, buf: &mut [u8]) -> usize {
+    ///         buf[..4].copy_from_slice(&self.0.to_le_bytes());
+    ///         4
+    ///     }
+    /// }
+    ///
+    /// impl Decode for Counter {
+    ///     fn decode(&mut self, buf: &[u8]) -> usize {
+    ///         self.0 = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    ///         4
+    ///     }
+    /// }
+    ///
+    /// let counter = Spinlock::new(Counter(42));
+    /// let mut buf = [0; 8];
+    /// assert_eq!(counter.encode_into(&mut buf), 4);
+    ///
+    /// let restored = Spinlock::new(Counter(0));
+    /// assert_eq!(restored.decode_from(&buf), 4);
+    /// assert_eq!(restored.lock().0, 42);
+    /// ```
+    fn encode_into(&self, buf: &mut [u8]) -> usize
+    where
+        T: Encode;
+
+    /// Locks the spinlock and overwrites the protected value with the one decoded from `buf`.
+    ///
+    /// Returns the number of bytes read by [`Decode::decode`]. The lock is held while the value
+    /// is decoded, so other users of the lock never observe a partially decoded value. See
+    /// [`encode_into`](Self::encode_into) for an example.
+    fn decode_from(&self, buf: &[u8]) -> usize
+    where
+        T: Decode;
+
+    /// Locks the spinlock, spinning until it is available.
+    ///
+    /// This is an alias for [`lock`](lock_api::Mutex::lock) that mirrors the API of
+    /// [`RefCell`](core::cell::RefCell), which eases migrating from a `RefCell` to a `Spinlock`.
+    /// Unlike `RefCell::borrow_mut`, it does not panic if the lock is held, but waits for it.
+    fn borrow_mut(&self) -> SpinlockGuard<'_, T>;
+
+    /// Tries to lock the spinlock without blocking.
+    ///
+    /// This mirrors [`RefCell::try_borrow_mut`](core::cell::RefCell::try_borrow_mut) and returns
+    /// a [`BorrowError`] if the lock is currently held.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(1);
+    /// let guard = spinlock.try_borrow_mut().unwrap();
+    /// assert!(spinlock.try_borrow_mut().is_err());
+    /// ```
+    fn try_borrow_mut(&self) -> Result<SpinlockGuard<'_, T>, BorrowError>;
+
+    /// Returns a numeric identity of this lock.
+    ///
+    /// This is the [`RawSpinlock::id`](crate::RawSpinlock::id) of the underlying raw lock, so it
+    /// is stable for the lifetime of the lock, but only unique among locks that are alive at the
+    /// same time.
+    fn id(&self) -> usize;
+
+    /// Returns the alignment of the storage of the guarded data in bytes.
+    ///
+    /// This is `align_of::<T>()`: the data is stored inline in the spinlock, so its address is
+    /// always a multiple of this value. SIMD code can compare it with the alignment that an
+    /// aligned load requires to choose between aligned and unaligned load paths.
+    /// [`SpinlockGuard::is_aligned_to`](SpinlockGuardExt::is_aligned_to) checks the actual
+    /// address instead.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// #[repr(align(32))]
+    /// struct Lanes([f32; 8]);
+    ///
+    /// let spinlock = Spinlock::new(Lanes([0.0; 8]));
+    /// assert_eq!(spinlock.data_align(), 32);
+    /// ```
+    fn data_align(&self) -> usize
+    where
+        T: Sized;
+
+    /// Locks the spinlock and returns the guard together with a reference to the raw lock.
+    ///
+    /// This is meant for hybrid data structures that hold the lock for structural changes
+    /// but also inspect the raw lock elsewhere, e.g. through
+    /// [`is_locked`](lock_api::RawMutex::is_locked) or [`RawSpinlock::id`](crate::RawSpinlock::id).
+    ///
+    /// The raw lock is owned by the guard while the guard is alive. Calling
+    /// [`unlock`](lock_api::RawMutex::unlock) through the raw reference while the guard exists
+    /// is undefined behavior, since the guard would unlock again and could give access to the
+    /// data concurrently with another lock holder; the `unsafe` contract of `unlock` forbids
+    /// this. Acquiring the raw lock while the guard is alive deadlocks. All other methods of
+    /// the raw lock only read its state and are fine to call at any time. The raw reference
+    /// outlives the guard, so it can also be used after the guard was dropped.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use lock_api::RawMutex;
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let (mut guard, raw) = spinlock.lock_with_raw();
+    /// *guard += 1;
+    /// assert!(raw.is_locked());
+    /// drop(guard);
+    /// assert!(!raw.is_locked());
+    /// ```
+    fn lock_with_raw(&self) -> (SpinlockGuard<'_, T>, &RawSpinlock);
+
+    /// Returns whether the current thread holds the lock, so that locking it would deadlock.
+    ///
+    /// This allows defensive code to take an alternative path instead of hanging. See
+    /// [`RawSpinlock::would_deadlock`](crate::RawSpinlock::would_deadlock) for details.
+    ///
+    /// Requires the `owner-tracking` feature.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// assert!(!spinlock.would_deadlock());
+    /// let _guard = spinlock.lock();
+    /// assert!(spinlock.would_deadlock());
+    /// ```
+    #[cfg(feature = "owner-tracking")]
+    fn would_deadlock(&self) -> bool;
+
+    /// Acquires the lock and records `tag` as the context of the holder until it is released.
+    ///
+    /// The tag is a small user-defined value, e.g. a `Subsystem` enum cast to `u32`, that a
+    /// crash handler can read through [`current_tag`](Self::current_tag) to find out which
+    /// subsystem held the lock. This costs one relaxed store on acquisition and release.
+    ///
+    /// Requires the `lock-tag` feature.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `tag` is `u32::MAX`, which is reserved for "no tag". The tag is checked before
+    /// acquiring the lock, so the lock stays free in this case.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// #[derive(Clone, Copy)]
+    /// enum Subsystem {
+    ///     Network = 1,
+    /// }
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let guard = spinlock.lock_tagged(Subsystem::Network as u32);
+    /// assert_eq!(spinlock.current_tag(), Some(1));
+    /// drop(guard);
+    /// assert_eq!(spinlock.current_tag(), None);
+    /// ```
+    #[cfg(feature = "lock-tag")]
+    fn lock_tagged(&self, tag: u32) -> SpinlockGuard<'_, T>;
+
+    /// Returns the context tag of the current holder of the lock.
+    ///
+    /// See [`RawSpinlock::current_tag`](crate::RawSpinlock::current_tag) for details.
+    ///
+    /// Requires the `lock-tag` feature.
+    #[cfg(feature = "lock-tag")]
+    fn current_tag(&self) -> Option<u32>;
+
+    /// Returns the number of guards of this lock that were acquired and not released yet.
+    ///
+    /// This is `Some(1)` while a guard is alive and stays at `Some(1)` if a guard is leaked,
+    /// e.g. through `mem::forget`. See
+    /// [`RawSpinlock::live_guard_count`](crate::RawSpinlock::live_guard_count) for details.
+    /// Always returns `None` in release builds.
+    ///
+    /// Requires the `guard-count` feature.
+    #[cfg(feature = "guard-count")]
+    fn live_guard_count(&self) -> Option<usize>;
+
+    /// Acquires the lock, asserting that the current thread doesn't hold it already.
+    ///
+    /// This enforces a single-guard invariant for locks that should only ever be held by one
+    /// logical operation at a time. Before acquiring the lock, the method checks through
+    /// [`would_deadlock`](Self::would_deadlock) whether the current thread is the owner of the
+    /// lock and panics instead of spinning forever on the re-entrant acquisition. This also
+    /// catches guards that the current thread leaked, e.g. through `mem::forget`. A guard held
+    /// by another thread is ordinary contention: the method spins until it is released, like
+    /// `lock`.
+    ///
+    /// Requires the `owner-tracking` feature.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the lock was acquired by the current thread and not released since.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// *spinlock.lock_unique() += 1;
+    /// *spinlock.lock_unique() += 1;
+    /// assert_eq!(*spinlock.lock(), 2);
+    /// ```
+    #[cfg(feature = "owner-tracking")]
+    #[track_caller]
+    fn lock_unique(&self) -> SpinlockGuard<'_, T>;
+
+    /// Returns whether an acquisition had to spin since the hint was last cleared.
+    ///
+    /// This is a heuristic that lets adaptive algorithms choose a strategy before locking. See
+    /// [`RawSpinlock::contention_hint`](crate::RawSpinlock::contention_hint) for details.
+    ///
+    /// Requires the `contention-hint` feature.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{ContentionLevel, Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// *spinlock.lock() += 1;
+    /// assert_eq!(spinlock.contention_hint(), ContentionLevel::Uncontended);
+    /// ```
+    #[cfg(feature = "contention-hint")]
+    fn contention_hint(&self) -> ContentionLevel;
+
+    /// Resets the [`contention_hint`](Self::contention_hint) to
+    /// [`Uncontended`](ContentionLevel::Uncontended).
+    ///
+    /// Requires the `contention-hint` feature.
+    #[cfg(feature = "contention-hint")]
+    fn clear_contention_hint(&self);
+
+    /// Locks the spinlock and records the caller location for debugging.
+    ///
+    /// The location can be queried through [`last_locked_at`](Self::last_locked_at), e.g. to
+    /// find out which code path holds a lock during a deadlock. Only acquisitions through this
+    /// method are recorded, not the ones through `lock` or `try_lock`. The location is only
+    /// recorded when debug assertions are enabled, so in release builds this method is
+    /// equivalent to `lock`.
+    ///
+    /// Requires the `track-caller` feature.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let _guard = spinlock.lock_tracked();
+    /// // the location is not recorded in release builds
+    /// if let Some(location) = spinlock.last_locked_at() {
+    ///     assert_eq!(location.line(), line!() - 3);
+    /// }
+    /// ```
+    #[cfg(feature = "track-caller")]
+    #[track_caller]
+    fn lock_tracked(&self) -> SpinlockGuard<'_, T>;
+
+    /// Returns the caller location of the last acquisition through
+    /// [`lock_tracked`](Self::lock_tracked).
+    ///
+    /// This reads the location without locking, so it can be called while the lock is held,
+    /// also by another thread. Returns `None` if the lock was never acquired through
+    /// `lock_tracked` or if debug assertions are disabled.
+    ///
+    /// Requires the `track-caller` feature.
+    #[cfg(feature = "track-caller")]
+    fn last_locked_at(&self) -> Option<&'static Location<'static>>;
+
+    /// Locks the spinlock and reports whether the acquisition was contended.
+    ///
+    /// The returned `bool` is `true` if the first acquisition attempt failed, i.e. the lock was
+    /// held by someone else and we had to spin at least once. This gives adaptive data
+    /// structures a contention signal without any additional atomic operations.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let (mut guard, contended) = spinlock.lock_contended();
+    /// *guard += 1;
+    /// assert!(!contended);
+    /// ```
+    fn lock_contended(&self) -> (SpinlockGuard<'_, T>, bool);
+
+    /// Locks the spinlock, halting the core with `WFE` on AArch64 while the lock is held.
+    ///
+    /// See [`RawSpinlock::lock_wfe`](crate::RawSpinlock::lock_wfe) for details. On other
+    /// architectures, this is equivalent to `lock`.
+    ///
+    /// Requires the `wfe` feature.
+    #[cfg(feature = "wfe")]
+    fn lock_wfe(&self) -> SpinlockGuard<'_, T>;
+
+    /// Locks the spinlock and reports how long the caller waited for it.
+    ///
+    /// The wait is measured from the first failed acquisition attempt, so it is zero if the lock
+    /// was free. The clock is only read if the first attempt fails, so uncontended acquisitions
+    /// don't pay for it. Unlike `lock_timed`, which measures how long the
+    /// lock is held, this measures the acquisition latency.
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    /// use std::time::Duration;
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let (mut guard, waited) = spinlock.lock_waited();
+    /// *guard += 1;
+    /// assert_eq!(waited, Duration::ZERO);
+    /// ```
+    #[cfg(feature = "std")]
+    fn lock_waited(&self) -> (SpinlockGuard<'_, T>, Duration);
+
+    /// Acquires the lock, or gives up as soon as `cancel` is set.
+    ///
+    /// Returns `None` if `cancel` was observed to be `true` before the lock could be acquired.
+    /// This allows a supervisor to abort a blocked acquisition, e.g. during shutdown, without
+    /// a timer. The flag is checked with a `Relaxed` load in every spin iteration, which only
+    /// reads the cache line of the flag and thus doesn't slow down the thread setting it.
+    ///
+    /// The lock is acquired even if `cancel` is set, as long as the lock is free on the first
+    /// attempt.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::sync::atomic::{AtomicBool, Ordering};
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let cancel = AtomicBool::new(false);
+    /// let guard = spinlock.try_lock_cancellable(&cancel).unwrap();
+    ///
+    /// cancel.store(true, Ordering::Relaxed);
+    /// assert!(spinlock.try_lock_cancellable(&cancel).is_none());
+    /// ```
+    fn try_lock_cancellable(&self, cancel: &AtomicBool) -> Option<SpinlockGuard<'_, T>>;
+
+    /// Reads the protected data without locking.
+    ///
+    /// This is an escape hatch for fast paths where the caller knows through external
+    /// synchronization that no other thread accesses the data mutably, e.g. after all writer
+    /// threads were joined.
+    ///
+    /// ## Safety
+    ///
+    /// While `f` runs, no other thread may mutate the data and the current thread must not hold
+    /// a guard of this lock that is used for mutation. Note that holding the lock on another
+    /// thread is allowed, as long as that thread doesn't write to the data.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(vec![1, 2, 3]);
+    /// // SAFETY: the lock is not shared with other threads
+    /// let sum: i32 = unsafe { spinlock.peek(|v| v.iter().sum()) };
+    /// assert_eq!(sum, 6);
+    /// ```
+    unsafe fn peek<R>(&self, f: impl FnOnce(&T) -> R) -> R;
+
+    /// Locks the spinlock only if `precheck` holds for the protected data.
+    ///
+    /// First runs `precheck` on the data without locking, like [`peek`](Self::peek), and returns
+    /// `None` right away if it fails. This avoids touching the lock in the common "nothing to
+    /// do" case. Otherwise, the lock is acquired and `precheck` is evaluated again, since the
+    /// data may have changed in the meantime. The guard is only returned if the second check
+    /// passes as well; otherwise, the lock is released and `None` is returned.
+    ///
+    /// ## Safety
+    ///
+    /// The first evaluation of `precheck` has the same requirements as [`peek`](Self::peek):
+    /// while it runs, no other thread may mutate the data and the current thread must not hold a
+    /// guard of this lock. Mutations through `Sync` interior mutability, e.g. storing to an
+    /// atomic flag through a shared reference to the guard, are allowed.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::sync::atomic::{AtomicBool, Ordering};
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// struct Work {
+    ///     pending: AtomicBool,
+    /// }
+    ///
+    /// let work = Spinlock::new(Work { pending: AtomicBool::new(false) });
+    /// let pending = |work: &Work| work.pending.load(Ordering::Acquire);
+    ///
+    /// // SAFETY: the data is only modified through the atomic flag
+    /// assert!(unsafe { work.lock_if(pending) }.is_none());
+    ///
+    /// work.lock().pending.store(true, Ordering::Release);
+    /// let guard = unsafe { work.lock_if(pending) }.expect("work is pending");
+    /// guard.pending.store(false, Ordering::Release);
+    /// ```
+    unsafe fn lock_if(&self, precheck: impl Fn(&T) -> bool) -> Option<SpinlockGuard<'_, T>>;
+
+    /// Locks the spinlock unless the protected data equals `target`.
+    ///
+    /// This is [`lock_if`](Self::lock_if) with an inequality check: if the data already equals
+    /// `target`, `None` is returned without touching the lock. Otherwise, the lock is acquired
+    /// and the data is compared again, since a concurrent update may have set it to `target` in
+    /// the meantime. In that case, the lock is released and `None` is returned as well. This
+    /// avoids locking in the steady state where the data is already up to date.
+    ///
+    /// ## Safety
+    ///
+    /// The first comparison reads the data without locking, so it has the same requirements as
+    /// [`peek`](Self::peek): while it runs, no other thread may mutate the data and the current
+    /// thread must not hold a guard of this lock.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let cached = Spinlock::new(1);
+    /// // SAFETY: the lock is not shared with other threads
+    /// assert!(unsafe { cached.lock_unless_eq(&1) }.is_none());
+    ///
+    /// let mut guard = unsafe { cached.lock_unless_eq(&2) }.expect("the value differs");
+    /// *guard = 2;
+    /// ```
+    unsafe fn lock_unless_eq(&self, target: &T) -> Option<SpinlockGuard<'_, T>>
+    where
+        T: PartialEq;
+
+    /// Waits until no other thread holds the lock.
+    ///
+    /// Acquires the lock and immediately releases it again. After this method returns, every
+    /// critical section that was in progress when it was called has finished. Another thread
+    /// may acquire the lock again right away, unless no other references to the lock exist.
+    ///
+    /// This is useful for teardown, e.g. in a `Drop` implementation that must wait for
+    /// in-flight critical sections of other threads before freeing a resource that is used
+    /// inside them.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// spinlock.drain();
+    /// assert!(!spinlock.is_locked());
+    /// ```
+    fn drain(&self);
+
+    /// Spins until the locked data satisfies `pred`, returning without holding the lock.
+    ///
+    /// Each attempt acquires the lock, calls `pred` on the data, and releases the lock again.
+    /// If `pred` returned `false`, this spins for a moment before the next attempt, so the
+    /// threads that change the data can acquire the lock in the meantime.
+    ///
+    /// Since the lock is released before this method returns, the condition may no longer hold
+    /// by the time the caller acts on it: another thread can change the data right after the
+    /// check. Callers that need the condition to hold while they use the data must lock the
+    /// spinlock and check it again.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    /// use std::{sync::Arc, thread};
+    ///
+    /// let ready = Arc::new(Spinlock::new(false));
+    /// let setter = {
+    ///     let ready = ready.clone();
+    ///     thread::spawn(move || *ready.lock() = true)
+    /// };
+    /// ready.wait_until(|ready| *ready);
+    /// # setter.join().unwrap();
+    /// ```
+    fn wait_until(&self, pred: impl Fn(&T) -> bool);
+
+    /// Polls the spinlock once for a cooperative scheduler.
+    ///
+    /// Returns the guard if the lock was acquired by this call and [`Poll::Pending`] if it is
+    /// held. See [`RawSpinlock::poll_lock`](crate::RawSpinlock::poll_lock) for details.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::task::Poll;
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let guard = spinlock.lock();
+    /// assert!(spinlock.poll_lock().is_pending());
+    /// drop(guard);
+    /// match spinlock.poll_lock() {
+    ///     Poll::Ready(mut guard) => *guard += 1,
+    ///     Poll::Pending => unreachable!(),
+    /// };
+    /// ```
+    fn poll_lock(&self) -> Poll<SpinlockGuard<'_, T>>;
+
+    /// Locks the spinlock and passes ownership of the guard to `f`.
+    ///
+    /// Unlike [`update`](Self::update), which only provides a `&mut T`, this allows `f` to
+    /// transform the guard, e.g. to [`map`](lock_api::MutexGuard::map) it to a field. Since `f`
+    /// must return a value that doesn't borrow from the guard, the guard can't escape `f` and is
+    /// dropped by the time this method returns, unless `f` leaks it through
+    /// [`mem::forget`](core::mem::forget).
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt, SpinlockGuard};
+    ///
+    /// let spinlock = Spinlock::new((0, String::new()));
+    /// let len = spinlock.hold(|guard| {
+    ///     let mut name = SpinlockGuard::map(guard, |(_, name)| name);
+    ///     name.push_str("spin");
+    ///     name.len()
+    /// });
+    /// assert_eq!(len, 4);
+    /// assert!(!spinlock.is_locked());
+    /// ```
+    fn hold<R>(&self, f: impl for<'g> FnOnce(SpinlockGuard<'g, T>) -> R) -> R;
+
+    /// Tries to acquire the lock within a spin budget and an optional deadline.
+    ///
+    /// Gives up and returns `None` when the lock was observed to be held in `max_spins`
+    /// consecutive spin iterations or when the `deadline` has expired, whichever happens first.
+    /// Pass `usize::MAX` as `max_spins` for a deadline-only bound, or `None` as `deadline` for a
+    /// purely deterministic bound that works without a clock.
+    ///
+    /// Reading a clock is usually much more expensive than a spin iteration, so the deadline is
+    /// only checked every 64 spin iterations, starting with the first one.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let guard = spinlock.try_lock_budget(100, None).unwrap();
+    /// assert!(spinlock.try_lock_budget(100, None).is_none());
+    /// ```
+    fn try_lock_budget(
+        &self,
+        max_spins: usize,
+        deadline: Option<&dyn Deadline>,
+    ) -> Option<SpinlockGuard<'_, T>>;
+
+    /// Runs `shared` on the locked data if the lock can be acquired within `budget` spin
+    /// iterations, otherwise runs `local` without the lock.
+    ///
+    /// This is a graceful degradation pattern for data whose exactness can be relaxed for a
+    /// moment, e.g. metrics: under extreme contention, `local` records the update in an
+    /// approximate thread-local value instead of waiting, and a later call reconciles it with the
+    /// shared data. The budget is counted like in [`try_lock_budget`](Self::try_lock_budget).
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::cell::Cell;
+    /// use spinning_top::{Spinlock, SpinlockExt};
+    ///
+    /// thread_local! {
+    ///     static PENDING: Cell<u64> = const { Cell::new(0) };
+    /// }
+    ///
+    /// fn count_request(total: &Spinlock<u64>) {
+    ///     total.lock_or_local(
+    ///         100,
+    ///         || PENDING.with(|pending| pending.set(pending.get() + 1)),
+    ///         |total| *total += 1 + PENDING.with(|pending| pending.replace(0)),
+    ///     );
+    /// }
+    ///
+    /// let total = Spinlock::new(0);
+    /// let guard = total.lock();
+    /// count_request(&total); // contended, counted locally
+    /// drop(guard);
+    /// count_request(&total); // reconciled
+    /// assert_eq!(*total.lock(), 2);
+    /// ```
+    fn lock_or_local<R>(
+        &self,
+        budget: usize,
+        local: impl FnOnce() -> R,
+        shared: impl FnOnce(&mut T) -> R,
+    ) -> R;
+}
+
+/// A point in time after which [`SpinlockExt::try_lock_budget`] gives up.
+///
+/// Implemented for `std::time::Instant` if the `std` feature is enabled. On `no_std` targets,
+/// it can be implemented on top of a hardware timer.
+pub trait Deadline {
+    /// Returns whether the deadline has passed.
+    fn has_expired(&self) -> bool;
+}
+
+#[cfg(feature = "std")]
+impl Deadline for Instant {
+    fn has_expired(&self) -> bool {
+        Instant::now() >= *self
+    }
+}
+
+/// The error returned by [`SpinlockExt::try_borrow_mut`] if the lock is currently held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowError {
+    _private: (),
+}
+
+impl fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("spinlock is already locked")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BorrowError {}
+
+impl<T: ?Sized> SpinlockExt<T> for Spinlock<T> {
+    #[cfg(feature = "std")]
+    fn try_lock_nanos(&self, nanos: u64) -> Option<SpinlockGuard<'_, T>> {
+        const ATTEMPTS_PER_CLOCK_READ: u32 = 64;
+
+        let budget = Duration::from_nanos(nanos);
+        let start = Instant::now();
+        loop {
+            for _ in 0..ATTEMPTS_PER_CLOCK_READ {
+                if let Some(guard) = self.try_lock() {
+                    return Some(guard);
+                }
+                relax();
+            }
+            if start.elapsed() >= budget {
+                return None;
+            }
+        }
+    }
+
+    fn lock_or_else<R>(&self, f_locked: impl FnOnce(&mut T) -> R, f_busy: impl FnOnce() -> R) -> R {
+        match self.try_lock() {
+            Some(mut guard) => f_locked(&mut guard),
+            None => f_busy(),
+        }
+    }
+
+    fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    fn fetch_update(&self, f: impl FnOnce(&T) -> T) -> T
+    where
+        T: Sized,
+    {
+        let mut guard = self.lock();
+        let new = f(&guard);
+        core::mem::replace(&mut *guard, new)
+    }
+
+    fn try_update<R, E>(&self, f: impl FnOnce(&mut T) -> Result<R, E>) -> Result<R, E> {
+        f(&mut self.lock())
+    }
+
+    fn try_update_rollback<R, E>(&self, f: impl FnOnce(&mut T) -> Result<R, E>) -> Result<R, E>
+    where
+        T: Clone,
+    {
+        let mut guard = self.lock();
+        let snapshot = guard.clone();
+        let result = f(&mut guard);
+        if result.is_err() {
+            *guard = snapshot;
+        }
+        result
+    }
+
+    fn lock_retry<R>(&self, mut body: impl FnMut(&mut T) -> ControlFlow<R>) -> R {
+        /// The number of spin loop iterations between two attempts.
+        const RETRY_SPINS: usize = 16;
+
+        loop {
+            if let ControlFlow::Break(result) = body(&mut self.lock()) {
+                return result;
+            }
+            for _ in 0..RETRY_SPINS {
+                relax();
+            }
+        }
+    }
+
+    #[cfg(feature = "timing")]
+    fn lock_timed(&self) -> TimedSpinlockGuard<'_, T> {
+        TimedSpinlockGuard::new(self.lock(), None)
+    }
+
+    #[cfg(feature = "timing")]
+    fn lock_max_hold(&self, max: Duration) -> TimedSpinlockGuard<'_, T> {
+        TimedSpinlockGuard::new(self.lock(), Some(max))
+    }
+
+    #[cfg(all(feature = "pin-core", target_os = "linux"))]
+    fn lock_pinned_core(&self) -> PinnedSpinlockGuard<'_, T> {
+        PinnedSpinlockGuard::new(|| self.lock())
+    }
+
+    fn lock_accounted<'a>(&'a self, counter: &'a AtomicUsize) -> AccountedGuard<'a, T> {
+        AccountedGuard::new(self.lock(), counter)
+    }
+
+    fn lock_guarded<F: FnOnce()>(&self, on_release: F) -> GuardedSpinlockGuard<'_, T, F> {
+        GuardedSpinlockGuard::new(self.lock(), on_release)
+    }
+
+    fn swap(&self, value: T) -> T
+    where
+        T: Sized,
+    {
+        core::mem::replace(&mut *self.lock(), value)
+    }
+
+    fn reset(&self, value: T)
+    where
+        T: Sized,
+    {
+        drop(self.swap(value));
+    }
+
+    fn snapshot(&self) -> T
+    where
+        T: Clone,
+    {
+        self.lock().clone()
+    }
+
+    fn compare_and_set(&self, expected: T, new: T) -> Result<(), T>
+    where
+        T: Copy + Eq,
+    {
+        let mut guard = self.lock();
+        if *guard == expected {
+            *guard = new;
+            Ok(())
+        } else {
+            Err(*guard)
+        }
+    }
+
+    fn encode_into(&self, buf: &mut [u8]) -> usize
+    where
+        T: Encode,
+    {
+        self.lock().encode(buf)
+    }
+
+    fn decode_from(&self, buf: &[u8]) -> usize
+    where
+        T: Decode,
+    {
+        self.lock().decode(buf)
+    }
+
+    fn borrow_mut(&self) -> SpinlockGuard<'_, T> {
+        self.lock()
+    }
+
+    fn try_borrow_mut(&self) -> Result<SpinlockGuard<'_, T>, BorrowError> {
+        self.try_lock().ok_or(BorrowError { _private: () })
+    }
+
+    fn id(&self) -> usize {
+        // SAFETY: the raw lock is only used to read its address
+        unsafe { self.raw() }.id()
+    }
+
+    fn data_align(&self) -> usize
+    where
+        T: Sized,
+    {
+        mem::align_of::<T>()
+    }
+
+    fn lock_with_raw(&self) -> (SpinlockGuard<'_, T>, &RawSpinlock) {
+        // SAFETY: the documentation forbids unlocking through the returned reference while the
+        // guard is alive, which is the same requirement as for `lock_api::RawMutex::unlock`
+        (self.lock(), unsafe { self.raw() })
+    }
+
+    #[cfg(feature = "owner-tracking")]
+    fn would_deadlock(&self) -> bool {
+        // SAFETY: the raw lock is only used for reading the owner
+        unsafe { self.raw() }.would_deadlock()
+    }
+
+    #[cfg(feature = "lock-tag")]
+    fn lock_tagged(&self, tag: u32) -> SpinlockGuard<'_, T> {
+        assert_ne!(
+            tag,
+            u32::MAX,
+            "`u32::MAX` is reserved and can't be used as a lock tag"
+        );
+        let guard = self.lock();
+        // SAFETY: the raw lock is only used for recording the tag
+        unsafe { self.raw() }.set_tag(tag);
+        guard
+    }
+
+    #[cfg(feature = "lock-tag")]
+    fn current_tag(&self) -> Option<u32> {
+        // SAFETY: the raw lock is only used for reading the tag
+        unsafe { self.raw() }.current_tag()
+    }
+
+    #[cfg(feature = "guard-count")]
+    fn live_guard_count(&self) -> Option<usize> {
+        // SAFETY: the raw lock is only used for reading the count
+        unsafe { self.raw() }.live_guard_count()
+    }
+
+    #[cfg(feature = "owner-tracking")]
+    #[track_caller]
+    fn lock_unique(&self) -> SpinlockGuard<'_, T> {
+        assert!(
+            !self.would_deadlock(),
+            "`lock_unique` called while the current thread holds the lock"
+        );
+        self.lock()
+    }
+
+    #[cfg(feature = "contention-hint")]
+    fn contention_hint(&self) -> ContentionLevel {
+        // SAFETY: the raw lock is only used for reading the hint
+        unsafe { self.raw() }.contention_hint()
+    }
+
+    #[cfg(feature = "contention-hint")]
+    fn clear_contention_hint(&self) {
+        // SAFETY: the raw lock is only used for clearing the hint
+        unsafe { self.raw() }.clear_contention_hint()
+    }
+
+    #[cfg(feature = "track-caller")]
+    #[track_caller]
+    fn lock_tracked(&self) -> SpinlockGuard<'_, T> {
+        let guard = self.lock();
+        // SAFETY: the raw lock is only used for recording the location
+        unsafe { self.raw() }.record_location(Location::caller());
+        guard
+    }
+
+    #[cfg(feature = "track-caller")]
+    fn last_locked_at(&self) -> Option<&'static Location<'static>> {
+        // SAFETY: the raw lock is only used for reading the location
+        unsafe { self.raw() }.last_locked_at()
+    }
+
+    #[cfg(feature = "wfe")]
+    fn lock_wfe(&self) -> SpinlockGuard<'_, T> {
+        // SAFETY: the guard is created right after acquiring the lock
+        unsafe {
+            self.raw().lock_wfe();
+            self.make_guard_unchecked()
+        }
+    }
+
+    fn lock_contended(&self) -> (SpinlockGuard<'_, T>, bool) {
+        match self.try_lock() {
+            Some(guard) => (guard, false),
+            None => (self.lock(), true),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn lock_waited(&self) -> (SpinlockGuard<'_, T>, Duration) {
+        match self.try_lock() {
+            Some(guard) => (guard, Duration::ZERO),
+            None => {
+                let start = Instant::now();
+                let guard = self.lock();
+                (guard, start.elapsed())
+            }
+        }
+    }
+
+    unsafe fn peek<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&*self.data_ptr())
+    }
+
+    unsafe fn lock_if(&self, precheck: impl Fn(&T) -> bool) -> Option<SpinlockGuard<'_, T>> {
+        if !self.peek(&precheck) {
+            return None;
+        }
+        let guard = self.lock();
+        if precheck(&guard) {
+            Some(guard)
+        } else {
+            None
+        }
+    }
+
+    unsafe fn lock_unless_eq(&self, target: &T) -> Option<SpinlockGuard<'_, T>>
+    where
+        T: PartialEq,
+    {
+        self.lock_if(|value| value != target)
+    }
+
+    fn drain(&self) {
+        drop(self.lock());
+    }
+
+    fn wait_until(&self, pred: impl Fn(&T) -> bool) {
+        loop {
+            let satisfied = pred(&self.lock());
+            if satisfied {
+                return;
+            }
+            relax();
+        }
+    }
+
+    fn poll_lock(&self) -> Poll<SpinlockGuard<'_, T>> {
+        match self.try_lock() {
+            Some(guard) => Poll::Ready(guard),
+            None => Poll::Pending,
+        }
+    }
+
+    fn hold<R>(&self, f: impl for<'g> FnOnce(SpinlockGuard<'g, T>) -> R) -> R {
+        f(self.lock())
+    }
+
+    fn try_lock_budget(
+        &self,
+        max_spins: usize,
+        deadline: Option<&dyn Deadline>,
+    ) -> Option<SpinlockGuard<'_, T>> {
+        const SPINS_PER_CLOCK_READ: usize = 64;
+
+        let mut spins = 0;
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return Some(guard);
+            }
+            while self.is_locked() {
+                if spins >= max_spins {
+                    return None;
+                }
+                if let Some(deadline) = deadline {
+                    if spins % SPINS_PER_CLOCK_READ == 0 && deadline.has_expired() {
+                        return None;
+                    }
+                }
+                spins += 1;
+                relax();
+            }
+        }
+    }
+
+    fn lock_or_local<R>(
+        &self,
+        budget: usize,
+        local: impl FnOnce() -> R,
+        shared: impl FnOnce(&mut T) -> R,
+    ) -> R {
+        match self.try_lock_budget(budget, None) {
+            Some(mut guard) => shared(&mut guard),
+            None => local(),
+        }
+    }
+
+    fn try_lock_cancellable(&self, cancel: &AtomicBool) -> Option<SpinlockGuard<'_, T>> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return Some(guard);
+            }
+            while self.is_locked() {
+                if cancel.load(Ordering::Relaxed) {
+                    return None;
+                }
+                relax();
+            }
+        }
+    }
+}
+
+/// Additional methods for a [`Spinlock`] protecting an array.
+pub trait ArraySpinlockExt<T, const N: usize> {
+    /// Acquires the lock and returns mutable access to the elements at the indices `i` and `j`.
+    ///
+    /// The lock is acquired once for both elements. The returned guard holds it until it is
+    /// dropped; [`parts_mut`](SplitSpinlockGuard::parts_mut) returns the elements in the order
+    /// of the indices. A pair of independent mapped guards isn't possible because each of them
+    /// would release the lock when dropped.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `i == j` or if one of the indices is out of bounds. The lock is not acquired in
+    /// this case.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{ArraySpinlockExt, Spinlock};
+    ///
+    /// let spinlock = Spinlock::new([10, 20, 30]);
+    /// let mut guard = spinlock.lock_two_indices(2, 0);
+    /// let (last, first) = guard.parts_mut();
+    /// core::mem::swap(last, first);
+    /// drop(guard);
+    /// assert_eq!(*spinlock.lock(), [30, 20, 10]);
+    /// ```
+    fn lock_two_indices(&self, i: usize, j: usize) -> SplitSpinlockGuard<'_, T, T>;
+}
+
+impl<T, const N: usize> ArraySpinlockExt<T, N> for Spinlock<[T; N]> {
+    fn lock_two_indices(&self, i: usize, j: usize) -> SplitSpinlockGuard<'_, T, T> {
+        assert_ne!(i, j, "the indices must be distinct");
+        assert!(
+            i < N && j < N,
+            "index out of bounds: the len is {} but the indices are {} and {}",
+            N,
+            i,
+            j
+        );
+        map_split(self.lock(), |array| {
+            let (low, high) = array.split_at_mut(i.max(j));
+            let (low, high) = (&mut low[i.min(j)], &mut high[0]);
+            if i < j {
+                (low, high)
+            } else {
+                (high, low)
+            }
+        })
+    }
+}
+
+/// Additional methods for a [`Spinlock`] protecting an [`Option`].
+pub trait OptionSpinlockExt<T> {
+    /// Acquires the lock and returns a guard for the contained value if it is `Some`.
+    ///
+    /// If the option is `None`, the lock is released again and `None` is returned. This
+    /// replaces the `if let Some(value) = guard.as_mut()` pattern and avoids holding the lock
+    /// when there is nothing to work on.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{OptionSpinlockExt, Spinlock};
+    ///
+    /// let connection = Spinlock::new(Some(String::from("db")));
+    /// if let Some(mut name) = connection.lock_some() {
+    ///     name.push_str("-primary");
+    /// }
+    /// assert_eq!(connection.lock().as_deref(), Some("db-primary"));
+    ///
+    /// *connection.lock() = None;
+    /// assert!(connection.lock_some().is_none());
+    /// assert!(!connection.is_locked());
+    /// ```
+    fn lock_some(&self) -> Option<MappedSpinlockGuard<'_, T>>;
+
+    /// Takes the value out of the option, leaving `None` in its place.
+    ///
+    /// The lock is only held for the [`Option::take`] and released before this method returns.
+    /// This is the "consume the pending item if present" pattern for one-shot slots: if several
+    /// threads race to take a value, exactly one of them gets it.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{OptionSpinlockExt, Spinlock};
+    ///
+    /// let slot = Spinlock::new(Some("request"));
+    /// assert_eq!(slot.take_some(), Some("request"));
+    /// assert_eq!(slot.take_some(), None);
+    /// ```
+    fn take_some(&self) -> Option<T>;
+}
+
+impl<T> OptionSpinlockExt<T> for Spinlock<Option<T>> {
+    fn lock_some(&self) -> Option<MappedSpinlockGuard<'_, T>> {
+        SpinlockGuard::try_map(self.lock(), Option::as_mut).ok()
+    }
+
+    fn take_some(&self) -> Option<T> {
+        self.lock().take()
+    }
+}
+
+/// Additional methods for a [`Spinlock`] protecting a [`Vec`].
+///
+/// Requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub trait VecSpinlockExt<T> {
+    /// Acquires the lock and returns a guard for the elements of the vector as a slice.
+    ///
+    /// The slice can't change the length of the vector, but it gives direct access to the slice
+    /// methods, e.g. for sorting or swapping elements.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, VecSpinlockExt};
+    ///
+    /// let spinlock = Spinlock::new(vec![3, 1, 2]);
+    /// spinlock.lock_slice().sort_unstable();
+    /// assert_eq!(*spinlock.lock(), [1, 2, 3]);
+    /// ```
+    fn lock_slice(&self) -> MappedSpinlockGuard<'_, [T]>;
+
+    /// Tries to acquire the lock and returns a guard for the elements of the vector as a slice.
+    ///
+    /// Returns `None` if the lock is already held.
+    fn try_lock_slice(&self) -> Option<MappedSpinlockGuard<'_, [T]>>;
+}
+
+#[cfg(feature = "alloc")]
+impl<T> VecSpinlockExt<T> for Spinlock<Vec<T>> {
+    fn lock_slice(&self) -> MappedSpinlockGuard<'_, [T]> {
+        SpinlockGuard::map(self.lock(), |vec| vec.as_mut_slice())
+    }
+
+    fn try_lock_slice(&self) -> Option<MappedSpinlockGuard<'_, [T]>> {
+        self.try_lock()
+            .map(|guard| SpinlockGuard::map(guard, |vec| vec.as_mut_slice()))
+    }
+}
+
+/// Additional methods for a [`Spinlock`] protecting a [`VecDeque`].
+///
+/// Requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub trait VecDequeSpinlockExt<T> {
+    /// Acquires the lock and returns an iterator that removes the elements from the front.
+    ///
+    /// This is the "process and empty the queue" pattern: the lock is held while iterating and
+    /// released when the iterator is dropped. See [`DrainGuard`] for the behavior if the
+    /// iterator is dropped early.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, VecDequeSpinlockExt};
+    /// use std::collections::VecDeque;
+    ///
+    /// let events = Spinlock::new(VecDeque::from(["connect", "send"]));
+    /// let mut handled = Vec::new();
+    /// for event in events.drain_all() {
+    ///     handled.push(event);
+    /// }
+    /// assert_eq!(handled, ["connect", "send"]);
+    /// assert!(events.lock().is_empty());
+    /// ```
+    fn drain_all(&self) -> DrainGuard<'_, T>;
+}
+
+#[cfg(feature = "alloc")]
+impl<T> VecDequeSpinlockExt<T> for Spinlock<VecDeque<T>> {
+    fn drain_all(&self) -> DrainGuard<'_, T> {
+        DrainGuard::new(self.lock())
+    }
+}
+
+/// Additional functions for [`SpinlockGuard`].
+///
+/// Like the functions of [`lock_api::MutexGuard`], these are associated functions instead of
+/// methods to avoid conflicts with methods of the guarded data. Call them as
+/// `SpinlockGuard::function(&guard)`.
+pub trait SpinlockGuardExt<T: ?Sized> {
+    /// Returns a raw pointer to the guarded data.
+    ///
+    /// The pointer is only valid while the guard is alive, i.e. while the lock is held. Since it
+    /// is derived from a mutable borrow of the guard, it may be used for writes, but it must not
+    /// be used while a reference obtained through the guard is alive.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockGuard, SpinlockGuardExt};
+    ///
+    /// let spinlock = Spinlock::new(0u32);
+    /// let mut guard = spinlock.lock();
+    /// let ptr = SpinlockGuard::as_mut_ptr(&mut guard);
+    /// unsafe { ptr.write(42) };
+    /// drop(guard);
+    /// assert_eq!(*spinlock.lock(), 42);
+    /// ```
+    fn as_mut_ptr(guard: &mut Self) -> *mut T;
+
+    /// Returns whether both guards belong to the same spinlock.
+    ///
+    /// This is useful in generic code that passes guards around without the locks they came
+    /// from. Since a spinlock can only be held once, this can only return `true` for two
+    /// references to the same guard.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockGuard, SpinlockGuardExt};
+    ///
+    /// let a = Spinlock::new(1);
+    /// let b = Spinlock::new(1);
+    /// let (guard_a, guard_b) = (a.lock(), b.lock());
+    /// assert!(SpinlockGuard::same_lock(&guard_a, &guard_a));
+    /// assert!(!SpinlockGuard::same_lock(&guard_a, &guard_b));
+    /// ```
+    fn same_lock(a: &Self, b: &Self) -> bool;
+
+    /// Returns whether the address of the guarded data is a multiple of `align`.
+    ///
+    /// This allows SIMD code to check whether a guarded buffer can be accessed through aligned
+    /// loads, e.g. if it requires a larger alignment than
+    /// [`data_align`](SpinlockExt::data_align) guarantees.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::{Spinlock, SpinlockGuard, SpinlockGuardExt};
+    ///
+    /// #[repr(align(16))]
+    /// struct Lanes([f32; 4]);
+    ///
+    /// let spinlock = Spinlock::new(Lanes([0.0; 4]));
+    /// let guard = spinlock.lock();
+    /// assert!(SpinlockGuard::is_aligned_to(&guard, 16));
+    /// ```
+    fn is_aligned_to(guard: &Self, align: usize) -> bool;
+
+    /// Releases the lock and then sets `signal` to `true`.
+    ///
+    /// This allows a waiter to block on the signal, e.g. through an event-driven scheduler,
+    /// instead of spinning on the lock. The signal is set with `Release` ordering after the
+    /// lock was released, so a waiter that observes the signal with an `Acquire` load also
+    /// observes all writes of the critical section and finds the lock unlocked, unless someone
+    /// else acquired it in the meantime.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::sync::atomic::{AtomicBool, Ordering};
+    /// use spinning_top::{Spinlock, SpinlockGuard, SpinlockGuardExt};
+    ///
+    /// let spinlock = Spinlock::new(0);
+    /// let done = AtomicBool::new(false);
+    /// let mut guard = spinlock.lock();
+    /// *guard += 1;
+    /// SpinlockGuard::unlock_and_signal(guard, &done);
+    ///
+    /// assert!(done.load(Ordering::Acquire));
+    /// assert!(!spinlock.is_locked());
+    /// ```
+    fn unlock_and_signal(guard: Self, signal: &AtomicBool)
+    where
+        Self: Sized;
+
+    /// Issues a sequentially consistent fence inside the critical section.
+    ///
+    /// The lock only orders memory accesses between threads that use the lock. If data written
+    /// under the lock is also observed by lock-free readers, e.g. through atomics that are read
+    /// without locking, those readers may need a total order with other `SeqCst` operations,
+    /// for example to check a flag and a value in a Dekker-style protocol. Call this method
+    /// between the writes that must be ordered. It is equivalent to
+    /// `core::sync::atomic::fence(Ordering::SeqCst)`, but makes the intent visible at the call
+    /// site.
+    ///
+    /// The fence doesn't make non-atomic writes visible to lock-free readers; any data they
+    /// read must be atomic.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::sync::atomic::{fence, AtomicBool, AtomicU32, Ordering};
+    /// use spinning_top::{Spinlock, SpinlockGuard, SpinlockGuardExt};
+    ///
+    /// static VALUE: AtomicU32 = AtomicU32::new(0);
+    /// static READY: AtomicBool = AtomicBool::new(false);
+    /// let writers = Spinlock::new(());
+    ///
+    /// // writer, serialized with other writers through the lock
+    /// let guard = writers.lock();
+    /// VALUE.store(42, Ordering::Relaxed);
+    /// SpinlockGuard::publish_fence(&guard);
+    /// READY.store(true, Ordering::Relaxed);
+    /// drop(guard);
+    ///
+    /// // lock-free reader
+    /// if READY.load(Ordering::Relaxed) {
+    ///     fence(Ordering::SeqCst);
+    ///     assert_eq!(VALUE.load(Ordering::Relaxed), 42);
+    /// }
+    /// ```
+    fn publish_fence(guard: &Self);
+}
+
+impl<T: ?Sized> SpinlockGuardExt<T> for SpinlockGuard<'_, T> {
+    fn as_mut_ptr(guard: &mut Self) -> *mut T {
+        SpinlockGuard::mutex(guard).data_ptr()
+    }
+
+    fn same_lock(a: &Self, b: &Self) -> bool {
+        core::ptr::eq(SpinlockGuard::mutex(a), SpinlockGuard::mutex(b))
+    }
+
+    fn is_aligned_to(guard: &Self, align: usize) -> bool {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        let addr = SpinlockGuard::mutex(guard).data_ptr() as *const u8 as usize;
+        addr & (align - 1) == 0
+    }
+
+    fn unlock_and_signal(guard: Self, signal: &AtomicBool) {
+        drop(guard);
+        signal.store(true, Ordering::Release);
+    }
+
+    fn publish_fence(_guard: &Self) {
+        core::sync::atomic::fence(Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{
+        string::ToString,
+        sync::{Arc, Barrier},
+        thread,
+        time::Duration,
+        vec::Vec,
+    };
+
+    #[test]
+    fn lock_some_present() {
+        let spinlock = Spinlock::new(Some(1));
+        let mut guard = spinlock.lock_some().unwrap();
+        assert!(spinlock.is_locked());
+        *guard += 1;
+        drop(guard);
+        assert_eq!(*spinlock.lock(), Some(2));
+    }
+
+    #[test]
+    fn lock_some_absent() {
+        let spinlock = Spinlock::new(None::<i32>);
+        assert!(spinlock.lock_some().is_none());
+        assert!(!spinlock.is_locked());
+    }
+
+    #[test]
+    fn take_some_empties_slot() {
+        let spinlock = Spinlock::new(Some(1));
+        assert_eq!(spinlock.take_some(), Some(1));
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), None);
+        assert_eq!(spinlock.take_some(), None);
+    }
+
+    #[test]
+    fn take_some_race() {
+        let spinlock = Arc::new(Spinlock::new(Some(1)));
+        let barrier = Arc::new(Barrier::new(2));
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let spinlock = spinlock.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    spinlock.take_some()
+                })
+            })
+            .collect();
+        let taken: Vec<_> = threads
+            .into_iter()
+            .filter_map(|thread| thread.join().unwrap())
+            .collect();
+        assert_eq!(taken, [1]);
+        assert_eq!(*spinlock.lock(), None);
+    }
+
+    #[test]
+    fn highly_aligned_data() {
+        #[repr(align(64))]
+        struct CacheLine([u8; 64]);
+
+        let spinlock = Spinlock::new(CacheLine([0; 64]));
+        assert_eq!(spinlock.data_align(), 64);
+        let guard = spinlock.lock();
+        assert!(SpinlockGuard::is_aligned_to(&guard, spinlock.data_align()));
+        assert!(SpinlockGuard::is_aligned_to(&guard, 1));
+        assert_eq!(guard.0.as_ptr() as usize % 64, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn alignment_not_power_of_two() {
+        let spinlock = Spinlock::new(0u32);
+        SpinlockGuard::is_aligned_to(&spinlock.lock(), 3);
+    }
+
+    #[test]
+    fn wait_until_other_thread() {
+        let spinlock = Arc::new(Spinlock::new(0));
+        let setter = {
+            let spinlock = spinlock.clone();
+            thread::spawn(move || {
+                for _ in 0..3 {
+                    *spinlock.lock() += 1;
+                    thread::yield_now();
+                }
+            })
+        };
+        spinlock.wait_until(|value| *value == 3);
+        assert!(!spinlock.is_locked());
+        setter.join().unwrap();
+        assert_eq!(*spinlock.lock(), 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn try_lock_nanos() {
+        let spinlock = Arc::new(Spinlock::new(0));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+                drop(guard);
+            })
+        };
+
+        barrier.wait();
+        assert!(spinlock.try_lock_nanos(1_000).is_none());
+        assert!(spinlock.try_lock_nanos(10_000_000_000).is_some());
+        holder.join().unwrap();
+    }
+
+    #[test]
+    fn lock_or_else_free() {
+        let spinlock = Spinlock::new(1);
+        let value = spinlock.lock_or_else(
+            |v| {
+                *v += 1;
+                *v
+            },
+            || 0,
+        );
+        assert_eq!(value, 2);
+        assert!(!spinlock.is_locked());
+    }
+
+    #[test]
+    fn lock_or_local_acquired() {
+        let spinlock = Spinlock::new(1);
+        let value = spinlock.lock_or_local(
+            10,
+            || unreachable!(),
+            |v| {
+                *v += 1;
+                *v
+            },
+        );
+        assert_eq!(value, 2);
+        assert!(!spinlock.is_locked());
+    }
+
+    #[test]
+    fn lock_or_local_fallback() {
+        let spinlock = Spinlock::new(1);
+        let guard = spinlock.lock();
+        let value = spinlock.lock_or_local(10, || 0, |_| unreachable!());
+        assert_eq!(value, 0);
+        drop(guard);
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[test]
+    fn lock_or_else_busy() {
+        let spinlock = Arc::new(Spinlock::new(1));
+        let locked = Arc::new(Barrier::new(2));
+        let checked = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let locked = locked.clone();
+            let checked = checked.clone();
+            thread::spawn(move || {
+                let _guard = spinlock.lock();
+                locked.wait();
+                checked.wait();
+            })
+        };
+
+        locked.wait();
+        let value = spinlock.lock_or_else(|v| *v, || 0);
+        assert_eq!(value, 0);
+        checked.wait();
+        holder.join().unwrap();
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[test]
+    fn update() {
+        let spinlock = Spinlock::new(1);
+        assert_eq!(
+            spinlock.update(|v| {
+                *v += 1;
+                *v * 10
+            }),
+            20
+        );
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), 2);
+    }
+
+    #[test]
+    fn try_update_ok() {
+        let spinlock = Spinlock::new(1);
+        let result: Result<i32, ()> = spinlock.try_update(|v| {
+            *v += 1;
+            Ok(*v * 10)
+        });
+        assert_eq!(result, Ok(20));
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), 2);
+    }
+
+    #[test]
+    fn try_update_err_keeps_changes() {
+        let spinlock = Spinlock::new(1);
+        let result: Result<(), &str> = spinlock.try_update(|v| {
+            *v += 1;
+            Err("invalid")
+        });
+        assert_eq!(result, Err("invalid"));
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), 2);
+    }
+
+    #[test]
+    fn try_update_rollback() {
+        let spinlock = Spinlock::new(std::vec![1]);
+        let result: Result<usize, ()> = spinlock.try_update_rollback(|v| {
+            v.push(2);
+            Ok(v.len())
+        });
+        assert_eq!(result, Ok(2));
+
+        let result: Result<(), &str> = spinlock.try_update_rollback(|v| {
+            v.push(3);
+            v[0] = 0;
+            Err("invalid")
+        });
+        assert_eq!(result, Err("invalid"));
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), [1, 2]);
+    }
+
+    #[test]
+    fn lock_retry_until_condition_clears() {
+        // (busy, value) like a device register that must be read again while busy
+        let spinlock = Arc::new(Spinlock::new((true, 0)));
+        let device = {
+            let spinlock = spinlock.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                // only possible because `lock_retry` releases the lock between attempts
+                *spinlock.lock() = (false, 42);
+            })
+        };
+
+        let mut attempts = 0;
+        let value = spinlock.lock_retry(|&mut (busy, value)| {
+            attempts += 1;
+            if busy {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(value)
+            }
+        });
+        device.join().unwrap();
+        assert_eq!(value, 42);
+        assert!(attempts > 1);
+        assert!(!spinlock.is_locked());
+    }
+
+    #[test]
+    fn fetch_update() {
+        let spinlock = Spinlock::new(std::string::String::from("a"));
+        let previous = spinlock.fetch_update(|s| s.clone() + "b");
+        assert_eq!(previous, "a");
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), "ab");
+    }
+
+    #[test]
+    fn swap() {
+        let spinlock = Spinlock::new(1);
+        assert_eq!(spinlock.swap(2), 1);
+        assert_eq!(spinlock.swap(3), 2);
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), 3);
+    }
+
+    #[test]
+    fn reset_drops_old_value() {
+        struct DropCounter<'a>(u32, &'a core::cell::Cell<u32>);
+
+        impl Drop for DropCounter<'_> {
+            fn drop(&mut self) {
+                self.1.set(self.1.get() + 1);
+            }
+        }
+
+        let drops = core::cell::Cell::new(0);
+        let spinlock = Spinlock::new(DropCounter(1, &drops));
+        spinlock.reset(DropCounter(2, &drops));
+        assert_eq!(drops.get(), 1);
+        assert!(!spinlock.is_locked());
+        assert_eq!(spinlock.lock().0, 2);
+
+        drop(spinlock);
+        assert_eq!(drops.get(), 2);
+    }
+
+    #[test]
+    fn snapshot() {
+        let spinlock = Spinlock::new(std::vec![1]);
+        spinlock.lock().push(2);
+        let snapshot = spinlock.snapshot();
+        spinlock.lock().push(3);
+        assert!(!spinlock.is_locked());
+        assert_eq!(snapshot, [1, 2]);
+        assert_eq!(*spinlock.lock(), [1, 2, 3]);
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Point {
+        x: u16,
+        y: u16,
+    }
+
+    impl Encode for Point {
+        fn encode(&self, buf: &mut [u8]) -> usize {
+            buf[..2].copy_from_slice(&self.x.to_be_bytes());
+            buf[2..4].copy_from_slice(&self.y.to_be_bytes());
+            4
+        }
+    }
+
+    impl Decode for Point {
+        fn decode(&mut self, buf: &[u8]) -> usize {
+            self.x = u16::from_be_bytes([buf[0], buf[1]]);
+            self.y = u16::from_be_bytes([buf[2], buf[3]]);
+            4
+        }
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let source = Spinlock::new(Point { x: 1, y: 0x0203 });
+        let mut buf = [0xff; 6];
+        assert_eq!(source.encode_into(&mut buf), 4);
+        assert!(!source.is_locked());
+        assert_eq!(buf, [0, 1, 2, 3, 0xff, 0xff]);
+
+        let target = Spinlock::new(Point::default());
+        assert_eq!(target.decode_from(&buf), 4);
+        assert!(!target.is_locked());
+        assert_eq!(target.into_inner(), source.into_inner());
+    }
+
+    #[test]
+    fn decode_is_atomic_for_other_lock_users() {
+        let lock = Arc::new(Spinlock::new(Point::default()));
+        let reader = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    let point = lock.lock();
+                    assert_eq!(point.x, point.y);
+                }
+            })
+        };
+        let mut buf = [0; 4];
+        for i in 0..100u16 {
+            Point { x: i, y: i }.encode(&mut buf);
+            lock.decode_from(&buf);
+        }
+        reader.join().unwrap();
+        assert_eq!(*lock.lock(), Point { x: 99, y: 99 });
+    }
+
+    #[test]
+    fn compare_and_set_success() {
+        let spinlock = Spinlock::new(1);
+        assert_eq!(spinlock.compare_and_set(1, 2), Ok(()));
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), 2);
+    }
+
+    #[test]
+    fn compare_and_set_mismatch() {
+        let spinlock = Spinlock::new(1);
+        assert_eq!(spinlock.compare_and_set(3, 2), Err(1));
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[test]
+    fn borrow_mut() {
+        let spinlock = Spinlock::new(1);
+        *spinlock.borrow_mut() += 1;
+        assert!(!spinlock.is_locked());
+
+        let guard = spinlock.borrow_mut();
+        assert!(spinlock.is_locked());
+        assert!(spinlock.try_lock().is_none());
+        drop(guard);
+        assert_eq!(*spinlock.lock(), 2);
+    }
+
+    #[test]
+    fn try_borrow_mut() {
+        let spinlock = Spinlock::new(1);
+        let guard = spinlock.try_borrow_mut().unwrap();
+        assert_eq!(*guard, 1);
+        assert_eq!(
+            spinlock.try_borrow_mut().unwrap_err().to_string(),
+            "spinlock is already locked"
+        );
+        drop(guard);
+        assert!(spinlock.try_borrow_mut().is_ok());
+    }
+
+    #[cfg(all(feature = "track-caller", debug_assertions))]
+    #[test]
+    fn lock_tracked() {
+        let spinlock = Spinlock::new(0);
+        assert!(spinlock.last_locked_at().is_none());
+
+        let line = line!() + 1;
+        let guard = spinlock.lock_tracked();
+        let location = spinlock.last_locked_at().unwrap();
+        assert_eq!(location.file(), file!());
+        assert_eq!(location.line(), line);
+        drop(guard);
+
+        // plain locking keeps the last tracked location
+        drop(spinlock.lock());
+        assert_eq!(spinlock.last_locked_at(), Some(location));
+
+        let line = line!() + 1;
+        drop(spinlock.lock_tracked());
+        assert_eq!(spinlock.last_locked_at().unwrap().line(), line);
+    }
+
+    #[cfg(feature = "owner-tracking")]
+    #[test]
+    fn would_deadlock() {
+        let spinlock = Arc::new(Spinlock::new(0));
+        assert!(!spinlock.would_deadlock());
+
+        let guard = spinlock.lock();
+        assert!(spinlock.would_deadlock());
+        let other = {
+            let spinlock = spinlock.clone();
+            thread::spawn(move || spinlock.would_deadlock())
+        };
+        assert!(!other.join().unwrap());
+        drop(guard);
+        assert!(!spinlock.would_deadlock());
+
+        let guard = spinlock.try_lock().unwrap();
+        assert!(spinlock.would_deadlock());
+        drop(guard);
+
+        // held by another thread
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let _guard = spinlock.lock();
+                barrier.wait();
+                barrier.wait();
+            })
+        };
+        barrier.wait();
+        assert!(spinlock.is_locked());
+        assert!(!spinlock.would_deadlock());
+        barrier.wait();
+        holder.join().unwrap();
+    }
+
+    #[cfg(feature = "contention-hint")]
+    #[test]
+    fn contention_hint() {
+        let spinlock = Arc::new(Spinlock::new(1));
+        *spinlock.lock() += 1;
+        assert_eq!(spinlock.contention_hint(), ContentionLevel::Uncontended);
+
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+                drop(guard);
+            })
+        };
+        barrier.wait();
+        drop(spinlock.lock());
+        holder.join().unwrap();
+        assert_eq!(spinlock.contention_hint(), ContentionLevel::Contended);
+
+        // uncontended acquisitions don't reset the hint
+        drop(spinlock.lock());
+        assert_eq!(spinlock.contention_hint(), ContentionLevel::Contended);
+        spinlock.clear_contention_hint();
+        assert_eq!(spinlock.contention_hint(), ContentionLevel::Uncontended);
+    }
+
+    #[test]
+    fn id() {
+        let a = Spinlock::new(1);
+        let b = Spinlock::new(1);
+        assert_ne!(a.id(), b.id());
+        assert_eq!(a.id(), a.id());
+        let _guard = a.lock();
+        assert_eq!(a.id(), unsafe { a.raw() }.id());
+    }
+
+    #[test]
+    fn lock_contended_uncontended() {
+        let spinlock = Spinlock::new(1);
+        let (guard, contended) = spinlock.lock_contended();
+        assert_eq!(*guard, 1);
+        assert!(!contended);
+    }
+
+    #[test]
+    fn lock_contended_waits() {
+        let spinlock = Arc::new(Spinlock::new(1));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let mut guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+                *guard += 1;
+            })
+        };
+
+        barrier.wait();
+        let (guard, contended) = spinlock.lock_contended();
+        assert_eq!(*guard, 2);
+        assert!(contended);
+        drop(guard);
+        holder.join().unwrap();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn lock_waited_uncontended() {
+        let spinlock = Spinlock::new(1);
+        let (guard, waited) = spinlock.lock_waited();
+        assert_eq!(*guard, 1);
+        assert_eq!(waited, Duration::ZERO);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn lock_waited_contended() {
+        let spinlock = Arc::new(Spinlock::new(1));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let mut guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+                *guard += 1;
+            })
+        };
+
+        barrier.wait();
+        let (guard, waited) = spinlock.lock_waited();
+        assert_eq!(*guard, 2);
+        assert!(waited > Duration::ZERO);
+        drop(guard);
+        holder.join().unwrap();
+    }
+
+    #[test]
+    fn try_lock_cancellable() {
+        let spinlock = Arc::new(Spinlock::new(1));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let cancel = cancel.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let _guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+                cancel.store(true, Ordering::Relaxed);
+                // keep holding the lock, so the waiter can only return through the cancellation
+                thread::sleep(Duration::from_millis(10));
+            })
+        };
+
+        barrier.wait();
+        assert!(spinlock.try_lock_cancellable(&cancel).is_none());
+        holder.join().unwrap();
+
+        cancel.store(false, Ordering::Relaxed);
+        assert_eq!(*spinlock.try_lock_cancellable(&cancel).unwrap(), 1);
+    }
+
+    #[test]
+    fn peek() {
+        let spinlock = Spinlock::new(1);
+        *spinlock.lock() += 1;
+        // SAFETY: the lock is only used by this thread and no guard is alive
+        assert_eq!(unsafe { spinlock.peek(|v| *v) }, 2);
+        assert!(!spinlock.is_locked());
+
+        // reading while the lock is held is fine as long as nobody writes
+        let _guard = spinlock.lock();
+        assert_eq!(unsafe { spinlock.peek(|v| *v + 1) }, 3);
+    }
+
+    #[test]
+    fn lock_if_rechecks_under_lock() {
+        let spinlock = Spinlock::new(AtomicBool::new(true));
+        let checks = AtomicUsize::new(0);
+        let precheck = |flag: &AtomicBool| {
+            checks.fetch_add(1, Ordering::Relaxed);
+            flag.load(Ordering::Relaxed)
+        };
+
+        // SAFETY: the data is only modified through the atomic flag
+        let guard = unsafe { spinlock.lock_if(precheck) }.unwrap();
+        assert!(spinlock.is_locked());
+        assert_eq!(checks.load(Ordering::Relaxed), 2);
+        guard.store(false, Ordering::Relaxed);
+        drop(guard);
+
+        // fails without locking
+        assert!(unsafe { spinlock.lock_if(precheck) }.is_none());
+        assert_eq!(checks.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn lock_if_value_changed_between_checks() {
+        let spinlock = Spinlock::new(AtomicBool::new(true));
+        // the first check passes and then simulates a concurrent update, which the second check
+        // under the lock detects
+        let precheck = |flag: &AtomicBool| flag.swap(false, Ordering::Relaxed);
+        // SAFETY: the data is only modified through the atomic flag
+        assert!(unsafe { spinlock.lock_if(precheck) }.is_none());
+        assert!(!spinlock.is_locked());
+        assert!(!spinlock.lock().load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn lock_unless_eq_skips_equal() {
+        let spinlock = Spinlock::new(5);
+        // SAFETY: the lock is not shared with other threads
+        assert!(unsafe { spinlock.lock_unless_eq(&5) }.is_none());
+        assert!(!spinlock.is_locked());
+    }
+
+    #[test]
+    fn lock_unless_eq_locks_on_mismatch() {
+        let spinlock = Spinlock::new(5);
+        // SAFETY: the lock is not shared with other threads
+        let mut guard = unsafe { spinlock.lock_unless_eq(&7) }.unwrap();
+        assert!(spinlock.is_locked());
+        *guard = 7;
+        drop(guard);
+        assert!(unsafe { spinlock.lock_unless_eq(&7) }.is_none());
+    }
+
+    #[test]
+    fn lock_unless_eq_value_matched_between_checks() {
+        /// Simulates a concurrent update by taking over the compared value after each comparison.
+        struct Racy(core::cell::Cell<u32>);
+
+        impl PartialEq for Racy {
+            fn eq(&self, other: &Self) -> bool {
+                let equal = self.0.get() == other.0.get();
+                self.0.set(other.0.get());
+                equal
+            }
+        }
+
+        let spinlock = Spinlock::new(Racy(core::cell::Cell::new(1)));
+        let target = Racy(core::cell::Cell::new(2));
+        // SAFETY: the lock is not shared with other threads
+        assert!(unsafe { spinlock.lock_unless_eq(&target) }.is_none());
+        assert!(!spinlock.is_locked());
+        assert_eq!(spinlock.lock().0.get(), 2);
+    }
+
+    #[test]
+    fn drain_waits_for_holder() {
+        let spinlock = Arc::new(Spinlock::new(false));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let mut guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+                *guard = true; // marks the end of the critical section
+            })
+        };
+
+        barrier.wait();
+        spinlock.drain();
+        assert!(unsafe { spinlock.peek(|finished| *finished) });
+        holder.join().unwrap();
+    }
+
+    #[test]
+    fn poll_lock_cooperative_loop() {
+        let spinlock = Spinlock::new(0);
+        // a task of the scheduler that holds the lock for three rounds of the run loop
+        let mut holder = Some(spinlock.lock());
+        let mut rounds = 0;
+
+        let mut guard = loop {
+            match spinlock.poll_lock() {
+                Poll::Ready(guard) => break guard,
+                Poll::Pending => {
+                    rounds += 1;
+                    // yield to the holder task
+                    if rounds == 3 {
+                        let mut guard = holder.take().unwrap();
+                        *guard += 1;
+                    }
+                }
+            }
+        };
+        assert_eq!(rounds, 3);
+        assert_eq!(*guard, 1);
+        *guard += 1;
+        assert!(spinlock.poll_lock().is_pending());
+        drop(guard);
+        assert!(spinlock.poll_lock().is_ready());
+    }
+
+    #[test]
+    fn hold_mapped_guard() {
+        let spinlock = Spinlock::new((1, 2));
+        let result = spinlock.hold(|guard| {
+            let mut second = SpinlockGuard::map(guard, |(_, second)| second);
+            *second += 1;
+            *second
+        });
+        assert_eq!(result, 3);
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), (1, 3));
+    }
+
+    #[test]
+    fn closure_panics_release_lock() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        fn assert_released(spinlock: &Spinlock<i32>, f: impl FnOnce()) {
+            assert!(catch_unwind(AssertUnwindSafe(f)).is_err());
+            assert!(spinlock.try_lock().is_some());
+        }
+
+        let spinlock = Spinlock::new(0);
+        assert_released(&spinlock, || spinlock.update(|_| panic!("update")));
+        assert_released(&spinlock, || {
+            spinlock.lock_or_else(|_| panic!("lock_or_else"), || ())
+        });
+        assert_released(&spinlock, || {
+            spinlock.fetch_update(|_| panic!("fetch_update"));
+        });
+        assert_released(&spinlock, || spinlock.hold(|_guard| panic!("hold")));
+        assert_released(&spinlock, || {
+            let _: Result<(), ()> = spinlock.try_update(|_| panic!("try_update"));
+        });
+        assert_released(&spinlock, || {
+            let _: Result<(), ()> = spinlock.try_update_rollback(|_| panic!("try_update_rollback"));
+        });
+        assert_released(&spinlock, || {
+            spinlock.lock_retry::<()>(|_| panic!("lock_retry"));
+        });
+        assert_released(&spinlock, || spinlock.wait_until(|_| panic!("wait_until")));
+        assert_released(&spinlock, || {
+            spinlock.lock_or_local(0, || (), |_| panic!("lock_or_local"))
+        });
+        assert_released(&spinlock, || {
+            // SAFETY: the lock is not shared with other threads
+            drop(unsafe { spinlock.lock_if(|_| panic!("lock_if")) });
+        });
+
+        // the contended paths run the closures while another guard holds the lock, which must
+        // stay the only holder
+        struct PanickingDeadline;
+
+        impl Deadline for PanickingDeadline {
+            fn has_expired(&self) -> bool {
+                panic!("has_expired")
+            }
+        }
+
+        let guard = spinlock.lock();
+        assert!(catch_unwind(AssertUnwindSafe(|| {
+            spinlock.try_lock_budget(usize::MAX, Some(&PanickingDeadline));
+        }))
+        .is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| {
+            spinlock.lock_or_local(0, || panic!("lock_or_local"), |_| ())
+        }))
+        .is_err());
+        drop(guard);
+        assert!(spinlock.try_lock().is_some());
+
+        let rw = crate::BoundedRwSpinlock::<_, 2>::new(0);
+        assert!(catch_unwind(AssertUnwindSafe(|| {
+            crate::BoundedRwSpinlockExt::with_exclusive(&rw, |_| panic!("with_exclusive"))
+        }))
+        .is_err());
+        assert!(rw.try_write().is_some());
+    }
+
+    #[test]
+    fn try_lock_budget_spins_only() {
+        let spinlock = Spinlock::new(1);
+        assert_eq!(*spinlock.try_lock_budget(0, None).unwrap(), 1);
+
+        let _guard = spinlock.lock();
+        assert!(spinlock.try_lock_budget(100, None).is_none());
+    }
+
+    #[test]
+    fn try_lock_budget_custom_deadline() {
+        use core::cell::Cell;
+
+        // expires after the given number of checks
+        struct Checks(Cell<usize>);
+
+        impl Deadline for Checks {
+            fn has_expired(&self) -> bool {
+                let remaining = self.0.get();
+                self.0.set(remaining.saturating_sub(1));
+                remaining == 0
+            }
+        }
+
+        let spinlock = Spinlock::new(1);
+        let _guard = spinlock.lock();
+        let deadline = Checks(Cell::new(3));
+        assert!(spinlock
+            .try_lock_budget(usize::MAX, Some(&deadline))
+            .is_none());
+        assert_eq!(deadline.0.get(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn try_lock_budget_deadline_only() {
+        let spinlock = Spinlock::new(1);
+        let _guard = spinlock.lock();
+        let start = Instant::now();
+        let deadline = start + Duration::from_millis(10);
+        assert!(spinlock
+            .try_lock_budget(usize::MAX, Some(&deadline))
+            .is_none());
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn try_lock_budget_both() {
+        let spinlock = Spinlock::new(1);
+        let _guard = spinlock.lock();
+
+        // the spin budget trips first
+        let start = Instant::now();
+        let far = start + Duration::from_secs(60);
+        assert!(spinlock.try_lock_budget(100, Some(&far)).is_none());
+        assert!(start.elapsed() < Duration::from_secs(60));
+
+        // the deadline trips first
+        let past = Instant::now();
+        assert!(spinlock.try_lock_budget(usize::MAX, Some(&past)).is_none());
+    }
+
+    #[test]
+    fn guard_same_lock() {
+        let locks = [Spinlock::new(0), Spinlock::new(0)];
+        let guard_0 = locks[0].lock();
+        let guard_1 = locks[1].lock();
+        assert!(SpinlockGuard::same_lock(&guard_0, &guard_0));
+        assert!(!SpinlockGuard::same_lock(&guard_0, &guard_1));
+        assert!(!SpinlockGuard::same_lock(&guard_1, &guard_0));
+    }
+
+    #[test]
+    fn guard_unlock_and_signal() {
+        let spinlock = Arc::new(Spinlock::new(0));
+        let signal = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let spinlock = spinlock.clone();
+            let signal = signal.clone();
+            thread::spawn(move || {
+                // waits on the signal instead of the lock
+                while !signal.load(Ordering::Acquire) {
+                    thread::yield_now();
+                }
+                let guard = spinlock.try_lock().expect("lock is free after the signal");
+                *guard
+            })
+        };
+
+        let mut guard = spinlock.lock();
+        *guard = 42;
+        SpinlockGuard::unlock_and_signal(guard, &signal);
+        assert_eq!(waiter.join().unwrap(), 42);
+    }
+
+    #[test]
+    fn lock_with_raw() {
+        use lock_api::RawMutex;
+
+        let spinlock = Spinlock::new(1);
+        let (mut guard, raw) = spinlock.lock_with_raw();
+        assert!(raw.is_locked());
+        assert!(!raw.try_lock());
+        assert_eq!(raw.id(), spinlock.id());
+        *guard += 1;
+        assert!(raw.is_locked());
+        drop(guard);
+        assert!(!raw.is_locked());
+        assert_eq!(*spinlock.lock(), 2);
+    }
+
+    #[test]
+    fn guard_publish_fence() {
+        let spinlock = Arc::new(Spinlock::new(()));
+        let value = Arc::new(AtomicUsize::new(0));
+        let ready = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let value = value.clone();
+            let ready = ready.clone();
+            thread::spawn(move || {
+                while !ready.load(Ordering::Relaxed) {
+                    thread::yield_now();
+                }
+                core::sync::atomic::fence(Ordering::SeqCst);
+                value.load(Ordering::Relaxed)
+            })
+        };
+
+        let guard = spinlock.lock();
+        value.store(7, Ordering::Relaxed);
+        SpinlockGuard::publish_fence(&guard);
+        ready.store(true, Ordering::Relaxed);
+        drop(guard);
+        assert_eq!(reader.join().unwrap(), 7);
+        assert!(!spinlock.is_locked());
+    }
+
+    #[test]
+    fn guard_as_mut_ptr() {
+        let spinlock = Spinlock::new([0u8; 4]);
+        let mut guard = spinlock.lock();
+        let ptr = SpinlockGuard::as_mut_ptr(&mut guard);
+        unsafe { (*ptr)[2] = 7 };
+        drop(guard);
+        assert_eq!(*spinlock.lock(), [0, 0, 7, 0]);
+    }
+
+    #[cfg(feature = "owner-tracking")]
+    #[test]
+    fn lock_unique_sequential() {
+        let spinlock = Spinlock::new(0);
+        *spinlock.lock_unique() += 1;
+        *spinlock.lock_unique() += 1;
+        assert_eq!(*spinlock.lock(), 2);
+    }
+
+    #[cfg(feature = "owner-tracking")]
+    #[test]
+    fn lock_unique_contended() {
+        let spinlock = Arc::new(Spinlock::new(0));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let _guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+            })
+        };
+        barrier.wait();
+        // another thread holding the lock is not a re-entrant acquisition
+        *spinlock.lock_unique() += 1;
+        holder.join().unwrap();
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[cfg(feature = "owner-tracking")]
+    #[test]
+    #[should_panic(expected = "while the current thread holds the lock")]
+    fn lock_unique_second_guard() {
+        let spinlock = Spinlock::new(0);
+        let _guard = spinlock.lock_unique();
+        drop(spinlock.lock_unique());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn lock_slice() {
+        let spinlock = Spinlock::new(std::vec![1, 2, 3]);
+        {
+            let mut slice = spinlock.lock_slice();
+            slice[0] = 10;
+            slice.swap(1, 2);
+            assert!(spinlock.try_lock_slice().is_none());
+        }
+        spinlock.try_lock_slice().unwrap()[1] += 1;
+        assert_eq!(*spinlock.lock(), [10, 4, 2]);
+    }
+
+    #[test]
+    fn lock_two_indices() {
+        let spinlock = Spinlock::new([1, 2, 3, 4]);
+        {
+            let mut guard = spinlock.lock_two_indices(1, 3);
+            assert!(spinlock.is_locked());
+            let (a, b) = guard.parts_mut();
+            *a += 10;
+            *b += *a;
+        }
+        {
+            let mut guard = spinlock.lock_two_indices(2, 0);
+            let (a, b) = guard.parts_mut();
+            assert_eq!((*a, *b), (3, 1));
+            *a = 0;
+        }
+        assert_eq!(*spinlock.lock(), [1, 12, 0, 16]);
+    }
+
+    #[test]
+    #[should_panic(expected = "distinct")]
+    fn lock_two_indices_same() {
+        let spinlock = Spinlock::new([1, 2]);
+        let _ = spinlock.lock_two_indices(1, 1);
+    }
+}
@@ -0,0 +1,91 @@
+//! C functions for locking a [`RawSpinlock`] that is shared with Rust code.
+
+use crate::RawSpinlock;
+use lock_api::RawMutex;
+
+/// Tries to acquire the given raw spinlock without spinning.
+///
+/// Returns `true` if the lock was acquired. This is the C interface of
+/// [`RawMutex::try_lock`]; C code declares it as:
+///
+/// ```c
+/// typedef struct RawSpinlock RawSpinlock;
+///
+/// bool spinlock_try_lock(const RawSpinlock *lock);
+/// void spinlock_unlock(const RawSpinlock *lock);
+/// ```
+///
+/// The return type is a C `_Bool`. The lock is passed as an opaque pointer: its layout depends
+/// on the enabled features of this crate, so it is **not** `repr(transparent)` over the lock
+/// word, and C code must neither create nor inspect it. Instead, the lock is created on the Rust
+/// side, e.g. as part of a [`Spinlock`](crate::Spinlock) whose raw lock is obtained through
+/// [`Mutex::raw`](lock_api::Mutex::raw), and only a pointer to it is handed to C.
+///
+/// Both functions operate on the same lock word as the Rust methods, so a lock acquired in C
+/// excludes Rust guards and vice versa.
+///
+/// Requires the `ffi` feature.
+///
+/// ## Safety
+///
+/// `lock` must point to a valid `RawSpinlock` that stays alive during the call.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{spinlock_try_lock, spinlock_unlock, Spinlock};
+///
+/// let spinlock = Spinlock::new(0);
+/// // SAFETY: the raw lock is only used to lock and unlock it without creating a guard
+/// let raw = unsafe { spinlock.raw() } as *const _;
+/// assert!(unsafe { spinlock_try_lock(raw) });
+/// assert!(spinlock.try_lock().is_none());
+/// unsafe { spinlock_unlock(raw) };
+/// assert!(spinlock.try_lock().is_some());
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn spinlock_try_lock(lock: *const RawSpinlock) -> bool {
+    (*lock).try_lock()
+}
+
+/// Releases the given raw spinlock.
+///
+/// This is the C interface of [`RawMutex::unlock`]. See [`spinlock_try_lock`] for the ABI.
+///
+/// Requires the `ffi` feature.
+///
+/// ## Safety
+///
+/// `lock` must point to a valid `RawSpinlock` that stays alive during the call. The lock must
+/// be held, and the code that acquired it must not use it afterwards, e.g. no guard of it may
+/// exist.
+#[no_mangle]
+pub unsafe extern "C" fn spinlock_unlock(lock: *const RawSpinlock) {
+    (*lock).unlock()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Spinlock;
+
+    #[test]
+    fn shares_lock_word_with_rust() {
+        let spinlock = Spinlock::new(1);
+        let raw = unsafe { spinlock.raw() } as *const RawSpinlock;
+
+        assert!(unsafe { spinlock_try_lock(raw) });
+        assert!(spinlock.is_locked());
+        assert!(spinlock.try_lock().is_none());
+        assert!(!unsafe { spinlock_try_lock(raw) });
+        unsafe { spinlock_unlock(raw) };
+        assert!(!spinlock.is_locked());
+
+        let guard = spinlock.lock();
+        assert!(!unsafe { spinlock_try_lock(raw) });
+        drop(guard);
+        assert!(unsafe { spinlock_try_lock(raw) });
+        unsafe { spinlock_unlock(raw) };
+        assert_eq!(*spinlock.lock(), 1);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::Spinlock;
+use core::{
+    fmt::{self, Write},
+    ops::Deref,
+};
+
+/// A [`Spinlock`] around a [`core::fmt::Write`] implementor that implements `fmt::Write` itself.
+///
+/// This is the `no_std` counterpart of `WriteSpinlock`, e.g. for a serial port that is shared
+/// by the `print!` macros of a kernel. `fmt::Write` is also implemented for shared references,
+/// so a `&FmtSpinlock` can be passed to `write!` and `writeln!` directly. Every call of a
+/// `fmt::Write` method locks the writer only for the duration of that call. A formatted message
+/// is written by a single `write_fmt` call, so concurrent messages are not interleaved.
+///
+/// The wrapper dereferences to the inner [`Spinlock`], so the writer can also be locked
+/// explicitly to write multiple messages without interruption.
+///
+/// ## Example
+///
+/// ```rust
+/// use core::fmt::{self, Write};
+/// use spinning_top::FmtSpinlock;
+///
+/// struct Serial;
+///
+/// impl Write for Serial {
+///     fn write_str(&mut self, s: &str) -> fmt::Result {
+///         // write the bytes of `s` to the serial port
+///         Ok(())
+///     }
+/// }
+///
+/// static SERIAL: FmtSpinlock<Serial> = FmtSpinlock::new(Serial);
+///
+/// macro_rules! println {
+///     ($($arg:tt)*) => {
+///         writeln!(&SERIAL, $($arg)*).unwrap()
+///     };
+/// }
+///
+/// println!("Hello {}!", "World");
+/// ```
+pub struct FmtSpinlock<W: ?Sized> {
+    lock: Spinlock<W>,
+}
+
+impl<W> FmtSpinlock<W> {
+    /// Creates a new unlocked `FmtSpinlock`.
+    pub const fn new(writer: W) -> Self {
+        Self {
+            lock: Spinlock::new(writer),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.lock.into_inner()
+    }
+}
+
+impl<W: ?Sized + Write> Write for &FmtSpinlock<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.lock.lock().write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.lock.lock().write_char(c)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        self.lock.lock().write_fmt(args)
+    }
+}
+
+impl<W: ?Sized + Write> Write for FmtSpinlock<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        (&*self).write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        (&*self).write_char(c)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        (&*self).write_fmt(args)
+    }
+}
+
+impl<W: ?Sized> Deref for FmtSpinlock<W> {
+    type Target = Spinlock<W>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lock
+    }
+}
+
+impl<W: ?Sized + fmt::Debug> fmt::Debug for FmtSpinlock<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FmtSpinlock")
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mock serial port that stores the written bytes in a fixed-size buffer.
+    struct MockSerial {
+        buf: [u8; 32],
+        len: usize,
+        writes: usize,
+    }
+
+    impl MockSerial {
+        const fn new() -> Self {
+            Self {
+                buf: [0; 32],
+                len: 0,
+                writes: 0,
+            }
+        }
+
+        fn output(&self) -> &str {
+            core::str::from_utf8(&self.buf[..self.len]).unwrap()
+        }
+    }
+
+    impl Write for MockSerial {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            let dst = self.buf.get_mut(self.len..end).ok_or(fmt::Error)?;
+            dst.copy_from_slice(s.as_bytes());
+            self.len = end;
+            self.writes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn formatted_output() {
+        let mut serial = FmtSpinlock::new(MockSerial::new());
+        write!(&serial, "x = {}", 1).unwrap();
+        serial.write_char(',').unwrap();
+        writeln!(serial, " y = {:02}", 2).unwrap();
+        assert!(!serial.is_locked());
+        assert_eq!(serial.lock().output(), "x = 1, y = 02\n");
+    }
+
+    #[test]
+    fn write_error() {
+        let serial = FmtSpinlock::new(MockSerial::new());
+        assert!(write!(&serial, "{:64}", "too long").is_err());
+        assert!(!serial.is_locked());
+    }
+
+    #[test]
+    fn guard_writes() {
+        let serial = FmtSpinlock::new(MockSerial::new());
+        {
+            let mut guard = serial.lock();
+            write!(guard, "a").unwrap();
+            write!(guard, "b").unwrap();
+        }
+        let serial = serial.into_inner();
+        assert_eq!(serial.output(), "ab");
+        assert_eq!(serial.writes, 2);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A [`Spinlock`] that counts its lock/unlock cycles in a generation number.
+///
+/// Every guard increments the generation when it is dropped, right before the lock is released,
+/// whether the data was modified or not. Code outside the lock can compare the
+/// [`generation`](Self::generation) before and after an operation to detect that a critical
+/// section completed in the meantime, which a comparison of the data itself can't do if it was
+/// changed and then changed back (the ABA problem). Each guard also remembers the generation it
+/// was acquired under, see [`GenerationSpinlockGuard::generation`].
+///
+/// ## Optimistic Reads
+///
+/// A lock-free reader of data that the critical sections update, e.g. a set of atomics, can
+/// check that it saw a consistent state: it reads the generation, makes sure the lock is not
+/// held, performs its reads, and then checks that the lock is still not held and that the
+/// generation is unchanged. Otherwise, a critical section ran concurrently with the reads, so the
+/// reader retries. Reading non-atomic data without the lock is still a data race and not
+/// allowed.
+///
+/// The generation is a `u64`, so this type is only available on targets with 64-bit atomics.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{GenerationSpinlock, GenerationSpinlockGuard};
+///
+/// let lock = GenerationSpinlock::new(0);
+/// let before = lock.generation();
+///
+/// let mut guard = lock.lock();
+/// assert_eq!(GenerationSpinlockGuard::generation(&guard), before);
+/// *guard += 1;
+/// drop(guard);
+///
+/// assert_eq!(lock.generation(), before + 1);
+/// ```
+pub struct GenerationSpinlock<T: ?Sized> {
+    /// Incremented while holding the lock, right before every release.
+    generation: AtomicU64,
+    lock: Spinlock<T>,
+}
+
+impl<T> GenerationSpinlock<T> {
+    /// Creates a new unlocked `GenerationSpinlock` with generation `0`.
+    pub const fn new(val: T) -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            lock: Spinlock::new(val),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: ?Sized> GenerationSpinlock<T> {
+    /// Returns the number of completed lock/unlock cycles.
+    ///
+    /// The load has `Acquire` semantics, so the effects of all critical sections up to the
+    /// returned generation are visible afterwards.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Acquires the lock, spinning until it is available.
+    pub fn lock(&self) -> GenerationSpinlockGuard<'_, T> {
+        self.guard(self.lock.lock())
+    }
+
+    /// Tries to acquire the lock without spinning.
+    ///
+    /// Returns `None` if the lock is already held.
+    pub fn try_lock(&self) -> Option<GenerationSpinlockGuard<'_, T>> {
+        self.lock.try_lock().map(|guard| self.guard(guard))
+    }
+
+    /// Returns whether the lock is currently held.
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_locked()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// The mutable borrow statically guarantees that no guard exists, so the generation is not
+    /// incremented.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.lock.get_mut()
+    }
+
+    fn guard<'a>(&'a self, guard: SpinlockGuard<'a, T>) -> GenerationSpinlockGuard<'a, T> {
+        GenerationSpinlockGuard {
+            guard,
+            // only updated while the lock is held, so no other thread can change it now
+            acquired_under: self.generation.load(Ordering::Relaxed),
+            generation: &self.generation,
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for GenerationSpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerationSpinlock")
+            .field("generation", &self.generation())
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+/// A RAII guard for the data of a [`GenerationSpinlock`].
+///
+/// Increments the generation of the lock when it is dropped, before the lock is released.
+pub struct GenerationSpinlockGuard<'a, T: ?Sized> {
+    guard: SpinlockGuard<'a, T>,
+    acquired_under: u64,
+    generation: &'a AtomicU64,
+}
+
+impl<T: ?Sized> GenerationSpinlockGuard<'_, T> {
+    /// Returns the generation of the lock at the time this guard acquired it.
+    ///
+    /// This is an associated function to avoid conflicts with methods of `T`. Call it as
+    /// `GenerationSpinlockGuard::generation(&guard)`.
+    pub fn generation(this: &Self) -> u64 {
+        this.acquired_under
+    }
+}
+
+impl<T: ?Sized> Deref for GenerationSpinlockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for GenerationSpinlockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for GenerationSpinlockGuard<'_, T> {
+    fn drop(&mut self) {
+        // the lock is still held, it is released when the `guard` field is dropped afterwards
+        self.generation
+            .store(self.acquired_under.wrapping_add(1), Ordering::Release);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for GenerationSpinlockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread, vec::Vec};
+
+    #[test]
+    fn generation_increases_per_cycle() {
+        let lock = GenerationSpinlock::new(0);
+        for expected in 0..5 {
+            assert_eq!(lock.generation(), expected);
+            // read-only critical sections count as well
+            drop(lock.lock());
+        }
+        drop(lock.try_lock().unwrap());
+        assert_eq!(lock.generation(), 6);
+
+        let guard = lock.lock();
+        assert!(lock.try_lock().is_none());
+        assert_eq!(lock.generation(), 6);
+        drop(guard);
+        assert_eq!(lock.generation(), 7);
+    }
+
+    #[test]
+    fn guard_reports_acquisition_generation() {
+        let lock = GenerationSpinlock::new(0);
+        drop(lock.lock());
+        let mut guard = lock.lock();
+        assert_eq!(GenerationSpinlockGuard::generation(&guard), 1);
+        *guard += 1;
+        drop(guard);
+        let guard = lock.try_lock().unwrap();
+        assert_eq!(GenerationSpinlockGuard::generation(&guard), 2);
+    }
+
+    #[test]
+    fn concurrent_cycles() {
+        const THREADS: u64 = 3;
+        const CYCLES: u64 = 100;
+
+        let lock = Arc::new(GenerationSpinlock::new(()));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..CYCLES {
+                        let guard = lock.lock();
+                        let generation = GenerationSpinlockGuard::generation(&guard);
+                        assert!(generation >= last);
+                        last = generation + 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(lock.generation(), THREADS * CYCLES);
+    }
+}
@@ -0,0 +1,83 @@
+//! A process-wide contention counter, enabled by the `global-stats` feature.
+
+use core::sync::atomic::Ordering;
+
+/// The counter type, which falls back to `AtomicUsize` on targets without 64-bit atomics.
+#[cfg(target_has_atomic = "64")]
+type Counter = core::sync::atomic::AtomicU64;
+#[cfg(not(target_has_atomic = "64"))]
+type Counter = core::sync::atomic::AtomicUsize;
+
+static CONTENTION_COUNT: Counter = Counter::new(0);
+
+/// Returns how many [`RawSpinlock::lock`](lock_api::RawMutex::lock) calls had to spin.
+///
+/// The counter is incremented once for every acquisition whose first attempt failed, across all
+/// [`RawSpinlock`](crate::RawSpinlock)s of the process. This provides a coarse contention metric,
+/// e.g. for health dashboards. It is updated with `Relaxed` operations.
+///
+/// On targets without 64-bit atomics, the counter has the width of `usize` and wraps around on
+/// overflow.
+///
+/// Requires the `global-stats` feature.
+#[allow(clippy::unnecessary_cast)] // the counter is a `usize` without 64-bit atomics
+pub fn global_contention_count() -> u64 {
+    CONTENTION_COUNT.load(Ordering::Relaxed) as u64
+}
+
+/// Resets the counter returned by [`global_contention_count`] to zero.
+///
+/// Requires the `global-stats` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{global_contention_count, reset_global_contention_count};
+///
+/// reset_global_contention_count();
+/// assert_eq!(global_contention_count(), 0);
+/// ```
+pub fn reset_global_contention_count() {
+    CONTENTION_COUNT.store(0, Ordering::Relaxed);
+}
+
+#[inline]
+pub(crate) fn record_contention() {
+    CONTENTION_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::Spinlock;
+    use std::{
+        sync::{Arc, Barrier},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn contended_lock_is_counted() {
+        let before = global_contention_count();
+
+        let spinlock = Arc::new(Spinlock::new(0));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let _guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+            })
+        };
+
+        barrier.wait();
+        *spinlock.lock() += 1;
+        holder.join().unwrap();
+
+        assert!(global_contention_count() > before);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::SpinlockGuard;
+use core::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+/// A [`SpinlockGuard`] that runs a cleanup function after releasing the lock.
+///
+/// Created by [`SpinlockExt::lock_guarded`](crate::SpinlockExt::lock_guarded). When the guard is
+/// dropped, the lock is released first and the cleanup function runs afterwards, so the cleanup
+/// may acquire the same lock again without deadlocking. This also happens if the guard is
+/// dropped while unwinding from a panic, which makes the guard suitable for exception-safe
+/// "flush and release" patterns.
+///
+/// A panic in the cleanup function during unwinding aborts the process, like any panic in a
+/// destructor during unwinding. If the guard is leaked, e.g. through [`core::mem::forget`],
+/// neither the lock is released nor the cleanup function called.
+pub struct GuardedSpinlockGuard<'a, T: ?Sized, F: FnOnce()> {
+    guard: ManuallyDrop<SpinlockGuard<'a, T>>,
+    on_release: ManuallyDrop<F>,
+}
+
+impl<'a, T: ?Sized, F: FnOnce()> GuardedSpinlockGuard<'a, T, F> {
+    pub(crate) fn new(guard: SpinlockGuard<'a, T>, on_release: F) -> Self {
+        Self {
+            guard: ManuallyDrop::new(guard),
+            on_release: ManuallyDrop::new(on_release),
+        }
+    }
+}
+
+impl<T: ?Sized, F: FnOnce()> Deref for GuardedSpinlockGuard<'_, T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized, F: FnOnce()> DerefMut for GuardedSpinlockGuard<'_, T, F> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized, F: FnOnce()> Drop for GuardedSpinlockGuard<'_, T, F> {
+    fn drop(&mut self) {
+        // SAFETY: neither field is used after this point
+        let on_release = unsafe {
+            ManuallyDrop::drop(&mut self.guard);
+            ManuallyDrop::take(&mut self.on_release)
+        };
+        on_release();
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, F: FnOnce()> fmt::Debug for GuardedSpinlockGuard<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use crate::{Spinlock, SpinlockExt};
+    use core::cell::Cell;
+
+    #[test]
+    fn cleanup_after_release() {
+        let spinlock = Spinlock::new(std::vec![1]);
+        let released = Cell::new(false);
+        let mut guard = spinlock.lock_guarded(|| {
+            // the lock is already free and can be taken again
+            spinlock.lock().push(3);
+            released.set(true);
+        });
+        guard.push(2);
+        assert!(!released.get());
+        drop(guard);
+
+        assert!(released.get());
+        assert!(!spinlock.is_locked());
+        assert_eq!(*spinlock.lock(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn cleanup_during_unwinding() {
+        let spinlock = Spinlock::new(0);
+        let lock_was_free = Cell::new(None);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = spinlock.lock_guarded(|| {
+                lock_was_free.set(Some(!spinlock.is_locked()));
+            });
+            *guard += 1;
+            panic!("critical section");
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(lock_was_free.get(), Some(true));
+        assert_eq!(*spinlock.lock(), 1);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Spinlock, SpinlockExt};
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+/// A reference to a [`Spinlock`] that implements [`Hash`], [`PartialEq`], and [`Eq`] by the
+/// identity of the lock.
+///
+/// Two handles are equal if they refer to the same lock, regardless of the protected values.
+/// Comparing and hashing only use the address of the lock and never access the protected data,
+/// so they never lock or block. This makes it possible to keep sets of locks, e.g. in a
+/// registry of held locks. In contrast, [`HashableSpinlock`](crate::HashableSpinlock) compares
+/// the protected values.
+///
+/// The handle dereferences to the [`Spinlock`], so it can be locked as usual.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{LockHandle, Spinlock};
+///
+/// let a = Spinlock::new(1);
+/// let b = Spinlock::new(1);
+/// assert!(LockHandle::new(&a) == LockHandle::new(&a));
+/// assert!(LockHandle::new(&a) != LockHandle::new(&b));
+/// ```
+pub struct LockHandle<'a, T: ?Sized>(&'a Spinlock<T>);
+
+impl<'a, T: ?Sized> LockHandle<'a, T> {
+    /// Creates a new handle for the given lock.
+    pub const fn new(lock: &'a Spinlock<T>) -> Self {
+        Self(lock)
+    }
+
+    /// Returns the referenced lock.
+    pub fn lock_ref(&self) -> &'a Spinlock<T> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Clone for LockHandle<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for LockHandle<'_, T> {}
+
+impl<'a, T: ?Sized> From<&'a Spinlock<T>> for LockHandle<'a, T> {
+    fn from(lock: &'a Spinlock<T>) -> Self {
+        Self::new(lock)
+    }
+}
+
+impl<T: ?Sized> Deref for LockHandle<'_, T> {
+    type Target = Spinlock<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<T: ?Sized> PartialEq for LockHandle<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id() == other.0.id()
+    }
+}
+
+impl<T: ?Sized> Eq for LockHandle<'_, T> {}
+
+impl<T: ?Sized> Hash for LockHandle<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.id().hash(state);
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for LockHandle<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LockHandle").field(&self.0.id()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    // the hash of a handle doesn't depend on the interior mutability of the lock
+    #[allow(clippy::mutable_key_type)]
+    fn hash_set() {
+        let a = Spinlock::new(1);
+        let b = Spinlock::new(1);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(LockHandle::new(&a)));
+        assert!(!set.insert(LockHandle::new(&a)));
+        assert!(set.insert(LockHandle::new(&b)));
+        assert_eq!(set.len(), 2);
+
+        // hashing and comparing doesn't lock
+        let _guard = a.lock();
+        assert!(set.contains(&LockHandle::new(&a)));
+        assert!(set.remove(&LockHandle::from(&b)));
+        assert_eq!(set.len(), 1);
+    }
+}
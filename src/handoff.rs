@@ -0,0 +1,282 @@
+//! A spinlock whose holder can hand the lock over to a designated waiter.
+
+use crate::{relax::relax, spinlock::DefaultGuardMarker};
+use core::{
+    mem,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use lock_api::RawMutex;
+
+/// The state of an unlocked lock.
+const UNLOCKED: usize = 0;
+/// The state of a locked lock. Any other state is the id of the waiter the lock is reserved for.
+const LOCKED: usize = 1;
+/// The id of the first registered waiter.
+const FIRST_WAITER: usize = 2;
+
+/// A raw spinlock that can be released directly to a designated waiter.
+///
+/// Besides the usual [`RawMutex`] operations, a thread can register as a waiter and get a
+/// [`WaiterToken`]. The holder of the lock can then release it through
+/// [`unlock_to`](Self::unlock_to), which reserves the lock for that waiter instead of unlocking
+/// it. While it is reserved, only [`claim`](Self::claim) with the designated token succeeds,
+/// all other acquisitions keep spinning. This is a directed handoff from one thread to a known
+/// other thread, e.g. from a producer to its consumer, as opposed to fair FIFO ordering.
+///
+/// A reserved lock stays reserved until the designated waiter claims it, so a token that is
+/// passed to `unlock_to` must eventually be claimed. Tokens are only meaningful for the lock
+/// that created them.
+#[derive(Debug)]
+pub struct RawHandoffSpinlock {
+    /// [`UNLOCKED`], [`LOCKED`], or the id of the waiter the lock is reserved for.
+    state: AtomicUsize,
+    /// The id of the next registered waiter.
+    next_waiter: AtomicUsize,
+}
+
+/// Identifies a waiter of a [`HandoffSpinlock`] or [`RawHandoffSpinlock`].
+///
+/// Created by [`register_waiter`](RawHandoffSpinlock::register_waiter). The token is not
+/// `Clone`, so only its owner can claim a lock that was handed over to it.
+#[derive(Debug)]
+pub struct WaiterToken {
+    id: usize,
+}
+
+impl RawHandoffSpinlock {
+    /// Registers a new waiter and returns its token.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the waiter ids are exhausted, i.e. after `usize::MAX - 2` registrations.
+    pub fn register_waiter(&self) -> WaiterToken {
+        // never wraps around, so ids are not reused and the states of the lock stay distinct
+        let id = self
+            .next_waiter
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+            .expect("RawHandoffSpinlock waiter id overflow");
+        WaiterToken { id }
+    }
+
+    /// Tries to acquire the lock as the waiter of `token` without spinning.
+    ///
+    /// Succeeds if the lock was handed over to `token` or if it is unlocked.
+    pub fn try_claim(&self, token: &WaiterToken) -> bool {
+        self.state
+            .compare_exchange(token.id, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+            || self.try_lock()
+    }
+
+    /// Acquires the lock as the waiter of `token`, spinning until it was handed over to `token`
+    /// or until it is unlocked.
+    pub fn claim(&self, token: &WaiterToken) {
+        while !self.try_claim(token) {
+            relax();
+        }
+    }
+
+    /// Releases the lock to the waiter of `token`.
+    ///
+    /// The lock stays reserved for that waiter until it calls [`claim`](Self::claim). Writes
+    /// before this call are visible to the waiter after claiming the lock.
+    ///
+    /// ## Safety
+    ///
+    /// The lock must be held in the current context, like for [`RawMutex::unlock`].
+    pub unsafe fn unlock_to(&self, token: &WaiterToken) {
+        self.state.store(token.id, Ordering::Release);
+    }
+}
+
+unsafe impl RawMutex for RawHandoffSpinlock {
+    const INIT: Self = Self {
+        state: AtomicUsize::new(UNLOCKED),
+        next_waiter: AtomicUsize::new(FIRST_WAITER),
+    };
+
+    // A spinlock guard can be sent to another thread and unlocked there, unless the
+    // `no-send-guards` feature is enabled
+    type GuardMarker = DefaultGuardMarker;
+
+    fn lock(&self) {
+        while !self.try_lock() {
+            // Wait until the lock looks unlocked before retrying. A reserved lock doesn't.
+            while self.state.load(Ordering::Relaxed) != UNLOCKED {
+                relax();
+            }
+        }
+    }
+
+    fn try_lock(&self) -> bool {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    unsafe fn unlock(&self) {
+        self.state.store(UNLOCKED, Ordering::Release);
+    }
+
+    fn is_locked(&self) -> bool {
+        // Relaxed is sufficient because this operation does not provide synchronization, only atomicity.
+        // A reserved lock counts as locked.
+        self.state.load(Ordering::Relaxed) != UNLOCKED
+    }
+}
+
+/// A mutual exclusion (Mutex) type based on busy-waiting that supports directed handoffs.
+///
+/// See [`RawHandoffSpinlock`] for the handoff semantics and [`HandoffSpinlockExt`] for the
+/// methods that use them.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{
+///     HandoffSpinlock, HandoffSpinlockExt, HandoffSpinlockGuard, HandoffSpinlockGuardExt,
+/// };
+///
+/// let lock = HandoffSpinlock::new(Vec::new());
+/// let consumer = lock.register_waiter();
+///
+/// let mut guard = lock.lock();
+/// guard.push("item");
+/// HandoffSpinlockGuard::unlock_to(guard, &consumer);
+/// // the lock is reserved for the consumer now
+/// assert!(lock.try_lock().is_none());
+///
+/// let mut guard = lock.claim(&consumer);
+/// assert_eq!(guard.pop(), Some("item"));
+/// ```
+pub type HandoffSpinlock<T> = lock_api::Mutex<RawHandoffSpinlock, T>;
+
+/// A RAII guard that frees the handoff spinlock when it goes out of scope.
+pub type HandoffSpinlockGuard<'a, T> = lock_api::MutexGuard<'a, RawHandoffSpinlock, T>;
+
+/// Methods for registering and claiming as a waiter of a [`HandoffSpinlock`].
+pub trait HandoffSpinlockExt<T: ?Sized> {
+    /// Registers a new waiter and returns its token.
+    ///
+    /// See [`RawHandoffSpinlock::register_waiter`].
+    fn register_waiter(&self) -> WaiterToken;
+
+    /// Acquires the lock as the waiter of `token`.
+    ///
+    /// Spins until the lock was handed over to `token` through
+    /// [`unlock_to`](HandoffSpinlockGuardExt::unlock_to) or until it is unlocked.
+    fn claim(&self, token: &WaiterToken) -> HandoffSpinlockGuard<'_, T>;
+
+    /// Tries to acquire the lock as the waiter of `token` without spinning.
+    ///
+    /// Returns `None` if the lock is held or reserved for another waiter.
+    fn try_claim(&self, token: &WaiterToken) -> Option<HandoffSpinlockGuard<'_, T>>;
+}
+
+impl<T: ?Sized> HandoffSpinlockExt<T> for HandoffSpinlock<T> {
+    fn register_waiter(&self) -> WaiterToken {
+        // SAFETY: the raw lock is only used for registering
+        unsafe { self.raw() }.register_waiter()
+    }
+
+    fn claim(&self, token: &WaiterToken) -> HandoffSpinlockGuard<'_, T> {
+        // SAFETY: the guard is created right after acquiring the lock
+        unsafe {
+            self.raw().claim(token);
+            self.make_guard_unchecked()
+        }
+    }
+
+    fn try_claim(&self, token: &WaiterToken) -> Option<HandoffSpinlockGuard<'_, T>> {
+        // SAFETY: the guard is only created if the lock was acquired
+        unsafe {
+            if self.raw().try_claim(token) {
+                Some(self.make_guard_unchecked())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Additional functions for [`HandoffSpinlockGuard`].
+///
+/// Like the functions of [`SpinlockGuardExt`](crate::SpinlockGuardExt), these are associated
+/// functions. Call them as `HandoffSpinlockGuard::function(guard)`.
+pub trait HandoffSpinlockGuardExt {
+    /// Releases the lock to the waiter of `token`.
+    ///
+    /// The lock stays reserved for that waiter until it calls
+    /// [`claim`](HandoffSpinlockExt::claim), other threads keep spinning in the meantime.
+    fn unlock_to(this: Self, token: &WaiterToken);
+}
+
+impl<T: ?Sized> HandoffSpinlockGuardExt for HandoffSpinlockGuard<'_, T> {
+    fn unlock_to(this: Self, token: &WaiterToken) {
+        let raw = HandoffSpinlockGuard::mutex(&this);
+        mem::forget(this);
+        // SAFETY: the lock was held by the forgotten guard
+        unsafe { raw.raw().unlock_to(token) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread, vec::Vec};
+
+    #[test]
+    fn reserved_lock_edge_states() {
+        let lock = RawHandoffSpinlock::INIT;
+        let a = lock.register_waiter();
+        let b = lock.register_waiter();
+
+        // claiming an unlocked lock works like `try_lock`
+        assert!(lock.try_claim(&a));
+        assert!(!lock.try_claim(&b));
+        unsafe { lock.unlock_to(&b) };
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock());
+        assert!(!lock.try_claim(&a));
+        assert!(lock.try_claim(&b));
+        unsafe { lock.unlock() };
+        assert!(!lock.is_locked());
+    }
+
+    #[test]
+    #[should_panic(expected = "waiter id overflow")]
+    fn waiter_ids_exhausted() {
+        let lock = RawHandoffSpinlock {
+            state: AtomicUsize::new(UNLOCKED),
+            next_waiter: AtomicUsize::new(usize::MAX - 1),
+        };
+        assert_eq!(lock.register_waiter().id, usize::MAX - 1);
+        // the counter is exhausted, the id `usize::MAX` would wrap it around
+        let _ = lock.register_waiter();
+    }
+
+    #[test]
+    fn designated_waiter_claims_first() {
+        let lock = Arc::new(HandoffSpinlock::new(Vec::new()));
+        let guard = lock.lock();
+        let token = lock.register_waiter();
+
+        let other = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.lock().push("other"))
+        };
+        HandoffSpinlockGuard::unlock_to(guard, &token);
+        // the non-designated threads keep waiting
+        assert!(lock.try_lock().is_none());
+
+        let designated = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.claim(&token).push("designated"))
+        };
+        designated.join().unwrap();
+        other.join().unwrap();
+        assert_eq!(*lock.lock(), ["designated", "other"]);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::Spinlock;
+use core::{
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+/// A [`Spinlock`] wrapper that implements [`Hash`], [`PartialEq`], and [`Eq`] by the inner value.
+///
+/// Since [`Spinlock`] is a type alias for [`lock_api::Mutex`], it can't carry these trait
+/// implementations itself. This wrapper allows using spinlock-protected values as keys in hash
+/// maps, e.g. during single-threaded setup code.
+///
+/// Hashing and comparing use `try_lock` to access the inner value and **panic** if the lock is
+/// currently held. Thus, this type should only be used in contexts where the locks are known to be
+/// free when they are hashed or compared.
+///
+/// The wrapper dereferences to the inner [`Spinlock`], so it can be locked as usual.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::HashableSpinlock;
+///
+/// let a = HashableSpinlock::new(1);
+/// let b = HashableSpinlock::new(1);
+/// assert!(a == b);
+///
+/// *a.lock() = 2;
+/// assert!(a != b);
+/// ```
+#[derive(Debug, Default)]
+pub struct HashableSpinlock<T: ?Sized>(Spinlock<T>);
+
+impl<T> HashableSpinlock<T> {
+    /// Creates a new unlocked `HashableSpinlock` wrapping the given value.
+    pub const fn new(val: T) -> Self {
+        Self(Spinlock::new(val))
+    }
+
+    /// Consumes the wrapper, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+impl<T> From<T> for HashableSpinlock<T> {
+    fn from(val: T) -> Self {
+        Self::new(val)
+    }
+}
+
+impl<T: ?Sized> Deref for HashableSpinlock<T> {
+    type Target = Spinlock<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for HashableSpinlock<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0
+            .try_lock()
+            .expect("HashableSpinlock is locked")
+            .hash(state)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for HashableSpinlock<T> {
+    fn eq(&self, other: &Self) -> bool {
+        // locking the same spinlock twice would fail, so compare by address first
+        if core::ptr::eq(self, other) {
+            return true;
+        }
+        let this = self.0.try_lock().expect("HashableSpinlock is locked");
+        let other = other.0.try_lock().expect("HashableSpinlock is locked");
+        *this == *other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for HashableSpinlock<T> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    // the keys are not mutated while they're in the map
+    #[allow(clippy::mutable_key_type)]
+    fn hash_map_keys() {
+        let mut map = HashMap::new();
+        map.insert(HashableSpinlock::new(1), "one");
+        map.insert(HashableSpinlock::new(2), "two");
+        map.insert(HashableSpinlock::new(1), "uno");
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&HashableSpinlock::new(1)), Some(&"uno"));
+        assert_eq!(map.get(&HashableSpinlock::new(2)), Some(&"two"));
+        assert_eq!(map.get(&HashableSpinlock::new(3)), None);
+    }
+
+    #[test]
+    fn eq_with_itself() {
+        let lock = HashableSpinlock::new(42);
+        assert!(lock == lock);
+    }
+
+    #[test]
+    #[should_panic(expected = "HashableSpinlock is locked")]
+    fn eq_while_locked() {
+        let a = HashableSpinlock::new(1);
+        let b = HashableSpinlock::new(1);
+        let _guard = a.lock();
+        let _ = a == b;
+    }
+}
@@ -0,0 +1,111 @@
+//! A process-wide histogram of spin iterations, enabled by the `histogram` feature.
+
+use core::sync::atomic::Ordering;
+
+/// The number of buckets of the [`spin_histogram`].
+pub const SPIN_HISTOGRAM_BUCKETS: usize = 32;
+
+/// The bucket type, which falls back to `AtomicUsize` on targets without 64-bit atomics.
+#[cfg(target_has_atomic = "64")]
+type Bucket = core::sync::atomic::AtomicU64;
+#[cfg(not(target_has_atomic = "64"))]
+type Bucket = core::sync::atomic::AtomicUsize;
+
+#[allow(clippy::declare_interior_mutable_const)] // only used to initialize the static below
+const ZERO: Bucket = Bucket::new(0);
+
+static BUCKETS: [Bucket; SPIN_HISTOGRAM_BUCKETS] = [ZERO; SPIN_HISTOGRAM_BUCKETS];
+
+/// Returns a histogram of the number of spin iterations per lock acquisition.
+///
+/// Every [`RawSpinlock::lock`](lock_api::RawMutex::lock) call of the process is counted in the
+/// bucket given by the bit length of its number of spin iterations: bucket `0` counts
+/// uncontended acquisitions, bucket `1` a single iteration, bucket `2` two or three iterations,
+/// bucket `3` four to seven iterations, and so on. The last bucket also counts all longer spins.
+///
+/// Compared to a single contention counter, the histogram shows how contention is distributed,
+/// e.g. whether acquisitions are usually fast but occasionally very slow. The buckets are
+/// updated with `Relaxed` operations, so the returned values are not a consistent snapshot.
+/// On targets without 64-bit atomics, the buckets have the width of `usize` and wrap around on
+/// overflow.
+///
+/// Requires the `histogram` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{spin_histogram, Spinlock};
+///
+/// let spinlock = Spinlock::new(0);
+/// *spinlock.lock() += 1;
+/// assert!(spin_histogram()[0] > 0);
+/// ```
+#[allow(clippy::unnecessary_cast)] // the buckets are `usize`s without 64-bit atomics
+pub fn spin_histogram() -> [u64; SPIN_HISTOGRAM_BUCKETS] {
+    let mut histogram = [0; SPIN_HISTOGRAM_BUCKETS];
+    for (value, bucket) in histogram.iter_mut().zip(&BUCKETS) {
+        *value = bucket.load(Ordering::Relaxed) as u64;
+    }
+    histogram
+}
+
+fn bucket(spins: usize) -> usize {
+    let bit_length = (usize::BITS - spins.leading_zeros()) as usize;
+    bit_length.min(SPIN_HISTOGRAM_BUCKETS - 1)
+}
+
+#[inline]
+pub(crate) fn record(spins: usize) {
+    BUCKETS[bucket(spins)].fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::Spinlock;
+    use std::{
+        sync::{Arc, Barrier},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn buckets() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(1), 1);
+        assert_eq!(bucket(2), 2);
+        assert_eq!(bucket(3), 2);
+        assert_eq!(bucket(4), 3);
+        assert_eq!(bucket(7), 3);
+        assert_eq!(bucket(1 << 20), 21);
+        assert_eq!(bucket(usize::MAX), SPIN_HISTOGRAM_BUCKETS - 1);
+    }
+
+    #[test]
+    fn records_acquisitions() {
+        let before = spin_histogram();
+        let spinlock = Arc::new(Spinlock::new(0));
+        *spinlock.lock() += 1;
+        assert!(spin_histogram()[0] > before[0]);
+
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let _guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+            })
+        };
+        barrier.wait();
+        let before = spin_histogram();
+        *spinlock.lock() += 1;
+        holder.join().unwrap();
+
+        let after = spin_histogram();
+        assert!((1..SPIN_HISTOGRAM_BUCKETS).any(|i| after[i] > before[i]));
+    }
+}
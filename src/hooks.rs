@@ -0,0 +1,128 @@
+//! Hooks for deterministically reproducing interleavings in tests.
+//!
+//! The hooks are invoked around the `compare_exchange` that tries to acquire a [`RawSpinlock`],
+//! which allows tests to inject `thread::yield_now` calls, delays, or synchronization with other
+//! threads at precise points. This module is only available with the `test-hooks` feature.
+//! Without the feature, the hooks are compiled out completely.
+//!
+//! The hooks are stored per thread, so they only affect lock operations of the thread that
+//! registered them.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use spinning_top::{hooks, Spinlock};
+//!
+//! hooks::set_before_cas(Some(std::thread::yield_now));
+//! let spinlock = Spinlock::new(0);
+//! *spinlock.lock() += 1; // yields before trying to acquire the lock
+//! hooks::set_before_cas(None);
+//! ```
+//!
+//! [`RawSpinlock`]: crate::RawSpinlock
+
+use std::{cell::Cell, thread_local};
+
+thread_local! {
+    static BEFORE_CAS: Cell<Option<fn()>> = Cell::new(None);
+    static AFTER_CAS: Cell<Option<fn()>> = Cell::new(None);
+}
+
+/// Sets the hook that is called right before each attempt to acquire a lock on this thread.
+///
+/// Pass `None` to remove the hook.
+pub fn set_before_cas(hook: Option<fn()>) {
+    BEFORE_CAS.with(|h| h.set(hook));
+}
+
+/// Sets the hook that is called right after each attempt to acquire a lock on this thread.
+///
+/// The hook is called regardless of whether the attempt succeeded. Pass `None` to remove the hook.
+pub fn set_after_cas(hook: Option<fn()>) {
+    AFTER_CAS.with(|h| h.set(hook));
+}
+
+pub(crate) fn before_cas() {
+    if let Some(hook) = BEFORE_CAS.with(Cell::get) {
+        hook();
+    }
+}
+
+pub(crate) fn after_cas() {
+    if let Some(hook) = AFTER_CAS.with(Cell::get) {
+        hook();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RawSpinlock;
+    use core::{
+        hint,
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+    use lock_api::RawMutex;
+    use std::thread;
+
+    static LOCK: RawSpinlock = RawSpinlock::INIT;
+    static ABOUT_TO_CAS: AtomicBool = AtomicBool::new(false);
+    static OTHER_ACQUIRED: AtomicBool = AtomicBool::new(false);
+    static AFTER_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn wait_for(flag: &AtomicBool) {
+        while !flag.load(Ordering::Acquire) {
+            hint::spin_loop();
+        }
+    }
+
+    fn let_other_thread_acquire() {
+        ABOUT_TO_CAS.store(true, Ordering::Release);
+        wait_for(&OTHER_ACQUIRED);
+    }
+
+    fn count_after() {
+        AFTER_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn force_contended_interleaving() {
+        let other = thread::spawn(|| {
+            wait_for(&ABOUT_TO_CAS);
+            assert!(LOCK.try_lock());
+            OTHER_ACQUIRED.store(true, Ordering::Release);
+        });
+
+        // the other thread acquires the lock between our check and our compare_exchange
+        set_before_cas(Some(let_other_thread_acquire));
+        set_after_cas(Some(count_after));
+        assert!(!LOCK.is_locked());
+        assert!(!LOCK.try_lock());
+        set_before_cas(None);
+        assert_eq!(AFTER_CALLS.load(Ordering::Relaxed), 1);
+
+        other.join().unwrap();
+        unsafe { LOCK.unlock() };
+        assert!(LOCK.try_lock());
+        assert_eq!(AFTER_CALLS.load(Ordering::Relaxed), 2);
+        set_after_cas(None);
+        unsafe { LOCK.unlock() };
+    }
+
+    #[test]
+    fn hooks_around_acquire_on_fail() {
+        static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        fn count_before() {
+            BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let lock = RawSpinlock::INIT;
+        set_before_cas(Some(count_before));
+        assert!(lock.try_lock_acquire_on_fail());
+        assert!(!lock.try_lock_acquire_on_fail());
+        set_before_cas(None);
+        assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 2);
+        unsafe { lock.unlock() };
+    }
+}
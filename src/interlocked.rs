@@ -0,0 +1,193 @@
+use crate::{relax::relax, Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Neither side is held.
+const IDLE: u8 = 0;
+/// Side A is held.
+const SIDE_A: u8 = 1;
+/// Side B is held.
+const SIDE_B: u8 = 2;
+
+/// Two spinlocks of which at most one can be held at a time.
+///
+/// This models mutually exclusive modes of a resource, e.g. transmitting and receiving on a
+/// half-duplex bus. A single state word records which side, if any, is active. Locking a side
+/// fails with `None` while the other side is held, and it spins while the same side is held by
+/// someone else.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::InterlockedPair;
+///
+/// let bus = InterlockedPair::new(Vec::<u8>::new(), Vec::<u8>::new());
+/// let mut tx = bus.lock_a().unwrap();
+/// tx.push(0x42);
+/// assert!(bus.lock_b().is_none()); // can't receive while transmitting
+///
+/// drop(tx);
+/// assert!(bus.lock_b().unwrap().is_empty());
+/// ```
+pub struct InterlockedPair<A, B> {
+    /// Which side is active, one of [`IDLE`], [`SIDE_A`], and [`SIDE_B`].
+    state: AtomicU8,
+    a: Spinlock<A>,
+    b: Spinlock<B>,
+}
+
+impl<A, B> InterlockedPair<A, B> {
+    /// Creates a new pair with neither side held.
+    pub const fn new(a: A, b: B) -> Self {
+        Self {
+            state: AtomicU8::new(IDLE),
+            a: Spinlock::new(a),
+            b: Spinlock::new(b),
+        }
+    }
+
+    /// Locks side A, spinning while side A is held elsewhere.
+    ///
+    /// Returns `None` if side B is held.
+    pub fn lock_a(&self) -> Option<InterlockedGuard<'_, A>> {
+        self.activate(SIDE_A, SIDE_B)?;
+        Some(InterlockedGuard::new(self.a.lock(), &self.state))
+    }
+
+    /// Locks side B, spinning while side B is held elsewhere.
+    ///
+    /// Returns `None` if side A is held.
+    pub fn lock_b(&self) -> Option<InterlockedGuard<'_, B>> {
+        self.activate(SIDE_B, SIDE_A)?;
+        Some(InterlockedGuard::new(self.b.lock(), &self.state))
+    }
+
+    /// Consumes the pair, returning the data of both sides.
+    pub fn into_inner(self) -> (A, B) {
+        (self.a.into_inner(), self.b.into_inner())
+    }
+
+    /// Marks `side` as active, spinning while it is active already.
+    ///
+    /// Fails if `other` is active. The inner spinlock of `side` is only locked after this
+    /// succeeded, so locking it never spins.
+    fn activate(&self, side: u8, other: u8) -> Option<()> {
+        loop {
+            match self
+                .state
+                .compare_exchange_weak(IDLE, side, Ordering::Acquire, Ordering::Relaxed)
+            {
+                Ok(_) => return Some(()),
+                Err(current) if current == other => return None,
+                Err(_) => relax(),
+            }
+        }
+    }
+}
+
+impl<A: fmt::Debug, B: fmt::Debug> fmt::Debug for InterlockedPair<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterlockedPair")
+            .field("a", &&self.a)
+            .field("b", &&self.b)
+            .finish()
+    }
+}
+
+/// A guard for one side of an [`InterlockedPair`].
+///
+/// Releases the side when dropped, which allows locking the other side again.
+pub struct InterlockedGuard<'a, T> {
+    guard: ManuallyDrop<SpinlockGuard<'a, T>>,
+    state: &'a AtomicU8,
+}
+
+impl<'a, T> InterlockedGuard<'a, T> {
+    fn new(guard: SpinlockGuard<'a, T>, state: &'a AtomicU8) -> Self {
+        Self {
+            guard: ManuallyDrop::new(guard),
+            state,
+        }
+    }
+}
+
+impl<T> Deref for InterlockedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for InterlockedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for InterlockedGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard is not used after this point
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        // the inner lock is released first, so the next holder of this side never spins on it
+        self.state.store(IDLE, Ordering::Release);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for InterlockedGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+    use std::{sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn sides_exclude_each_other() {
+        let pair = InterlockedPair::new(1, "b");
+        let a = pair.lock_a().unwrap();
+        assert!(pair.lock_b().is_none());
+        drop(a);
+
+        let b = pair.lock_b().unwrap();
+        assert_eq!(*b, "b");
+        assert!(pair.lock_a().is_none());
+        drop(b);
+
+        *pair.lock_a().unwrap() += 1;
+        assert_eq!(pair.into_inner(), (2, "b"));
+    }
+
+    #[test]
+    fn same_side_waits() {
+        let pair = Arc::new(InterlockedPair::new(0, ()));
+        let guard = pair.lock_a().unwrap();
+
+        let acquired = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let pair = pair.clone();
+            let acquired = acquired.clone();
+            thread::spawn(move || {
+                let mut guard = pair.lock_a().expect("side A is not blocked by itself");
+                acquired.store(true, Ordering::Relaxed);
+                *guard += 1;
+            })
+        };
+
+        thread::sleep(Duration::from_millis(10));
+        assert!(!acquired.load(Ordering::Relaxed));
+        drop(guard);
+        waiter.join().unwrap();
+        assert_eq!(*pair.lock_a().unwrap(), 1);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::relax::relax;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A one-shot gate that blocks waiting threads until it is opened.
+///
+/// Threads calling [`wait`](Self::wait) spin until some thread calls [`open`](Self::open).
+/// Once opened, the latch stays open forever, so all current and future waiters pass
+/// immediately. Unlike a barrier, the latch doesn't count arrivals and can't be reset.
+///
+/// Opening the latch has `Release` semantics and passing it has `Acquire` semantics, so all
+/// writes before `open` are visible to the threads that return from `wait`. This makes the latch
+/// suitable for signaling that an initialization is complete.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::SpinLatch;
+///
+/// static READY: SpinLatch = SpinLatch::new();
+///
+/// let waiter = std::thread::spawn(|| READY.wait());
+/// READY.open();
+/// waiter.join().unwrap();
+/// assert!(READY.is_open());
+/// ```
+#[derive(Debug, Default)]
+pub struct SpinLatch {
+    open: AtomicBool,
+}
+
+impl SpinLatch {
+    /// Creates a new closed latch.
+    pub const fn new() -> Self {
+        Self {
+            open: AtomicBool::new(false),
+        }
+    }
+
+    /// Opens the latch, releasing all current and future waiters.
+    ///
+    /// Opening an already open latch has no effect.
+    pub fn open(&self) {
+        self.open.store(true, Ordering::Release);
+    }
+
+    /// Returns whether the latch is open.
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Acquire)
+    }
+
+    /// Spins until the latch is open.
+    pub fn wait(&self) {
+        while !self.is_open() {
+            relax();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        vec::Vec,
+    };
+
+    #[test]
+    fn waiters_proceed_after_open() {
+        const WAITERS: usize = 4;
+
+        let latch = Arc::new(SpinLatch::new());
+        let passed = Arc::new(AtomicUsize::new(0));
+        let waiters: Vec<_> = (0..WAITERS)
+            .map(|_| {
+                let latch = latch.clone();
+                let passed = passed.clone();
+                thread::spawn(move || {
+                    latch.wait();
+                    passed.fetch_add(1, Ordering::Relaxed);
+                })
+            })
+            .collect();
+
+        thread::yield_now();
+        assert!(!latch.is_open());
+        assert_eq!(passed.load(Ordering::Relaxed), 0);
+
+        latch.open();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        assert_eq!(passed.load(Ordering::Relaxed), WAITERS);
+
+        // late waiters pass immediately
+        latch.wait();
+        assert!(latch.is_open());
+    }
+}
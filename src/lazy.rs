@@ -0,0 +1,195 @@
+use crate::{MappedSpinlockGuard, Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A [`Spinlock`] whose data is initialized on first access.
+///
+/// This allows placing data in a `static` that has no `const` constructor. The data is stored
+/// inline as a [`MaybeUninit`], so no allocation is needed. The first call of
+/// [`get_or_init`](Self::get_or_init) runs the initialization function while holding the lock,
+/// so concurrent callers wait for it and the data is initialized exactly once. All calls return
+/// a guard that gives access to the initialized data.
+///
+/// If the initialization function panics, the lock is released and the data stays
+/// uninitialized, so the next call of `get_or_init` runs its initialization function.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::LazySpinlock;
+/// use std::collections::HashMap;
+///
+/// static REGISTRY: LazySpinlock<HashMap<u32, &str>> = LazySpinlock::new();
+///
+/// REGISTRY.get_or_init(HashMap::new).insert(1, "one");
+/// assert_eq!(REGISTRY.get_or_init(|| unreachable!())[&1], "one");
+/// ```
+pub struct LazySpinlock<T> {
+    /// Whether the data is initialized. Only modified while holding the lock.
+    initialized: AtomicBool,
+    lock: Spinlock<MaybeUninit<T>>,
+}
+
+impl<T> LazySpinlock<T> {
+    /// Creates a new unlocked and uninitialized `LazySpinlock`.
+    pub const fn new() -> Self {
+        Self {
+            initialized: AtomicBool::new(false),
+            lock: Spinlock::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Locks the data, initializing it through `f` first if it is not initialized yet.
+    ///
+    /// `f` runs while the lock is held, so other callers spin until the initialization is
+    /// complete. If several threads find the data uninitialized at the same time, only the
+    /// first one to acquire the lock runs its `f`; the others get a guard to the data it
+    /// initialized. For global state that is initialized lazily and then locked for each use,
+    /// this is the only call needed.
+    ///
+    /// The data is stored as a [`MaybeUninit`], so the guard is a [`MappedSpinlockGuard`]
+    /// rather than a [`SpinlockGuard`].
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> MappedSpinlockGuard<'_, T> {
+        let mut guard = self.lock.lock();
+        if !self.initialized.load(Ordering::Relaxed) {
+            guard.write(f());
+            self.initialized.store(true, Ordering::Release);
+        }
+        // SAFETY: the data was initialized above or by an earlier call
+        SpinlockGuard::map(guard, |data| unsafe { &mut *data.as_mut_ptr() })
+    }
+
+    /// Locks the data if it is initialized.
+    ///
+    /// Returns `None` without waiting for the lock if the data is not initialized yet.
+    pub fn get(&self) -> Option<MappedSpinlockGuard<'_, T>> {
+        if !self.is_initialized() {
+            return None;
+        }
+        // the data is never deinitialized, so it is still initialized after locking
+        let guard = self.lock.lock();
+        // SAFETY: the data is initialized
+        Some(SpinlockGuard::map(guard, |data| unsafe {
+            &mut *data.as_mut_ptr()
+        }))
+    }
+
+    /// Returns whether the data is initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::Acquire)
+    }
+
+    /// Consumes the lock, returning the data if it is initialized.
+    pub fn into_inner(mut self) -> Option<T> {
+        if *self.initialized.get_mut() {
+            *self.initialized.get_mut() = false;
+            // SAFETY: the data is initialized and the flag was reset, so it isn't dropped again
+            Some(unsafe { self.lock.get_mut().as_ptr().read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Default for LazySpinlock<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for LazySpinlock<T> {
+    fn drop(&mut self) {
+        if *self.initialized.get_mut() {
+            // SAFETY: the data is initialized
+            unsafe { self.lock.get_mut().as_mut_ptr().drop_in_place() };
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LazySpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("LazySpinlock");
+        if !self.is_initialized() {
+            d.field("data", &format_args!("<uninit>"));
+        } else if let Some(guard) = self.lock.try_lock() {
+            // SAFETY: the data is initialized
+            d.field("data", unsafe { &*guard.as_ptr() });
+        } else {
+            d.field("data", &format_args!("<locked>"));
+        }
+        d.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use std::{string::String, sync::Arc, thread, vec::Vec};
+
+    #[test]
+    fn init_once() {
+        let lock = LazySpinlock::new();
+        assert!(lock.get().is_none());
+        *lock.get_or_init(|| 1) += 1;
+        assert!(lock.is_initialized());
+        assert_eq!(*lock.get_or_init(|| unreachable!()), 2);
+        assert_eq!(*lock.get().unwrap(), 2);
+        assert_eq!(lock.into_inner(), Some(2));
+        assert_eq!(LazySpinlock::<u32>::new().into_inner(), None);
+    }
+
+    #[test]
+    fn concurrent_first_callers() {
+        const THREADS: usize = 4;
+        let inits = Arc::new(AtomicUsize::new(0));
+        let lock = Arc::new(LazySpinlock::new());
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let inits = inits.clone();
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    let mut guard = lock.get_or_init(|| {
+                        inits.fetch_add(1, Ordering::Relaxed);
+                        String::from("init")
+                    });
+                    assert!(guard.starts_with("init"));
+                    guard.push('!');
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(inits.load(Ordering::Relaxed), 1);
+        assert_eq!(*lock.get().unwrap(), "init!!!!");
+    }
+
+    #[test]
+    fn init_panic() {
+        let lock = LazySpinlock::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            drop(lock.get_or_init(|| panic!("init")));
+        }));
+        assert!(result.is_err());
+        assert!(!lock.is_initialized());
+        assert!(!lock.lock.is_locked());
+        assert_eq!(*lock.get_or_init(|| 5), 5);
+    }
+
+    #[test]
+    fn drops_data() {
+        let data = Arc::new(());
+        let lock = LazySpinlock::new();
+        drop(lock.get_or_init(|| data.clone()));
+        assert_eq!(Arc::strong_count(&data), 2);
+        drop(lock);
+        assert_eq!(Arc::strong_count(&data), 1);
+    }
+}
@@ -29,9 +29,194 @@
 #![warn(missing_docs)]
 #![warn(missing_debug_implementations)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 /// The spinlock implemenation is based on the abstractions provided by the `lock_api` crate.
 pub use lock_api;
 
-pub use spinlock::{const_spinlock, RawSpinlock, Spinlock, SpinlockGuard};
+pub use accounted::AccountedGuard;
+#[cfg(all(feature = "pin-core", target_os = "linux"))]
+pub use affinity::PinnedSpinlockGuard;
+pub use atomic::{AtomicSpinlock, SmallCopy};
+pub use bounded::BoundedSpinlock;
+pub use bounded_rw::{
+    BoundedRwSpinlock, BoundedRwSpinlockExt, BoundedRwSpinlockReadGuard,
+    BoundedRwSpinlockWriteGuard, RawBoundedRwSpinlock,
+};
+pub use channel::SpinChannel;
+pub use checked::{checked_spinlock, NoInteriorMutability};
+pub use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+pub use compat::StdCompatSpinlock;
+#[cfg(feature = "critical-section")]
+pub use cs::{CsSpinlock, CsSpinlockGuard, RawCsSpinlock};
+#[cfg(feature = "alloc")]
+pub use drain::DrainGuard;
+#[cfg(all(feature = "tsx", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use elision::{ElidedSpinlock, ElidedSpinlockGuard, RawElidedSpinlock};
+pub use exchanger::SpinExchanger;
+pub use exclusive::ExclusiveLock;
+pub use ext::{
+    ArraySpinlockExt, BorrowError, Deadline, OptionSpinlockExt, SpinlockExt, SpinlockGuardExt,
+};
+#[cfg(feature = "alloc")]
+pub use ext::{VecDequeSpinlockExt, VecSpinlockExt};
+#[cfg(feature = "ffi")]
+pub use ffi::{spinlock_try_lock, spinlock_unlock};
+pub use fmt_write::FmtSpinlock;
+#[cfg(target_has_atomic = "64")]
+pub use generation::{GenerationSpinlock, GenerationSpinlockGuard};
+#[cfg(feature = "global-stats")]
+pub use global_stats::{global_contention_count, reset_global_contention_count};
+pub use guarded::GuardedSpinlockGuard;
+pub use handle::LockHandle;
+pub use handoff::{
+    HandoffSpinlock, HandoffSpinlockExt, HandoffSpinlockGuard, HandoffSpinlockGuardExt,
+    RawHandoffSpinlock, WaiterToken,
+};
+pub use hashable::HashableSpinlock;
+#[cfg(feature = "histogram")]
+pub use histogram::{spin_histogram, SPIN_HISTOGRAM_BUCKETS};
+pub use interlocked::{InterlockedGuard, InterlockedPair};
+pub use latch::SpinLatch;
+pub use lazy::LazySpinlock;
+pub use multi::{
+    lock_all_tuple, lock_coupling, lock_either, move_between, snapshot_all, try_lock_either,
+    Either, LockTuple, SnapshotTuple,
+};
+#[cfg(feature = "alloc")]
+pub use multi::{try_lock_all, try_lock_mask, unlock_all};
+pub use named::NamedSpinlock;
+pub use oneshot::{OneshotSender, SpinOneshot};
+#[cfg(target_has_atomic = "64")]
+pub use optimistic::{OptimisticSpinlock, OptimisticSpinlockGuard};
+pub use phase_fair::{
+    PhaseFairRwSpinlock, PhaseFairRwSpinlockReadGuard, PhaseFairRwSpinlockWriteGuard,
+    RawPhaseFairRwSpinlock,
+};
+pub use pool::{SpinPool, SpinPoolGuard};
+pub use preempt::{
+    NoPreemptionControl, PreemptSpinlock, PreemptSpinlockGuard, PreemptionControl,
+    RawPreemptSpinlock,
+};
+pub use priority::PrioritySpinlock;
+#[cfg(feature = "std")]
+pub use reentrant_rw::{
+    RawReentrantRwSpinlock, ReentrantRwSpinlock, ReentrantRwSpinlockReadGuard,
+    ReentrantRwSpinlockWriteGuard,
+};
+#[cfg(feature = "panic-on-long-spin")]
+pub use relax::set_long_spin_limit;
+#[cfg(feature = "dynamic-relax")]
+pub use relax::set_relax_fn;
+#[cfg(feature = "env-config")]
+pub use relax::{default_spin_strategy, SpinStrategy};
+pub use scalable_rw::{
+    RawScalableRwSpinlock, ScalableRwSpinlock, ScalableRwSpinlockReadGuard,
+    ScalableRwSpinlockWriteGuard,
+};
+pub use semaphore::SpinSemaphore;
+pub use signal::{SignalSafeSpinlock, SignalSafeSpinlockGuard};
+#[cfg(feature = "alloc")]
+pub use slot::get_or_init_lock;
+#[cfg(feature = "arc_lock")]
+pub use spinlock::ArcSpinlockGuard;
+#[cfg(feature = "contention-hint")]
+pub use spinlock::ContentionLevel;
+pub use spinlock::{
+    const_spinlock, from_raw_parts, into_inner, into_raw_parts, lock_ptr, MappedSpinlockGuard,
+    RawSpinlock, Spinlock, SpinlockGuard,
+};
+pub use split::{map_split, SplitSpinlockGuard};
+#[cfg(all(
+    feature = "tsc-backoff",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+pub use strategy::TscBackoff;
+#[cfg(feature = "std")]
+pub use strategy::{spin_yield_threshold, SpinThenYield};
+pub use strategy::{
+    ExponentialPause, RawStrategySpinlock, RelaxStrategy, Spin, StrategySpinlock,
+    StrategySpinlockGuard,
+};
+#[cfg(feature = "timing")]
+pub use timing::{set_handoff_sink, set_hold_time_sink, TimedSpinlockGuard};
+pub use typed_view::{assume_init, TypedView, TypedViewGuard};
+#[cfg(target_has_atomic = "64")]
+pub use versioned::{DirtyTrackedGuard, VersionedSpinlock};
+pub use wait_queue::{WaitNode, WaitQueue};
+#[cfg(feature = "std")]
+pub use write::WriteSpinlock;
 
+mod accounted;
+#[cfg(all(feature = "pin-core", target_os = "linux"))]
+mod affinity;
+mod atomic;
+mod bounded;
+mod bounded_rw;
+mod channel;
+mod checked;
+mod codec;
+#[cfg(feature = "std")]
+mod compat;
+#[cfg(feature = "critical-section")]
+mod cs;
+#[cfg(feature = "alloc")]
+mod drain;
+#[cfg(all(feature = "tsx", any(target_arch = "x86", target_arch = "x86_64")))]
+mod elision;
+mod exchanger;
+mod exclusive;
+mod ext;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fmt_write;
+#[cfg(target_has_atomic = "64")]
+mod generation;
+#[cfg(feature = "global-stats")]
+mod global_stats;
+mod guarded;
+mod handle;
+mod handoff;
+mod hashable;
+#[cfg(feature = "histogram")]
+mod histogram;
+#[cfg(feature = "test-hooks")]
+pub mod hooks;
+mod interlocked;
+mod latch;
+mod lazy;
+mod macros;
+mod multi;
+mod named;
+mod oneshot;
+#[cfg(target_has_atomic = "64")]
+mod optimistic;
+#[cfg(feature = "owner-tracking")]
+mod owner;
+mod phase_fair;
+mod pool;
+mod preempt;
+mod priority;
+#[cfg(feature = "std")]
+mod reentrant_rw;
+mod relax;
+mod scalable_rw;
+mod semaphore;
+mod signal;
+#[cfg(feature = "alloc")]
+mod slot;
 mod spinlock;
+mod split;
+mod strategy;
+#[cfg(feature = "timing")]
+mod timing;
+mod typed_view;
+#[cfg(target_has_atomic = "64")]
+mod versioned;
+mod wait_queue;
+#[cfg(feature = "std")]
+mod write;
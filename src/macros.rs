@@ -0,0 +1,94 @@
+/// Defines a static spinlock.
+///
+/// The lock is created through [`const_spinlock`](crate::const_spinlock), so the initializer
+/// must be a constant expression. Attributes, e.g. doc comments, and a visibility are
+/// forwarded to the static. There are three forms:
+///
+/// - `spinlock_static!(NAME: Spinlock<T> = init);` defines a `static NAME: Spinlock<T>`.
+/// - `spinlock_static!(NAME: NamedSpinlock<T> = init);` defines a
+///   `static NAME: NamedSpinlock<T>` whose [`name`](crate::NamedSpinlock::name) is the name of the
+///   static, e.g. `"NAME"`.
+/// - `spinlock_static!(NAME: Spinlock<T> = init, align = N);` places the spinlock at an address
+///   that is a multiple of `N`, e.g. to keep frequently used locks on separate cache lines. Since
+///   the alignment of a type can't be set generically, the static is a
+///   `&'static Spinlock<T>` that points to the aligned lock. Thanks to auto-deref, it is used
+///   just like the other forms.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::spinlock_static;
+///
+/// struct SerialPort(u16);
+///
+/// impl SerialPort {
+///     const fn new() -> Self {
+///         SerialPort(0x3F8)
+///     }
+/// }
+///
+/// spinlock_static!(
+///     /// The first serial port.
+///     pub SERIAL: Spinlock<SerialPort> = SerialPort::new()
+/// );
+/// spinlock_static!(KEYBOARD: NamedSpinlock<Vec<u8>> = Vec::new());
+/// spinlock_static!(COUNTER: Spinlock<u64> = 0, align = 64);
+///
+/// assert_eq!(SERIAL.lock().0, 0x3F8);
+/// assert_eq!(KEYBOARD.name(), "KEYBOARD");
+/// *COUNTER.lock() += 1;
+/// assert_eq!(&*COUNTER as *const _ as usize % 64, 0);
+/// ```
+#[macro_export]
+macro_rules! spinlock_static {
+    ($(#[$attr:meta])* $vis:vis $name:ident: Spinlock<$t:ty> = $init:expr $(,)?) => {
+        $(#[$attr])*
+        $vis static $name: $crate::Spinlock<$t> = $crate::const_spinlock($init);
+    };
+    ($(#[$attr:meta])* $vis:vis $name:ident: NamedSpinlock<$t:ty> = $init:expr $(,)?) => {
+        $(#[$attr])*
+        $vis static $name: $crate::NamedSpinlock<$t> =
+            $crate::NamedSpinlock::new(stringify!($name), $init);
+    };
+    ($(#[$attr:meta])* $vis:vis $name:ident: Spinlock<$t:ty> = $init:expr, align = $align:literal $(,)?) => {
+        $(#[$attr])*
+        $vis static $name: &$crate::Spinlock<$t> = {
+            #[repr(align($align))]
+            struct Aligned($crate::Spinlock<$t>);
+            static ALIGNED: Aligned = Aligned($crate::const_spinlock($init));
+            &ALIGNED.0
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    crate::spinlock_static!(PLAIN: Spinlock<u32> = 1);
+    crate::spinlock_static!(
+        /// A named lock.
+        pub(crate) NAMED: NamedSpinlock<u32> = 2,
+    );
+    crate::spinlock_static!(ALIGNED: Spinlock<u8> = 3, align = 128);
+
+    #[test]
+    fn plain() {
+        *PLAIN.lock() += 1;
+        assert!(!PLAIN.is_locked());
+        assert_eq!(*PLAIN.lock(), 2);
+    }
+
+    #[test]
+    fn named() {
+        assert_eq!(NAMED.name(), "NAMED");
+        assert_eq!(*NAMED.lock(), 2);
+    }
+
+    #[test]
+    fn aligned() {
+        assert_eq!(ALIGNED as *const _ as usize % 128, 0);
+        let guard = ALIGNED.lock();
+        assert!(ALIGNED.try_lock().is_none());
+        drop(guard);
+        assert_eq!(*ALIGNED.lock(), 3);
+    }
+}
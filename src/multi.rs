@@ -0,0 +1,679 @@
+//! Helpers for working with multiple spinlocks at once.
+
+use crate::{relax::relax, RawSpinlock, Spinlock, SpinlockGuard};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::mem;
+use lock_api::RawMutex;
+
+/// A value of one of two possible types.
+///
+/// Returned by [`try_lock_either`] and [`lock_either`] to tell the caller which of the two
+/// locks was acquired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<A, B> {
+    /// The first (left) alternative.
+    Left(A),
+    /// The second (right) alternative.
+    Right(B),
+}
+
+/// Tries to lock `a`, then `b`, without blocking.
+///
+/// Returns the guard of the first lock that could be acquired, or `None` if both locks are
+/// currently held.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{try_lock_either, Either, Spinlock};
+///
+/// let a = Spinlock::new(1);
+/// let b = Spinlock::new(2);
+///
+/// let guard_a = a.lock();
+/// match try_lock_either(&a, &b) {
+///     Some(Either::Right(guard)) => assert_eq!(*guard, 2),
+///     _ => unreachable!(),
+/// };
+/// ```
+pub fn try_lock_either<'a, T: ?Sized>(
+    a: &'a Spinlock<T>,
+    b: &'a Spinlock<T>,
+) -> Option<Either<SpinlockGuard<'a, T>, SpinlockGuard<'a, T>>> {
+    if let Some(guard) = a.try_lock() {
+        Some(Either::Left(guard))
+    } else {
+        b.try_lock().map(Either::Right)
+    }
+}
+
+/// Locks whichever of `a` and `b` becomes available first.
+///
+/// Spins while alternating attempts on both locks until one of them succeeds. The returned
+/// [`Either`] tells which lock was acquired.
+pub fn lock_either<'a, T: ?Sized>(
+    a: &'a Spinlock<T>,
+    b: &'a Spinlock<T>,
+) -> Either<SpinlockGuard<'a, T>, SpinlockGuard<'a, T>> {
+    loop {
+        if let Some(guard) = try_lock_either(a, b) {
+            return guard;
+        }
+        relax();
+    }
+}
+
+/// Locks `parent`, derives a child lock from its data, and locks the child as well.
+///
+/// This is the lock coupling (or hand-over-hand locking) primitive for traversing a tree of
+/// spinlock-protected nodes: the parent stays locked while the child is acquired, so the child
+/// can't be unlinked from the parent in between. Both guards are returned; dropping the parent
+/// guard first releases the parent while keeping the child locked.
+///
+/// The child lock must outlive `'a` independently of the parent's data, e.g. because the nodes
+/// live in an arena and the parent stores a `&'a Spinlock<C>` to its child. A child lock owned
+/// by the parent's data, e.g. in a `Box`, can't be returned, since it could be freed as soon as
+/// the parent is unlocked.
+///
+/// To avoid deadlocks, all threads must lock the nodes in the same order, i.e. from parents to
+/// children.
+///
+/// ## Panics
+///
+/// Panics if `get_child` returns `parent` itself, which would otherwise deadlock. If
+/// `get_child` panics, the parent is released.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{lock_coupling, Spinlock};
+///
+/// struct Dir<'a> {
+///     entries: u32,
+///     child: &'a Spinlock<u32>,
+/// }
+///
+/// let file = Spinlock::new(0);
+/// let dir = Spinlock::new(Dir { entries: 1, child: &file });
+///
+/// let (dir_guard, mut file_guard) = lock_coupling(&dir, |dir| dir.child);
+/// *file_guard += dir_guard.entries;
+/// drop(dir_guard);
+/// // the child is still locked
+/// assert!(file.try_lock().is_none());
+/// ```
+pub fn lock_coupling<'a, P: ?Sized, C: ?Sized>(
+    parent: &'a Spinlock<P>,
+    get_child: impl FnOnce(&mut P) -> &'a Spinlock<C>,
+) -> (SpinlockGuard<'a, P>, SpinlockGuard<'a, C>) {
+    let mut parent_guard = parent.lock();
+    let child = get_child(&mut parent_guard);
+    // SAFETY: the raw locks are only used for comparing their identities
+    let (parent_id, child_id) = unsafe { (parent.raw().id(), child.raw().id()) };
+    assert_ne!(
+        parent_id, child_id,
+        "the child lock of `lock_coupling` is the parent lock"
+    );
+    (parent_guard, child.lock())
+}
+
+/// Tries to lock all given spinlocks without blocking, with all-or-nothing semantics.
+///
+/// The locks are tried in order. If any of them is currently held, all previously acquired
+/// locks are released again and `None` is returned. Thus, this function never holds a partial
+/// set of the locks when it returns.
+///
+/// Requires the `alloc` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{try_lock_all, Spinlock};
+///
+/// let shards = [Spinlock::new(0), Spinlock::new(1), Spinlock::new(2)];
+///
+/// let guards = try_lock_all(&shards).unwrap();
+/// assert_eq!(guards.len(), 3);
+/// drop(guards);
+///
+/// let _guard = shards[1].lock();
+/// assert!(try_lock_all(&shards).is_none());
+/// assert!(!shards[0].is_locked());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn try_lock_all<T>(locks: &[Spinlock<T>]) -> Option<Vec<SpinlockGuard<'_, T>>> {
+    // collecting into an `Option` stops at the first `None` and drops the acquired guards
+    locks.iter().map(|lock| lock.try_lock()).collect()
+}
+
+/// Tries to lock each of the given spinlocks without blocking and reports which ones succeeded.
+///
+/// Unlike [`try_lock_all`], the acquisitions are independent: every spinlock is tried once and
+/// the acquired ones stay locked. The returned vector has one entry per spinlock, which is the
+/// guard if the spinlock was acquired and `None` otherwise. Bit `i` of the returned mask is set
+/// if the spinlock at index `i` was acquired, so the caller can quickly decide which subset to
+/// process, e.g. through `mask.count_ones()` or `mask.trailing_zeros()`.
+///
+/// Requires the `alloc` feature.
+///
+/// ## Panics
+///
+/// Panics if more than 64 spinlocks are given, since the mask has only 64 bits.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{try_lock_mask, Spinlock};
+///
+/// let queues = [Spinlock::new(0), Spinlock::new(1), Spinlock::new(2)];
+/// let _busy = queues[1].lock();
+///
+/// let (guards, mask) = try_lock_mask(&queues);
+/// assert_eq!(mask, 0b101);
+/// assert!(guards[1].is_none());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn try_lock_mask<T>(locks: &[Spinlock<T>]) -> (Vec<Option<SpinlockGuard<'_, T>>>, u64) {
+    assert!(
+        locks.len() <= 64,
+        "`try_lock_mask` supports at most 64 locks, got {}",
+        locks.len()
+    );
+    let mut mask = 0;
+    let guards = locks
+        .iter()
+        .enumerate()
+        .map(|(i, lock)| {
+            let guard = lock.try_lock();
+            if guard.is_some() {
+                mask |= 1 << i;
+            }
+            guard
+        })
+        .collect();
+    (guards, mask)
+}
+
+/// Unlocks the given guards in reverse order, i.e. the last guard is released first.
+///
+/// Dropping the guards has the same effect, but this function documents the intent and
+/// guarantees the release order. See the [`unlock_all!`](crate::unlock_all!) macro for a variant
+/// that doesn't require an allocation and supports guards of different types.
+///
+/// Requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub fn unlock_all<T: ?Sized>(mut guards: Vec<SpinlockGuard<'_, T>>) {
+    while let Some(guard) = guards.pop() {
+        drop(guard);
+    }
+}
+
+/// Unlocks the given guards in reverse order, i.e. the last guard is released first.
+///
+/// The guards can be of different types, e.g. guards of spinlocks with different inner types.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{unlock_all, Spinlock};
+///
+/// let a = Spinlock::new(1);
+/// let b = Spinlock::new("b");
+///
+/// let guard_a = a.lock();
+/// let guard_b = b.lock();
+/// unlock_all!(guard_a, guard_b); // releases `b`, then `a`
+///
+/// assert!(!a.is_locked());
+/// assert!(!b.is_locked());
+/// ```
+#[macro_export]
+macro_rules! unlock_all {
+    () => {};
+    ($first:expr $(, $rest:expr)* $(,)?) => {{
+        $crate::unlock_all!($($rest),*);
+        ::core::mem::drop($first);
+    }};
+}
+
+/// Locks all spinlocks of a tuple in a globally consistent order.
+///
+/// The spinlocks may have different inner types. They are acquired in the order of their
+/// addresses, regardless of their order in the tuple, so two threads locking the same set of
+/// spinlocks can't deadlock, even if they pass them in different orders. The guards are
+/// returned in the same positional order as the spinlocks.
+///
+/// Implemented for tuples with 2 to 6 elements.
+///
+/// ## Panics
+///
+/// Panics if the same spinlock is passed more than once, which would otherwise deadlock.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{lock_all_tuple, Spinlock};
+///
+/// let a = Spinlock::new(1);
+/// let b = Spinlock::new("b");
+///
+/// let (guard_b, guard_a) = lock_all_tuple((&b, &a));
+/// assert_eq!((*guard_a, *guard_b), (1, "b"));
+/// ```
+pub fn lock_all_tuple<L: LockTuple>(locks: L) -> L::Guards {
+    locks.lock_all()
+}
+
+/// A tuple of spinlock references that can be locked by [`lock_all_tuple`].
+pub trait LockTuple {
+    /// The tuple of guards returned by [`lock_all_tuple`].
+    type Guards;
+
+    /// Locks all spinlocks, see [`lock_all_tuple`].
+    fn lock_all(self) -> Self::Guards;
+}
+
+/// Locks all spinlocks of the tuple at once and returns clones of their data.
+///
+/// The spinlocks are locked like in [`lock_all_tuple`], so they are all held while the data is
+/// cloned, and released afterwards. This gives a consistent cut across the spinlocks as long as
+/// every writer holds at most one of them at a time: the snapshot reflects a state that
+/// actually existed at one point in time, unlike cloning the data of one spinlock after the
+/// other. The clones are returned in the same positional order as the spinlocks.
+///
+/// Implemented for tuples with 2 to 6 elements.
+///
+/// ## Panics
+///
+/// Panics if the same spinlock is passed more than once.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{snapshot_all, Spinlock};
+///
+/// let name = Spinlock::new(String::from("sensor"));
+/// let reading = Spinlock::new(21.5);
+///
+/// let (name, reading) = snapshot_all((&name, &reading));
+/// assert_eq!((name.as_str(), reading), ("sensor", 21.5));
+/// ```
+pub fn snapshot_all<L: SnapshotTuple>(locks: L) -> L::Values {
+    locks.snapshot_all()
+}
+
+/// A tuple of references to spinlocks with `Clone` data that can be read by [`snapshot_all`].
+pub trait SnapshotTuple: LockTuple {
+    /// The tuple of clones returned by [`snapshot_all`].
+    type Values;
+
+    /// Clones the data of all spinlocks, see [`snapshot_all`].
+    fn snapshot_all(self) -> Self::Values;
+}
+
+/// Moves the data of `from` into the `Option` of `to`, leaving the default value in `from`.
+///
+/// Both spinlocks are held during the move, so no other thread observes the value in both or
+/// in neither of them. They are locked in the order of their addresses like in
+/// [`lock_all_tuple`], so opposing concurrent moves between the same spinlocks can't deadlock.
+/// This models a single-item handoff between pipeline stages: the value itself is moved, e.g.
+/// a `Vec` only moves its pointer, length and capacity.
+///
+/// If `to` already holds a value, it is overwritten and returned, so the caller can decide
+/// whether to drop it or put it back.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{move_between, Spinlock};
+///
+/// let stage1 = Spinlock::new(vec![1, 2, 3]);
+/// let stage2 = Spinlock::new(None);
+///
+/// assert_eq!(move_between(&stage1, &stage2), None);
+/// assert!(stage1.lock().is_empty());
+/// assert_eq!(*stage2.lock(), Some(vec![1, 2, 3]));
+/// ```
+pub fn move_between<T: Default>(from: &Spinlock<T>, to: &Spinlock<Option<T>>) -> Option<T> {
+    let (mut from, mut to) = lock_all_tuple((from, to));
+    to.replace(mem::take(&mut *from))
+}
+
+fn lock_sorted(raws: &mut [&RawSpinlock]) {
+    raws.sort_unstable_by_key(|raw| raw.id());
+    for pair in raws.windows(2) {
+        assert_ne!(
+            pair[0].id(),
+            pair[1].id(),
+            "the same spinlock was passed to `lock_all_tuple` more than once"
+        );
+    }
+    for raw in raws {
+        raw.lock();
+    }
+}
+
+macro_rules! impl_lock_tuple {
+    ($($lock:ident: $t:ident),+) => {
+        impl<'a, $($t: ?Sized),+> LockTuple for ($(&'a Spinlock<$t>,)+) {
+            type Guards = ($(SpinlockGuard<'a, $t>,)+);
+
+            fn lock_all(self) -> Self::Guards {
+                let ($($lock,)+) = self;
+                // SAFETY: the raw locks are only used to acquire the locks, which are then
+                // turned into guards below
+                let mut raws = [$(unsafe { $lock.raw() }),+];
+                lock_sorted(&mut raws);
+                // SAFETY: all locks were acquired by `lock_sorted`
+                unsafe { ($($lock.make_guard_unchecked(),)+) }
+            }
+        }
+
+        impl<'a, $($t: Clone),+> SnapshotTuple for ($(&'a Spinlock<$t>,)+) {
+            type Values = ($($t,)+);
+
+            fn snapshot_all(self) -> Self::Values {
+                let ($($lock,)+) = self.lock_all();
+                ($($t::clone(&$lock),)+)
+            }
+        }
+    };
+}
+
+impl_lock_tuple!(a: A, b: B);
+impl_lock_tuple!(a: A, b: B, c: C);
+impl_lock_tuple!(a: A, b: B, c: C, d: D);
+impl_lock_tuple!(a: A, b: B, c: C, d: D, e: E);
+impl_lock_tuple!(a: A, b: B, c: C, d: D, e: E, f: F);
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn try_lock_either_prefers_first() {
+        let a = Spinlock::new(1);
+        let b = Spinlock::new(2);
+        assert!(matches!(try_lock_either(&a, &b), Some(Either::Left(_))));
+
+        let _guard_a = a.lock();
+        assert!(matches!(try_lock_either(&a, &b), Some(Either::Right(_))));
+
+        let _guard_b = b.lock();
+        assert!(try_lock_either(&a, &b).is_none());
+    }
+
+    #[test]
+    fn lock_either_returns_free_lock() {
+        let a = Spinlock::new(1);
+        let b = Spinlock::new(2);
+        let _guard_a = a.lock();
+        match lock_either(&a, &b) {
+            Either::Right(guard) => assert_eq!(*guard, 2),
+            Either::Left(_) => panic!("`a` is held"),
+        };
+    }
+
+    #[test]
+    fn lock_either_waits_for_release() {
+        let locks = Arc::new((Spinlock::new(1), Spinlock::new(2)));
+        let guard_a = locks.0.lock();
+        let guard_b = locks.1.lock();
+
+        let waiter = {
+            let locks = locks.clone();
+            thread::spawn(move || match lock_either(&locks.0, &locks.1) {
+                Either::Left(guard) => *guard,
+                Either::Right(guard) => *guard,
+            })
+        };
+
+        thread::sleep(Duration::from_millis(10));
+        drop(guard_b);
+        assert_eq!(waiter.join().unwrap(), 2);
+        drop(guard_a);
+    }
+
+    #[test]
+    fn unlock_all_macro() {
+        let locks = [Spinlock::new(1), Spinlock::new(2), Spinlock::new(3)];
+        let guard_0 = locks[0].lock();
+        let guard_1 = locks[1].lock();
+        let guard_2 = locks[2].lock();
+        crate::unlock_all!(guard_0, guard_1, guard_2);
+        assert!(locks.iter().all(|l| !l.is_locked()));
+    }
+
+    #[test]
+    fn lock_all_tuple_positional_order() {
+        let a = Spinlock::new(1u8);
+        let b = Spinlock::new("b");
+        let c = Spinlock::new([3u32; 2]);
+        let (guard_c, guard_a, guard_b) = lock_all_tuple((&c, &a, &b));
+        assert_eq!(*guard_a, 1);
+        assert_eq!(*guard_b, "b");
+        assert_eq!(*guard_c, [3, 3]);
+        assert!(a.is_locked() && b.is_locked() && c.is_locked());
+    }
+
+    #[test]
+    #[should_panic(expected = "more than once")]
+    fn lock_all_tuple_duplicate() {
+        let a = Spinlock::new(1);
+        let b = Spinlock::new(2);
+        drop(lock_all_tuple((&a, &b, &a)));
+    }
+
+    #[test]
+    fn lock_all_tuple_opposing_orders() {
+        const ROUNDS: usize = 1000;
+
+        let locks = Arc::new((Spinlock::new(0u64), Spinlock::new(0u8), Spinlock::new(0i32)));
+        let forward = {
+            let locks = locks.clone();
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    let (mut a, mut b, mut c) = lock_all_tuple((&locks.0, &locks.1, &locks.2));
+                    *a += 1;
+                    *b = b.wrapping_add(1);
+                    *c += 1;
+                    drop((a, b, c));
+                    thread::yield_now();
+                }
+            })
+        };
+        for _ in 0..ROUNDS {
+            let (mut c, mut b, mut a) = lock_all_tuple((&locks.2, &locks.1, &locks.0));
+            *a += 1;
+            *b = b.wrapping_add(1);
+            *c += 1;
+            drop((a, b, c));
+            thread::yield_now();
+        }
+        forward.join().unwrap();
+
+        assert_eq!(*locks.0.lock(), 2 * ROUNDS as u64);
+        assert_eq!(*locks.2.lock(), 2 * ROUNDS as i32);
+    }
+
+    #[test]
+    fn snapshot_all_consistent_cut() {
+        const ROUNDS: u32 = 1000;
+
+        // the writer increments `a` and then `b`, holding only one lock at a time, so `a` is
+        // always equal to `b` or one ahead of it
+        let locks = Arc::new((Spinlock::new(0u32), Spinlock::new(0u32)));
+        let writer = {
+            let locks = locks.clone();
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    *locks.0.lock() += 1;
+                    thread::yield_now();
+                    *locks.1.lock() += 1;
+                    thread::yield_now();
+                }
+            })
+        };
+        loop {
+            let (a, b) = snapshot_all((&locks.0, &locks.1));
+            assert!(a == b || a == b + 1, "inconsistent snapshot: {} {}", a, b);
+            if b == ROUNDS {
+                break;
+            }
+            thread::yield_now();
+        }
+        writer.join().unwrap();
+
+        // the clones are independent of the locks
+        let (a, mut b) = snapshot_all((&locks.1, &locks.0));
+        b += 1;
+        assert_eq!((a, b), (ROUNDS, ROUNDS + 1));
+        assert!(!locks.0.is_locked() && !locks.1.is_locked());
+    }
+
+    #[test]
+    fn lock_coupling_two_levels() {
+        struct Node<'a> {
+            name: &'static str,
+            children: [&'a Spinlock<u32>; 2],
+        }
+
+        let leaves = [Spinlock::new(10), Spinlock::new(20)];
+        let root = Spinlock::new(Node {
+            name: "root",
+            children: [&leaves[0], &leaves[1]],
+        });
+
+        for index in 0..2 {
+            let (parent, mut child) = lock_coupling(&root, |node| node.children[index]);
+            assert_eq!(parent.name, "root");
+            assert!(root.is_locked());
+            assert!(leaves[index].is_locked());
+            assert!(!leaves[1 - index].is_locked());
+            *child += 1;
+
+            // hand over: release the parent and keep working on the child
+            drop(parent);
+            assert!(!root.is_locked());
+            *child *= 2;
+        }
+        assert_eq!((*leaves[0].lock(), *leaves[1].lock()), (22, 42));
+    }
+
+    #[test]
+    #[should_panic(expected = "is the parent lock")]
+    fn lock_coupling_same_lock() {
+        let lock = Spinlock::new(0);
+        drop(lock_coupling(&lock, |_| &lock));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn try_lock_mask_reports_acquired() {
+        let locks: Vec<_> = (0..8).map(Spinlock::new).collect();
+        let held = [locks[0].lock(), locks[3].lock(), locks[7].lock()];
+
+        let (guards, mask) = try_lock_mask(&locks);
+        assert_eq!(mask, 0b0111_0110);
+        for (i, guard) in guards.iter().enumerate() {
+            assert_eq!(guard.is_some(), mask & (1 << i) != 0);
+            if let Some(guard) = guard {
+                assert_eq!(**guard, i);
+            }
+        }
+        // the acquired locks stay locked until their guards are dropped
+        assert!(locks.iter().all(|lock| lock.is_locked()));
+        drop(guards);
+        drop(held);
+        assert_eq!(try_lock_mask(&locks).1, 0xff);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    #[should_panic(expected = "at most 64 locks")]
+    fn try_lock_mask_too_many() {
+        let locks: Vec<_> = (0..65).map(Spinlock::new).collect();
+        drop(try_lock_mask(&locks));
+    }
+
+    #[test]
+    fn move_between_resets_source() {
+        let from = Spinlock::new(std::string::String::from("buffer"));
+        let to = Spinlock::new(None);
+        assert_eq!(move_between(&from, &to), None);
+        assert!(from.lock().is_empty());
+        assert_eq!(to.lock().as_deref(), Some("buffer"));
+
+        // an occupied destination is overwritten and the displaced value returned
+        *from.lock() = "next".into();
+        assert_eq!(move_between(&from, &to).as_deref(), Some("buffer"));
+        assert_eq!(to.lock().as_deref(), Some("next"));
+        assert!(!from.is_locked() && !to.is_locked());
+    }
+
+    #[test]
+    fn move_between_opposing_moves() {
+        const ROUNDS: u32 = 1000;
+
+        // one thread moves the value forward, the other one locks the spinlocks in the opposite
+        // positional order to move it back. Displaced values are added back to the source, so
+        // the sum of both spinlocks is preserved.
+        let locks = Arc::new((Spinlock::new(1u32), Spinlock::new(None)));
+        let backward = {
+            let locks = locks.clone();
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    let (mut to, mut from) = lock_all_tuple((&locks.1, &locks.0));
+                    *from += to.take().unwrap_or(0);
+                    drop((to, from));
+                    thread::yield_now();
+                }
+            })
+        };
+        for _ in 0..ROUNDS {
+            if let Some(displaced) = move_between(&locks.0, &locks.1) {
+                *locks.0.lock() += displaced;
+            }
+            thread::yield_now();
+        }
+        backward.join().unwrap();
+
+        let (from, to) = lock_all_tuple((&locks.0, &locks.1));
+        assert_eq!(*from + to.unwrap_or(0), 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn unlock_all_vec() {
+        let locks = [Spinlock::new(1), Spinlock::new(2), Spinlock::new(3)];
+        let guards = locks.iter().map(|l| l.lock()).collect();
+        unlock_all(guards);
+        assert!(locks.iter().all(|l| !l.is_locked()));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn try_lock_all_releases_prefix() {
+        let locks = [
+            Spinlock::new(1),
+            Spinlock::new(2),
+            Spinlock::new(3),
+            Spinlock::new(4),
+        ];
+
+        let guard = locks[2].lock();
+        assert!(try_lock_all(&locks).is_none());
+        assert!(!locks[0].is_locked());
+        assert!(!locks[1].is_locked());
+        assert!(!locks[3].is_locked());
+        drop(guard);
+
+        let guards = try_lock_all(&locks).unwrap();
+        assert_eq!(guards.iter().map(|g| **g).sum::<i32>(), 10);
+        assert!(locks.iter().all(|l| l.is_locked()));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{relax::relax, Spinlock};
+use core::{
+    fmt, mem,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// The sender has not been created yet.
+const EMPTY: u8 = 0;
+/// The sender exists, but has not sent a value yet.
+const PENDING: u8 = 1;
+/// The value was sent and can be received.
+const READY: u8 = 2;
+/// The sender was dropped without sending a value.
+const DISCONNECTED: u8 = 3;
+/// The value was taken by the receiver.
+const RECEIVED: u8 = 4;
+
+/// A channel for handing a single value from one thread to another.
+///
+/// The sending half is obtained through [`sender`](Self::sender) and consumed by
+/// [`OneshotSender::send`], so at most one value is ever sent. The value is received through
+/// [`recv`](Self::recv), which spins until it is available. This is useful for fork/join
+/// patterns, where a worker computes a result and hands it back once.
+///
+/// Sending has `Release` semantics and receiving has `Acquire` semantics, so all writes of the
+/// sender before `send` are visible to the receiver.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::SpinOneshot;
+/// use std::thread;
+///
+/// let oneshot = SpinOneshot::new();
+/// thread::scope(|s| {
+///     let sender = oneshot.sender();
+///     s.spawn(move || sender.send(6 * 7));
+///     assert_eq!(oneshot.recv(), 42);
+/// });
+/// ```
+pub struct SpinOneshot<T> {
+    state: AtomicU8,
+    value: Spinlock<Option<T>>,
+}
+
+impl<T> SpinOneshot<T> {
+    /// Creates a new oneshot channel without a value.
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            value: Spinlock::new(None),
+        }
+    }
+
+    /// Returns the sending half of the channel.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the sender was already created before.
+    pub fn sender(&self) -> OneshotSender<'_, T> {
+        if self
+            .state
+            .compare_exchange(EMPTY, PENDING, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            panic!("the sender of this oneshot channel was already created");
+        }
+        OneshotSender { oneshot: self }
+    }
+
+    /// Spins until the value is sent, then returns it.
+    ///
+    /// It's allowed to call this method before the sender is created.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the sender is dropped without sending a value or if the value was already
+    /// received.
+    pub fn recv(&self) -> T {
+        loop {
+            match self.state.load(Ordering::Acquire) {
+                READY => {
+                    if self
+                        .state
+                        .compare_exchange(READY, RECEIVED, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        return self.value.lock().take().expect("value of READY channel");
+                    }
+                }
+                DISCONNECTED => panic!("the sender was dropped without sending a value"),
+                RECEIVED => panic!("the value of this oneshot channel was already received"),
+                _ => relax(),
+            }
+        }
+    }
+
+    /// Returns whether a value was sent and can be received without spinning.
+    pub fn is_ready(&self) -> bool {
+        self.state.load(Ordering::Acquire) == READY
+    }
+}
+
+impl<T> Default for SpinOneshot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for SpinOneshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpinOneshot")
+            .field("is_ready", &self.is_ready())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The sending half of a [`SpinOneshot`] channel.
+///
+/// Dropping the sender without calling [`send`](Self::send) makes
+/// [`SpinOneshot::recv`] panic instead of spinning forever.
+pub struct OneshotSender<'a, T> {
+    oneshot: &'a SpinOneshot<T>,
+}
+
+impl<T> OneshotSender<'_, T> {
+    /// Sends the value, consuming the sender.
+    pub fn send(self, value: T) {
+        *self.oneshot.value.lock() = Some(value);
+        self.oneshot.state.store(READY, Ordering::Release);
+        // the value was sent, so the channel must not be marked as disconnected
+        mem::forget(self);
+    }
+}
+
+impl<T> Drop for OneshotSender<'_, T> {
+    fn drop(&mut self) {
+        self.oneshot.state.store(DISCONNECTED, Ordering::Release);
+    }
+}
+
+impl<T> fmt::Debug for OneshotSender<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OneshotSender").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{string::String, sync::Arc, thread};
+
+    #[test]
+    fn send_recv() {
+        let oneshot = SpinOneshot::new();
+        assert!(!oneshot.is_ready());
+        oneshot.sender().send(1);
+        assert!(oneshot.is_ready());
+        assert_eq!(oneshot.recv(), 1);
+        assert!(!oneshot.is_ready());
+    }
+
+    #[test]
+    fn producer_thread() {
+        let oneshot = Arc::new(SpinOneshot::new());
+        let consumer = {
+            let oneshot = oneshot.clone();
+            // may start receiving before the sender exists
+            thread::spawn(move || oneshot.recv())
+        };
+        let producer = {
+            let oneshot = oneshot.clone();
+            thread::spawn(move || oneshot.sender().send(String::from("result")))
+        };
+
+        producer.join().unwrap();
+        assert_eq!(consumer.join().unwrap(), "result");
+    }
+
+    #[test]
+    #[should_panic(expected = "dropped without sending")]
+    fn sender_dropped() {
+        let oneshot = SpinOneshot::<u32>::new();
+        drop(oneshot.sender());
+        oneshot.recv();
+    }
+
+    #[test]
+    #[should_panic(expected = "already created")]
+    fn second_sender() {
+        let oneshot = SpinOneshot::<u32>::new();
+        let _sender = oneshot.sender();
+        let _ = oneshot.sender();
+    }
+
+    #[test]
+    #[should_panic(expected = "already received")]
+    fn recv_twice() {
+        let oneshot = SpinOneshot::new();
+        oneshot.sender().send(1);
+        oneshot.recv();
+        oneshot.recv();
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A [`Spinlock`] for optimistic concurrency, whose updates are only committed if the data is
+/// unchanged.
+///
+/// A writer reads the data and its [`version`](Self::version), computes the update without
+/// holding the lock, and then commits it through [`lock_if_version`](Self::lock_if_version).
+/// The commit only succeeds if nobody else committed an update in the meantime; otherwise, the
+/// writer starts over with a fresh read. Every guard increments the version when it is dropped.
+///
+/// The version is a `u64`, so it doesn't wrap around in practice and a stale version can't
+/// match again. This type is therefore only available on targets with 64-bit atomics.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::OptimisticSpinlock;
+///
+/// let counter = OptimisticSpinlock::new(1);
+/// loop {
+///     let (value, version) = counter.read();
+///     let new = value * 2; // potentially expensive computation
+///     if let Some(mut guard) = counter.lock_if_version(version) {
+///         *guard = new;
+///         break;
+///     }
+/// }
+/// assert_eq!(counter.read(), (2, 1));
+/// ```
+pub struct OptimisticSpinlock<T: ?Sized> {
+    /// Incremented while holding the lock, right before every release of a guard.
+    version: AtomicU64,
+    lock: Spinlock<T>,
+}
+
+impl<T> OptimisticSpinlock<T> {
+    /// Creates a new unlocked `OptimisticSpinlock` with version `0`.
+    pub const fn new(val: T) -> Self {
+        Self {
+            version: AtomicU64::new(0),
+            lock: Spinlock::new(val),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: ?Sized> OptimisticSpinlock<T> {
+    /// Returns the number of committed updates.
+    ///
+    /// The load has `Acquire` semantics, so the effects of all updates up to the returned
+    /// version are visible afterwards.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Locks the data if its version still equals `expected`.
+    ///
+    /// The version is checked while holding the lock, so the returned guard is guaranteed to
+    /// give access to the data of version `expected`. If the version changed, the lock is
+    /// released again and `None` is returned. Dropping the guard increments the version, also
+    /// if the data was not modified or if the guard is dropped during a panic.
+    pub fn lock_if_version(&self, expected: u64) -> Option<OptimisticSpinlockGuard<'_, T>> {
+        let guard = self.lock.lock();
+        // only updated while the lock is held, so no other thread can change it now
+        if self.version.load(Ordering::Relaxed) == expected {
+            Some(OptimisticSpinlockGuard {
+                guard,
+                version: &self.version,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the lock is currently held.
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_locked()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// The mutable borrow statically guarantees that no guard exists, so the version is not
+    /// incremented.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.lock.get_mut()
+    }
+}
+
+impl<T: Clone> OptimisticSpinlock<T> {
+    /// Returns a clone of the data together with its version.
+    pub fn read(&self) -> (T, u64) {
+        let guard = self.lock.lock();
+        // only updated while the lock is held, so it matches the data
+        let version = self.version.load(Ordering::Relaxed);
+        (guard.clone(), version)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for OptimisticSpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OptimisticSpinlock")
+            .field("version", &self.version())
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+/// A RAII guard returned by [`OptimisticSpinlock::lock_if_version`].
+///
+/// Increments the version of the lock when it is dropped, before the lock is released.
+pub struct OptimisticSpinlockGuard<'a, T: ?Sized> {
+    guard: SpinlockGuard<'a, T>,
+    version: &'a AtomicU64,
+}
+
+impl<T: ?Sized> Deref for OptimisticSpinlockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for OptimisticSpinlockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for OptimisticSpinlockGuard<'_, T> {
+    fn drop(&mut self) {
+        // the lock is still held, it is released when the `guard` field is dropped afterwards
+        let version = self.version.load(Ordering::Relaxed);
+        self.version.store(version + 1, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for OptimisticSpinlockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_if_version_matches() {
+        let lock = OptimisticSpinlock::new(1);
+        let (value, version) = lock.read();
+        let mut guard = lock.lock_if_version(version).unwrap();
+        *guard = value + 1;
+        // the version is only incremented when the guard is dropped
+        assert_eq!(lock.version(), 0);
+        drop(guard);
+        assert_eq!(lock.version(), 1);
+        assert_eq!(lock.read(), (2, 1));
+    }
+
+    #[test]
+    fn lock_if_version_changed() {
+        let lock = OptimisticSpinlock::new(1);
+        let (_, version) = lock.read();
+        *lock.lock_if_version(version).unwrap() = 5;
+        assert!(lock.lock_if_version(version).is_none());
+        assert!(!lock.is_locked());
+        assert_eq!(lock.read(), (5, 1));
+
+        // an unmodified guard still counts as an update
+        drop(lock.lock_if_version(1).unwrap());
+        assert!(lock.lock_if_version(1).is_none());
+        assert_eq!(lock.version(), 2);
+    }
+}
@@ -0,0 +1,15 @@
+//! Identification of the thread holding a lock, enabled by the `owner-tracking` feature.
+
+std::thread_local! {
+    static MARKER: u8 = const { 0 };
+}
+
+/// Returns a non-zero identity of the current thread, or `0` if it is unknown.
+///
+/// The identity is the address of a thread-local variable, so it is unique among running
+/// threads. It is unknown while the thread-local variables of the thread are destroyed.
+pub(crate) fn current_thread() -> usize {
+    MARKER
+        .try_with(|marker| marker as *const u8 as usize)
+        .unwrap_or(0)
+}
@@ -0,0 +1,325 @@
+//! A phase-fair read-write spinlock.
+
+use crate::{relax::relax, spinlock::DefaultGuardMarker};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lock_api::RawRwLock;
+
+/// The increment of `rin` and `rout` for every reader.
+const RINC: usize = 0x100;
+/// The bits of `rin` that are reserved for the writer.
+const WBITS: usize = 0x3;
+/// The bit of `rin` that is set while a writer is present.
+const PRES: usize = 0x2;
+/// The bit of `rin` that identifies the phase of the present writer.
+const PHID: usize = 0x1;
+
+/// A raw read-write spinlock with phase-fair ordering.
+///
+/// This is the phase-fair ticket lock (PF-T) of Brandenburg and Anderson, "Spin-Based
+/// Reader-Writer Synchronization for Multiprocessor Real-Time Systems" (2010). Readers and
+/// writers alternate in phases:
+///
+/// - Writers are served one at a time in arrival order, through a ticket lock.
+/// - A writer that arrives while readers hold the lock prevents new readers from entering and
+///   waits until the current readers left.
+/// - Readers that arrive during a write phase wait for its end and then enter together in the
+///   next read phase, before the next writer in line.
+///
+/// Thus a reader waits for at most one write phase, and a writer waits for at most one read
+/// phase plus the write phases of the writers ahead of it. Neither readers nor writers can
+/// starve, unlike with [`RawBoundedRwSpinlock`](crate::RawBoundedRwSpinlock), which doesn't
+/// prioritize writers.
+///
+/// ## Layout
+///
+/// The lock consists of four counters. Readers increment `rin` on entry and `rout` on exit,
+/// in steps of `0x100`. Writers draw tickets from `win` and are served when `wout` reaches
+/// their ticket. The lowest two bits of `rin` are set by the writer that is present: one bit
+/// marks the presence, the other bit the parity of the writer's ticket. The parity lets waiting readers
+/// tell consecutive write phases apart, so they enter as soon as the write phase they observed
+/// has ended, even if the next writer sets its bits again right away.
+#[derive(Debug)]
+pub struct RawPhaseFairRwSpinlock {
+    rin: AtomicUsize,
+    rout: AtomicUsize,
+    win: AtomicUsize,
+    wout: AtomicUsize,
+}
+
+unsafe impl RawRwLock for RawPhaseFairRwSpinlock {
+    const INIT: Self = Self {
+        rin: AtomicUsize::new(0),
+        rout: AtomicUsize::new(0),
+        win: AtomicUsize::new(0),
+        wout: AtomicUsize::new(0),
+    };
+
+    // A spinlock guard can be sent to another thread and unlocked there, unless the
+    // `no-send-guards` feature is enabled
+    type GuardMarker = DefaultGuardMarker;
+
+    fn lock_shared(&self) {
+        let writer = self.rin.fetch_add(RINC, Ordering::Acquire) & WBITS;
+        // wait until the write phase that was observed on arrival, if any, has ended
+        while writer != 0 && writer == self.rin.load(Ordering::Acquire) & WBITS {
+            relax();
+        }
+    }
+
+    fn try_lock_shared(&self) -> bool {
+        let rin = self.rin.load(Ordering::Relaxed);
+        rin & WBITS == 0
+            && self
+                .rin
+                .compare_exchange(
+                    rin,
+                    rin.wrapping_add(RINC),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    unsafe fn unlock_shared(&self) {
+        self.rout.fetch_add(RINC, Ordering::Release);
+    }
+
+    fn lock_exclusive(&self) {
+        let ticket = self.win.fetch_add(1, Ordering::Relaxed);
+        while self.wout.load(Ordering::Acquire) != ticket {
+            relax();
+        }
+        // block new readers and wait for the current ones to leave
+        let readers = self
+            .rin
+            .fetch_add(PRES | (ticket & PHID), Ordering::Relaxed);
+        while self.rout.load(Ordering::Acquire) != readers {
+            relax();
+        }
+    }
+
+    fn try_lock_exclusive(&self) -> bool {
+        let ticket = self.wout.load(Ordering::Relaxed);
+        if self
+            .win
+            .compare_exchange(
+                ticket,
+                ticket.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            // another writer holds the lock or waits for it
+            return false;
+        }
+
+        // it's our turn as a writer, so no writer bits are set
+        let readers = self.rin.load(Ordering::Relaxed);
+        if self.rout.load(Ordering::Acquire) == readers
+            && self
+                .rin
+                .compare_exchange(
+                    readers,
+                    readers | PRES | (ticket & PHID),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+        {
+            true
+        } else {
+            // pass the turn to the next writer without starting a write phase
+            self.wout.fetch_add(1, Ordering::Release);
+            false
+        }
+    }
+
+    unsafe fn unlock_exclusive(&self) {
+        // let the waiting readers enter before the next writer sets its bits
+        self.rin.fetch_and(!WBITS, Ordering::Release);
+        self.wout.fetch_add(1, Ordering::Release);
+    }
+
+    fn is_locked(&self) -> bool {
+        // Relaxed is sufficient because this operation does not provide synchronization, only atomicity.
+        self.rin.load(Ordering::Relaxed) & !WBITS != self.rout.load(Ordering::Relaxed)
+            || self.is_locked_exclusive()
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        // also true while the next writer waits for the readers to leave
+        self.rin.load(Ordering::Relaxed) & WBITS != 0
+    }
+}
+
+/// A read-write lock based on [`RawPhaseFairRwSpinlock`], in which neither readers nor writers
+/// starve.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::PhaseFairRwSpinlock;
+///
+/// let lock = PhaseFairRwSpinlock::new(0);
+/// {
+///     let reader_1 = lock.read();
+///     let reader_2 = lock.read();
+///     assert_eq!(*reader_1 + *reader_2, 0);
+///     assert!(lock.try_write().is_none());
+/// }
+/// *lock.write() += 1;
+/// assert_eq!(*lock.read(), 1);
+/// ```
+pub type PhaseFairRwSpinlock<T> = lock_api::RwLock<RawPhaseFairRwSpinlock, T>;
+
+/// A RAII guard returned by [`PhaseFairRwSpinlock::read`](lock_api::RwLock::read).
+pub type PhaseFairRwSpinlockReadGuard<'a, T> =
+    lock_api::RwLockReadGuard<'a, RawPhaseFairRwSpinlock, T>;
+
+/// A RAII guard returned by [`PhaseFairRwSpinlock::write`](lock_api::RwLock::write).
+pub type PhaseFairRwSpinlockWriteGuard<'a, T> =
+    lock_api::RwLockWriteGuard<'a, RawPhaseFairRwSpinlock, T>;
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+        vec,
+        vec::Vec,
+    };
+
+    fn raw<T>(lock: &PhaseFairRwSpinlock<T>) -> &RawPhaseFairRwSpinlock {
+        // SAFETY: the raw lock is only inspected
+        unsafe { lock.raw() }
+    }
+
+    #[test]
+    fn exclusion() {
+        let lock = PhaseFairRwSpinlock::new(1);
+        let readers = [lock.read(), lock.read()];
+        assert!(lock.try_write().is_none());
+        assert!(lock.is_locked());
+        drop(readers);
+        assert!(!lock.is_locked());
+
+        let writer = lock.write();
+        assert!(lock.is_locked_exclusive());
+        assert!(lock.try_read().is_none());
+        assert!(lock.try_write().is_none());
+        drop(writer);
+        assert!(!lock.is_locked());
+
+        // a failed `try_write` passes its turn on, so writers aren't blocked afterwards
+        let reader = lock.read();
+        assert!(lock.try_write().is_none());
+        drop(reader);
+        *lock.try_write().unwrap() += 1;
+        assert_eq!(*lock.read(), 2);
+    }
+
+    #[test]
+    fn waiting_writer_blocks_new_readers() {
+        let lock = Arc::new(PhaseFairRwSpinlock::new(0));
+        let reader = lock.read();
+
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.write() += 1)
+        };
+        while !lock.is_locked_exclusive() {
+            thread::yield_now();
+        }
+        // a new reader would extend the read phase and could starve the writer
+        assert!(lock.try_read().is_none());
+        let late_reader = {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.read())
+        };
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(*reader, 0);
+
+        drop(reader);
+        writer.join().unwrap();
+        assert_eq!(late_reader.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn phases_alternate() {
+        let lock = Arc::new(PhaseFairRwSpinlock::new(()));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let first_writer = lock.write();
+
+        // a second writer queues behind the first one
+        let second_writer = {
+            let lock = lock.clone();
+            let order = order.clone();
+            thread::spawn(move || {
+                let _guard = lock.write();
+                order.lock().unwrap().push("writer");
+            })
+        };
+        while raw(&lock).win.load(Ordering::Relaxed) != 2 {
+            thread::yield_now();
+        }
+
+        // a reader that arrives later still enters before the second writer
+        let reader = {
+            let lock = lock.clone();
+            let order = order.clone();
+            thread::spawn(move || {
+                let _guard = lock.read();
+                order.lock().unwrap().push("reader");
+            })
+        };
+        while raw(&lock).rin.load(Ordering::Relaxed) < RINC {
+            thread::yield_now();
+        }
+
+        drop(first_writer);
+        second_writer.join().unwrap();
+        reader.join().unwrap();
+        assert_eq!(*order.lock().unwrap(), ["reader", "writer"]);
+    }
+
+    #[test]
+    fn mixed_load_makes_progress() {
+        const READERS: usize = 2;
+        const WRITERS: usize = 2;
+        const ROUNDS: usize = 200;
+
+        // both elements are always equal outside of write critical sections
+        let lock = Arc::new(PhaseFairRwSpinlock::new(vec![0usize; 2]));
+        let mut threads = Vec::new();
+        for _ in 0..READERS {
+            let lock = lock.clone();
+            threads.push(thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    let guard = lock.read();
+                    assert_eq!(guard[0], guard[1]);
+                }
+            }));
+        }
+        for _ in 0..WRITERS {
+            let lock = lock.clone();
+            threads.push(thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    let mut guard = lock.write();
+                    guard[0] += 1;
+                    guard[1] += 1;
+                }
+            }));
+        }
+        // every thread finishes its rounds, so no thread starved
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*lock.read(), vec![WRITERS * ROUNDS; 2]);
+        assert!(!lock.is_locked());
+    }
+}
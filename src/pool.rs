@@ -0,0 +1,152 @@
+use crate::{Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// A fixed-capacity pool of `N` objects that can be checked out one at a time.
+///
+/// Each object is stored in its own [`Spinlock`]. [`checkout`](Self::checkout) tries to lock the
+/// slots in order and returns a guard for the first free one, so concurrent checkouts never wait
+/// for each other. Dropping the guard returns the object to the pool, keeping its current
+/// state. The pool doesn't allocate, so it can be used in `no_std` environments.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::SpinPool;
+///
+/// let pool = SpinPool::new([[0u8; 16], [0u8; 16]]);
+/// let mut a = pool.checkout().unwrap();
+/// let b = pool.checkout().unwrap();
+/// a[0] = 1;
+/// assert!(pool.checkout().is_none());
+///
+/// drop(a);
+/// let a = pool.checkout().unwrap();
+/// assert_eq!(a[0], 1);
+/// # drop(b);
+/// ```
+pub struct SpinPool<T, const N: usize> {
+    slots: [Spinlock<T>; N],
+}
+
+impl<T, const N: usize> SpinPool<T, N> {
+    /// Creates a new pool containing the given objects.
+    pub fn new(objects: [T; N]) -> Self {
+        Self {
+            slots: objects.map(Spinlock::new),
+        }
+    }
+
+    /// Checks out a free object without waiting.
+    ///
+    /// Returns `None` if all objects are checked out.
+    pub fn checkout(&self) -> Option<SpinPoolGuard<'_, T>> {
+        self.slots
+            .iter()
+            .enumerate()
+            .find_map(|(slot, lock)| lock.try_lock().map(|guard| SpinPoolGuard { guard, slot }))
+    }
+
+    /// Returns the number of objects in the pool.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Consumes the pool, returning the objects.
+    pub fn into_inner(self) -> [T; N] {
+        self.slots.map(Spinlock::into_inner)
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SpinPool<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpinPool")
+            .field("slots", &self.slots)
+            .finish()
+    }
+}
+
+/// A RAII guard for an object checked out of a [`SpinPool`].
+///
+/// The object is returned to the pool when this guard is dropped.
+pub struct SpinPoolGuard<'a, T> {
+    guard: SpinlockGuard<'a, T>,
+    slot: usize,
+}
+
+impl<T> SpinPoolGuard<'_, T> {
+    /// Returns the index of the pool slot that holds the object.
+    ///
+    /// This is an associated function to avoid conflicts with methods of `T`. Call it as
+    /// `SpinPoolGuard::slot(&guard)`.
+    pub fn slot(this: &Self) -> usize {
+        this.slot
+    }
+}
+
+impl<T> Deref for SpinPoolGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for SpinPoolGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SpinPoolGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn exhaustion() {
+        let pool = SpinPool::new([1, 2, 3]);
+        let guards: Vec<_> = (0..pool.capacity())
+            .map(|_| pool.checkout().unwrap())
+            .collect();
+        assert!(pool.checkout().is_none());
+        assert_eq!(guards.iter().map(|g| **g).sum::<i32>(), 6);
+    }
+
+    #[test]
+    fn drop_replenishes() {
+        let pool = SpinPool::new([0; 2]);
+        let _a = pool.checkout().unwrap();
+        let b = pool.checkout().unwrap();
+        assert!(pool.checkout().is_none());
+        drop(b);
+        assert!(pool.checkout().is_some());
+    }
+
+    #[test]
+    fn slot_reused() {
+        let pool = SpinPool::new([0; 3]);
+        let a = pool.checkout().unwrap();
+        let mut b = pool.checkout().unwrap();
+        let c = pool.checkout().unwrap();
+        let slot = SpinPoolGuard::slot(&b);
+        *b = 42;
+        drop(b);
+
+        let b = pool.checkout().unwrap();
+        assert_eq!(SpinPoolGuard::slot(&b), slot);
+        assert_eq!(*b, 42);
+        drop((a, b, c));
+        assert_eq!(pool.into_inner(), [0, 42, 0]);
+    }
+}
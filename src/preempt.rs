@@ -0,0 +1,164 @@
+use crate::RawSpinlock;
+use core::marker::PhantomData;
+use lock_api::{GuardNoSend, RawMutex};
+
+/// Controls preemption of the current task, e.g. in a cooperative green-thread runtime.
+///
+/// Used by [`RawPreemptSpinlock`] to prevent the lock holder from being descheduled inside the
+/// critical section, which would leave all other tasks spinning on the lock.
+pub trait PreemptionControl {
+    /// Disables preemption of the current task.
+    ///
+    /// Calls can be nested, so implementations should count them.
+    fn disable();
+
+    /// Re-enables preemption of the current task after a previous call to `disable`.
+    fn enable();
+}
+
+/// A [`PreemptionControl`] that does nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoPreemptionControl;
+
+impl PreemptionControl for NoPreemptionControl {
+    fn disable() {}
+
+    fn enable() {}
+}
+
+/// A [`RawSpinlock`] that disables preemption while it is held.
+///
+/// Preemption is disabled through `P` before the lock is acquired and re-enabled after it is
+/// released. Since preemption state is usually tied to the current task, the guards of this lock
+/// can't be sent to other threads.
+pub struct RawPreemptSpinlock<P: PreemptionControl = NoPreemptionControl> {
+    inner: RawSpinlock,
+    _control: PhantomData<fn() -> P>,
+}
+
+unsafe impl<P: PreemptionControl> RawMutex for RawPreemptSpinlock<P> {
+    const INIT: Self = Self {
+        inner: RawSpinlock::INIT,
+        _control: PhantomData,
+    };
+
+    // The guard must be released on the task that disabled preemption
+    type GuardMarker = GuardNoSend;
+
+    fn lock(&self) {
+        P::disable();
+        self.inner.lock();
+    }
+
+    fn try_lock(&self) -> bool {
+        P::disable();
+        let locked = self.inner.try_lock();
+        if !locked {
+            P::enable();
+        }
+        locked
+    }
+
+    unsafe fn unlock(&self) {
+        self.inner.unlock();
+        P::enable();
+    }
+
+    fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+}
+
+impl<P: PreemptionControl> core::fmt::Debug for RawPreemptSpinlock<P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RawPreemptSpinlock")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// A spinlock that disables preemption through `P` while it is held.
+///
+/// ## Example
+///
+/// ```rust
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+/// use spinning_top::{PreemptSpinlock, PreemptionControl};
+///
+/// static PREEMPT_DISABLED: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct Scheduler;
+///
+/// impl PreemptionControl for Scheduler {
+///     fn disable() {
+///         PREEMPT_DISABLED.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn enable() {
+///         PREEMPT_DISABLED.fetch_sub(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let spinlock = PreemptSpinlock::<_, Scheduler>::new(0);
+/// {
+///     let mut guard = spinlock.lock();
+///     *guard += 1;
+///     assert_eq!(PREEMPT_DISABLED.load(Ordering::Relaxed), 1);
+/// }
+/// assert_eq!(PREEMPT_DISABLED.load(Ordering::Relaxed), 0);
+/// ```
+pub type PreemptSpinlock<T, P = NoPreemptionControl> = lock_api::Mutex<RawPreemptSpinlock<P>, T>;
+
+/// A RAII guard returned by [`PreemptSpinlock::lock`](lock_api::Mutex::lock).
+pub type PreemptSpinlockGuard<'a, T, P = NoPreemptionControl> =
+    lock_api::MutexGuard<'a, RawPreemptSpinlock<P>, T>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static DISABLED: AtomicUsize = AtomicUsize::new(0);
+    static ENABLED: AtomicUsize = AtomicUsize::new(0);
+
+    struct MockControl;
+
+    impl PreemptionControl for MockControl {
+        fn disable() {
+            DISABLED.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn enable() {
+            ENABLED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn disables_preemption_while_held() {
+        let spinlock = PreemptSpinlock::<_, MockControl>::new(0);
+        {
+            let mut guard = spinlock.lock();
+            assert_eq!(DISABLED.load(Ordering::Relaxed), 1);
+            assert_eq!(ENABLED.load(Ordering::Relaxed), 0);
+            *guard += 1;
+
+            // a failed attempt must not change the preemption state
+            assert!(spinlock.try_lock().is_none());
+            assert_eq!(
+                DISABLED.load(Ordering::Relaxed) - ENABLED.load(Ordering::Relaxed),
+                1
+            );
+        }
+        assert_eq!(
+            DISABLED.load(Ordering::Relaxed),
+            ENABLED.load(Ordering::Relaxed)
+        );
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[test]
+    fn no_preemption_control() {
+        let spinlock: PreemptSpinlock<_> = PreemptSpinlock::new(1);
+        assert_eq!(*spinlock.lock(), 1);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{relax::relax, Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// A [`Spinlock`] that lets high-priority waiters acquire the lock before low-priority ones.
+///
+/// Waiters pass a priority to [`lock`](Self::lock) and publish it in a shared "highest waiting
+/// priority" field. A waiter only attempts to acquire the lock if no higher priority is waiting,
+/// so when the lock is released, the waiters with the highest priority are the only ones that
+/// compete for it.
+///
+/// ## Best Effort
+///
+/// The prioritization is best-effort only:
+///
+/// - A low-priority waiter that checked the waiting priority just before a high-priority waiter
+///   arrived may still acquire the lock.
+/// - Only the highest waiting priority is tracked. When a waiter acquires the lock, it resets the
+///   field and the remaining waiters have to publish their priorities again, which gives lower
+///   priorities a short window to acquire the lock.
+/// - Like any spinlock, the lock doesn't prevent priority inversion: a high-priority waiter
+///   still has to wait until a low-priority holder releases the lock, and spinning high-priority
+///   waiters can starve a preempted holder of CPU time. Low-priority waiters can starve as long
+///   as higher priorities keep arriving.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::PrioritySpinlock;
+///
+/// let spinlock = PrioritySpinlock::new(0);
+/// *spinlock.lock(10) += 1;
+/// assert_eq!(*spinlock.lock(0), 1);
+/// ```
+pub struct PrioritySpinlock<T: ?Sized> {
+    /// The highest priority of all waiting threads, `0` if none are waiting.
+    waiting: AtomicU8,
+    lock: Spinlock<T>,
+}
+
+impl<T> PrioritySpinlock<T> {
+    /// Creates a new unlocked `PrioritySpinlock`.
+    pub const fn new(val: T) -> Self {
+        Self {
+            waiting: AtomicU8::new(0),
+            lock: Spinlock::new(val),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: ?Sized> PrioritySpinlock<T> {
+    /// Acquires the lock with the given priority, spinning until it is available.
+    ///
+    /// Higher values mean higher priority.
+    pub fn lock(&self, priority: u8) -> SpinlockGuard<'_, T> {
+        loop {
+            if self.highest_waiting_priority() <= priority {
+                if let Some(guard) = self.lock.try_lock() {
+                    // reset the field if we were the waiter with the highest priority
+                    let _ = self.waiting.compare_exchange(
+                        priority,
+                        0,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    );
+                    return guard;
+                }
+            }
+            if self.highest_waiting_priority() < priority {
+                self.waiting.fetch_max(priority, Ordering::Relaxed);
+            }
+            relax();
+        }
+    }
+
+    /// Tries to acquire the lock without spinning, regardless of any waiting priorities.
+    pub fn try_lock(&self) -> Option<SpinlockGuard<'_, T>> {
+        self.lock.try_lock()
+    }
+
+    /// Returns whether the lock is currently held.
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_locked()
+    }
+
+    /// Returns the highest priority of all waiting threads, or `0` if none are waiting.
+    ///
+    /// Since waiters publish their priority while spinning, the value is only a snapshot.
+    pub fn highest_waiting_priority(&self) -> u8 {
+        self.waiting.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PrioritySpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrioritySpinlock")
+            .field("waiting", &self.highest_waiting_priority())
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn uncontended() {
+        let spinlock = PrioritySpinlock::new(1);
+        *spinlock.lock(0) += 1;
+        *spinlock.lock(u8::MAX) += 1;
+        assert_eq!(spinlock.highest_waiting_priority(), 0);
+        assert_eq!(spinlock.into_inner(), 3);
+    }
+
+    #[test]
+    fn high_priority_tends_to_win() {
+        const ROUNDS: usize = 20;
+        const LOW: u8 = 1;
+        const HIGH: u8 = 9;
+
+        let mut high_wins = 0;
+        for _ in 0..ROUNDS {
+            // stores the priority of the first waiter that acquired the lock
+            let spinlock = Arc::new(PrioritySpinlock::new(None));
+            let arrived = Arc::new(AtomicUsize::new(0));
+            let guard = spinlock.lock(0);
+
+            let waiters: std::vec::Vec<_> = [LOW, HIGH]
+                .iter()
+                .map(|&priority| {
+                    let spinlock = spinlock.clone();
+                    let arrived = arrived.clone();
+                    thread::spawn(move || {
+                        arrived.fetch_add(1, Ordering::Relaxed);
+                        let mut first = spinlock.lock(priority);
+                        first.get_or_insert(priority);
+                    })
+                })
+                .collect();
+
+            while arrived.load(Ordering::Relaxed) < 2 || spinlock.highest_waiting_priority() < HIGH
+            {
+                thread::yield_now();
+            }
+            for _ in 0..10 {
+                thread::yield_now();
+            }
+            drop(guard);
+
+            for waiter in waiters {
+                waiter.join().unwrap();
+            }
+            if *spinlock.lock(0) == Some(HIGH) {
+                high_wins += 1;
+            }
+        }
+
+        assert!(
+            high_wins * 4 >= ROUNDS * 3,
+            "high priority won {}/{}",
+            high_wins,
+            ROUNDS
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! A read-write spinlock with reentrant reads, enabled by the `std` feature.
+
+use crate::relax::relax;
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use lock_api::{GuardNoSend, RawRwLock};
+use std::vec::Vec;
+
+/// The bit of the state that is set while a writer holds the lock.
+const WRITER: usize = 1 << (usize::BITS - 1);
+/// The bit of the state that is set while a writer waits for the readers to leave.
+const WRITER_WAITING: usize = 1 << (usize::BITS - 2);
+
+/// The id that is assigned to the next lock that needs one.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+std::thread_local! {
+    /// The read recursion depth of the current thread for each lock it holds for reading,
+    /// keyed by the id of the raw lock.
+    static READ_DEPTHS: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A raw read-write spinlock whose read lock can be acquired recursively by the same thread.
+///
+/// Writers have priority: while a writer waits, new readers are blocked, so a steady stream of
+/// readers can't starve writers. A thread that already holds the read lock can still acquire
+/// it again, because the waiting writer can't proceed before the outer read lock is released
+/// anyway. The recursion depth is tracked per thread, and only the outermost release
+/// decrements the reader count of the lock.
+///
+/// Since the depth is stored in a thread-local variable, guards can't be sent to other threads.
+/// It is keyed by an id that is assigned to the lock on its first read and never reused, so a
+/// leaked read guard of a lock that no longer exists can't be mistaken for a read lock of a
+/// new lock at the same address.
+///
+/// ## Write while reading
+///
+/// A writer requires that there are no readers, including the writing thread itself. Acquiring
+/// the write lock while the current thread holds the read lock would therefore deadlock.
+/// `lock_exclusive` detects this and panics instead, while `try_lock_exclusive` returns `false`.
+#[derive(Debug)]
+pub struct RawReentrantRwSpinlock {
+    /// The number of threads holding a read lock, [`WRITER`] if a writer holds the lock, and
+    /// [`WRITER_WAITING`] while a writer waits.
+    state: AtomicUsize,
+    /// The unique id of this lock, or `0` if it wasn't assigned yet.
+    id: AtomicUsize,
+}
+
+impl RawReentrantRwSpinlock {
+    /// Returns the unique id of this lock, assigning one on the first call.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the ids are exhausted.
+    fn key(&self) -> usize {
+        let id = self.id.load(Ordering::Relaxed);
+        if id != 0 {
+            return id;
+        }
+        let new = NEXT_ID
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+            .expect("RawReentrantRwSpinlock id overflow");
+        match self
+            .id
+            .compare_exchange(0, new, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => new,
+            // another thread assigned an id in the meantime
+            Err(id) => id,
+        }
+    }
+
+    /// Returns the read recursion depth of the current thread.
+    fn read_depth(&self) -> usize {
+        READ_DEPTHS.with(|depths| {
+            depths
+                .borrow()
+                .iter()
+                .find(|(key, _)| *key == self.key())
+                .map_or(0, |(_, depth)| *depth)
+        })
+    }
+
+    /// Increments the read recursion depth if the current thread already holds a read lock.
+    fn try_reenter(&self) -> bool {
+        READ_DEPTHS.with(|depths| {
+            match depths
+                .borrow_mut()
+                .iter_mut()
+                .find(|(key, _)| *key == self.key())
+            {
+                Some((_, depth)) => {
+                    *depth += 1;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Tries to become a new reader, failing while a writer holds the lock or waits for it.
+    fn try_enter(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        while state & (WRITER | WRITER_WAITING) == 0 {
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    READ_DEPTHS.with(|depths| depths.borrow_mut().push((self.key(), 1)));
+                    return true;
+                }
+                Err(current) => state = current,
+            }
+        }
+        false
+    }
+}
+
+unsafe impl RawRwLock for RawReentrantRwSpinlock {
+    const INIT: Self = Self {
+        state: AtomicUsize::new(0),
+        id: AtomicUsize::new(0),
+    };
+
+    // The read recursion depth is thread-local
+    type GuardMarker = GuardNoSend;
+
+    fn lock_shared(&self) {
+        if self.try_reenter() {
+            return;
+        }
+        while !self.try_enter() {
+            relax();
+        }
+    }
+
+    fn try_lock_shared(&self) -> bool {
+        self.try_reenter() || self.try_enter()
+    }
+
+    unsafe fn unlock_shared(&self) {
+        let outermost = READ_DEPTHS.with(|depths| {
+            let mut depths = depths.borrow_mut();
+            let index = depths
+                .iter()
+                .position(|(key, _)| *key == self.key())
+                .expect("read lock is not held by the current thread");
+            depths[index].1 -= 1;
+            if depths[index].1 == 0 {
+                depths.swap_remove(index);
+                true
+            } else {
+                false
+            }
+        });
+        if outermost {
+            self.state.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    fn lock_exclusive(&self) {
+        if self.read_depth() > 0 {
+            panic!("write lock requested while the current thread holds the read lock");
+        }
+        while !self.try_lock_exclusive() {
+            // block new readers until we got the lock
+            self.state.fetch_or(WRITER_WAITING, Ordering::Relaxed);
+            relax();
+        }
+    }
+
+    fn try_lock_exclusive(&self) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+        // clears the waiting bit, other waiting writers set it again
+        state & !WRITER_WAITING == 0
+            && self
+                .state
+                .compare_exchange(state, WRITER, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+    }
+
+    unsafe fn unlock_exclusive(&self) {
+        self.state.fetch_and(!WRITER, Ordering::Release);
+    }
+
+    fn is_locked(&self) -> bool {
+        // Relaxed is sufficient because this operation does not provide synchronization, only atomicity.
+        self.state.load(Ordering::Relaxed) & !WRITER_WAITING != 0
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & WRITER != 0
+    }
+}
+
+/// A read-write lock based on [`RawReentrantRwSpinlock`], whose read lock can be acquired
+/// recursively by the same thread.
+///
+/// Requires the `std` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::ReentrantRwSpinlock;
+///
+/// fn depth(lock: &ReentrantRwSpinlock<Vec<u32>>, level: usize) -> usize {
+///     let tree = lock.read();
+///     if level < tree.len() {
+///         // doesn't deadlock, even if a writer is waiting
+///         depth(lock, level + 1)
+///     } else {
+///         level
+///     }
+/// }
+///
+/// let lock = ReentrantRwSpinlock::new(vec![1, 2, 3]);
+/// assert_eq!(depth(&lock, 0), 3);
+/// lock.write().push(4);
+/// ```
+pub type ReentrantRwSpinlock<T> = lock_api::RwLock<RawReentrantRwSpinlock, T>;
+
+/// A RAII guard returned by [`ReentrantRwSpinlock::read`](lock_api::RwLock::read).
+pub type ReentrantRwSpinlockReadGuard<'a, T> =
+    lock_api::RwLockReadGuard<'a, RawReentrantRwSpinlock, T>;
+
+/// A RAII guard returned by [`ReentrantRwSpinlock::write`](lock_api::RwLock::write).
+pub type ReentrantRwSpinlockWriteGuard<'a, T> =
+    lock_api::RwLockWriteGuard<'a, RawReentrantRwSpinlock, T>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem;
+    use std::{sync::Arc, thread};
+
+    fn raw<T>(lock: &ReentrantRwSpinlock<T>) -> &RawReentrantRwSpinlock {
+        // SAFETY: the raw lock is only inspected
+        unsafe { lock.raw() }
+    }
+
+    #[test]
+    fn nested_reads() {
+        let lock = ReentrantRwSpinlock::new(1);
+        let outer = lock.read();
+        let inner = lock.read();
+        let innermost = lock.try_read().unwrap();
+        assert_eq!(*outer + *inner + *innermost, 3);
+        assert_eq!(raw(&lock).state.load(Ordering::Relaxed), 1);
+
+        drop(outer);
+        drop(innermost);
+        assert!(lock.try_write().is_none());
+        drop(inner);
+        assert_eq!(raw(&lock).read_depth(), 0);
+        *lock.try_write().unwrap() += 1;
+        assert_eq!(*lock.read(), 2);
+    }
+
+    #[test]
+    fn nested_read_while_writer_waits() {
+        let lock = Arc::new(ReentrantRwSpinlock::new(0));
+        let outer = lock.read();
+
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.write() += 1)
+        };
+        while raw(&lock).state.load(Ordering::Relaxed) & WRITER_WAITING == 0 {
+            thread::yield_now();
+        }
+
+        // new readers are blocked by the waiting writer
+        let other_reader = {
+            let lock = lock.clone();
+            thread::spawn(move || lock.try_read().is_some())
+        };
+        assert!(!other_reader.join().unwrap());
+
+        // but the current thread already holds the lock, so it can read again
+        let inner = lock.read();
+        assert_eq!(*inner, 0);
+        drop(inner);
+        assert!(!writer.is_finished());
+
+        drop(outer);
+        writer.join().unwrap();
+        assert_eq!(*lock.read(), 1);
+    }
+
+    #[test]
+    fn independent_locks() {
+        let a = ReentrantRwSpinlock::new(1);
+        let b = ReentrantRwSpinlock::new(2);
+        let read_a = a.read();
+        assert!(b.try_write().is_some());
+        let read_b = b.read();
+        assert_eq!(*read_a + *read_b, 3);
+    }
+
+    #[test]
+    fn leaked_guard_of_dropped_lock() {
+        let mut lock = ReentrantRwSpinlock::new(1);
+        mem::forget(lock.read());
+        // replaces the lock in place, so the new lock has the same address
+        lock = ReentrantRwSpinlock::new(2);
+
+        let reader = lock.read();
+        assert!(lock.is_locked());
+        assert_eq!(raw(&lock).state.load(Ordering::Relaxed), 1);
+        assert!(lock.try_write().is_none());
+        drop(reader);
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "holds the read lock")]
+    fn write_while_reading() {
+        let lock = ReentrantRwSpinlock::new(1);
+        let _reader = lock.read();
+        assert!(lock.try_write().is_none());
+        drop(lock.write());
+    }
+}
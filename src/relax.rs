@@ -0,0 +1,312 @@
+//! The busy-wait hint used while spinning on a lock.
+
+use core::hint;
+
+/// Tells the CPU that we're inside a busy-wait loop.
+///
+/// Calls [`core::hint::spin_loop`], unless a custom function was registered through
+/// `set_relax_fn` (requires the `dynamic-relax` feature).
+///
+/// Under Miri, this yields to the other threads if the `std` feature is enabled. Miri runs all
+/// threads on a single interpreter thread, so a spin loop only makes progress if the thread
+/// holding the lock gets scheduled. Miri already treats `spin_loop` as a yield point, but the
+/// explicit yield keeps this working independently of Miri's scheduling heuristics. Production
+/// builds are unaffected.
+#[inline]
+pub(crate) fn relax() {
+    #[cfg(feature = "dynamic-relax")]
+    if let Some(f) = dynamic::relax_fn() {
+        f();
+        return;
+    }
+
+    #[cfg(all(miri, feature = "std"))]
+    std::thread::yield_now();
+
+    hint::spin_loop();
+}
+
+/// The state of the spin loop of a single lock acquisition.
+pub(crate) struct SpinWait {
+    #[cfg(any(
+        feature = "histogram",
+        all(feature = "panic-on-long-spin", debug_assertions)
+    ))]
+    spins: usize,
+    #[cfg(feature = "env-config")]
+    backoff: crate::ExponentialPause,
+}
+
+impl SpinWait {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(any(
+                feature = "histogram",
+                all(feature = "panic-on-long-spin", debug_assertions)
+            ))]
+            spins: 0,
+            #[cfg(feature = "env-config")]
+            backoff: Default::default(),
+        }
+    }
+
+    /// Performs a single iteration of the spin loop.
+    #[inline]
+    pub(crate) fn spin(&mut self) {
+        #[cfg(any(
+            feature = "histogram",
+            all(feature = "panic-on-long-spin", debug_assertions)
+        ))]
+        {
+            self.spins += 1;
+        }
+        #[cfg(all(feature = "panic-on-long-spin", debug_assertions))]
+        long_spin::check(self.spins);
+
+        #[cfg(feature = "env-config")]
+        match env_config::default_spin_strategy() {
+            env_config::SpinStrategy::Hint => relax(),
+            env_config::SpinStrategy::Yield => std::thread::yield_now(),
+            env_config::SpinStrategy::Backoff => crate::RelaxStrategy::relax(&mut self.backoff),
+        }
+
+        #[cfg(not(feature = "env-config"))]
+        relax();
+    }
+
+    /// Returns the number of spin iterations so far.
+    #[cfg(feature = "histogram")]
+    #[inline]
+    pub(crate) fn spins(&self) -> usize {
+        self.spins
+    }
+}
+
+#[cfg(feature = "env-config")]
+pub use env_config::{default_spin_strategy, SpinStrategy};
+
+#[cfg(feature = "dynamic-relax")]
+pub use dynamic::set_relax_fn;
+
+#[cfg(feature = "panic-on-long-spin")]
+pub use long_spin::set_long_spin_limit;
+
+#[cfg(feature = "panic-on-long-spin")]
+mod long_spin {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static LIMIT: AtomicUsize = AtomicUsize::new(10_000_000);
+
+    /// Sets the number of spin iterations after which a lock acquisition panics.
+    ///
+    /// With the `panic-on-long-spin` feature, a lock acquisition that spins for more than this
+    /// number of iterations panics with a "spinlock spun too long" message instead of hanging
+    /// forever. This turns a silent deadlock into a panic with a backtrace. The default limit is
+    /// 10 million iterations.
+    ///
+    /// The check is only performed when debug assertions are enabled, so it has no effect in
+    /// release builds.
+    pub fn set_long_spin_limit(limit: usize) {
+        LIMIT.store(limit, Ordering::Relaxed);
+    }
+
+    #[cfg(debug_assertions)]
+    #[inline]
+    pub(super) fn check(spins: usize) {
+        if spins > LIMIT.load(Ordering::Relaxed) {
+            panic!("spinlock spun too long — probable deadlock");
+        }
+    }
+}
+
+#[cfg(feature = "dynamic-relax")]
+mod dynamic {
+    use core::{
+        ptr,
+        sync::atomic::{AtomicPtr, Ordering},
+    };
+
+    static RELAX_FN: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+    /// Registers a function that is called instead of [`core::hint::spin_loop`] while spinning.
+    ///
+    /// This allows benchmarking harnesses to swap the busy-wait strategy at runtime without
+    /// recompiling. The function applies to all spinlocks of this crate and replaces any
+    /// previously registered function.
+    ///
+    /// ## Overhead
+    ///
+    /// With the `dynamic-relax` feature enabled, every spin iteration performs an additional
+    /// `Relaxed` load and, if a function is registered, an indirect call. Without the feature,
+    /// the intrinsic is called directly and this function does not exist.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// spinning_top::set_relax_fn(std::thread::yield_now);
+    /// ```
+    pub fn set_relax_fn(f: fn()) {
+        RELAX_FN.store(f as *mut (), Ordering::Relaxed);
+    }
+
+    pub(super) fn relax_fn() -> Option<fn()> {
+        let f = RELAX_FN.load(Ordering::Relaxed);
+        if f.is_null() {
+            None
+        } else {
+            // SAFETY: non-null values are only stored by `set_relax_fn`, which stores a `fn()`
+            Some(unsafe { core::mem::transmute::<*mut (), fn()>(f) })
+        }
+    }
+}
+
+#[cfg(feature = "env-config")]
+mod env_config {
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    /// The spin strategy of [`RawSpinlock`](crate::RawSpinlock), selected through the
+    /// `SPINNING_TOP_STRATEGY` environment variable.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SpinStrategy {
+        /// Issue a busy-wait hint per iteration (`SPINNING_TOP_STRATEGY=hint`, the default).
+        Hint,
+        /// Yield to the operating system scheduler per iteration (`SPINNING_TOP_STRATEGY=yield`).
+        Yield,
+        /// Issue exponentially growing bursts of busy-wait hints, like
+        /// [`ExponentialPause`](crate::ExponentialPause) (`SPINNING_TOP_STRATEGY=backoff`).
+        Backoff,
+    }
+
+    const UNINIT: u8 = 0;
+
+    static STRATEGY: AtomicU8 = AtomicU8::new(UNINIT);
+
+    impl SpinStrategy {
+        fn parse(value: Option<&str>) -> Self {
+            match value {
+                Some("yield") => Self::Yield,
+                Some("backoff") => Self::Backoff,
+                _ => Self::Hint,
+            }
+        }
+
+        fn to_u8(self) -> u8 {
+            match self {
+                Self::Hint => 1,
+                Self::Yield => 2,
+                Self::Backoff => 3,
+            }
+        }
+
+        fn from_u8(value: u8) -> Self {
+            match value {
+                2 => Self::Yield,
+                3 => Self::Backoff,
+                _ => Self::Hint,
+            }
+        }
+    }
+
+    /// Returns the spin strategy used by [`RawSpinlock`](crate::RawSpinlock).
+    ///
+    /// The strategy is read from the `SPINNING_TOP_STRATEGY` environment variable, which can be
+    /// set to `hint`, `yield`, or `backoff`. This allows operators to tune the contention
+    /// behavior without rebuilding. If the variable is unset or has an invalid value, the `hint`
+    /// strategy is used.
+    ///
+    /// The variable is read once, on the first contended lock acquisition or the first call of
+    /// this function, and cached afterwards. Changes to the environment after that point have no
+    /// effect. The one-time read takes the environment lock of the standard library, while every
+    /// later spin iteration only performs an additional `Relaxed` load.
+    ///
+    /// Requires the `env-config` feature.
+    pub fn default_spin_strategy() -> SpinStrategy {
+        match STRATEGY.load(Ordering::Relaxed) {
+            UNINIT => {
+                let value = std::env::var("SPINNING_TOP_STRATEGY").ok();
+                let strategy = SpinStrategy::parse(value.as_deref());
+                // concurrent initializations read the same variable, so it doesn't matter
+                // which of them wins
+                STRATEGY.store(strategy.to_u8(), Ordering::Relaxed);
+                strategy
+            }
+            value => SpinStrategy::from_u8(value),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parse() {
+            assert_eq!(SpinStrategy::parse(Some("yield")), SpinStrategy::Yield);
+            assert_eq!(SpinStrategy::parse(Some("backoff")), SpinStrategy::Backoff);
+            assert_eq!(SpinStrategy::parse(Some("hint")), SpinStrategy::Hint);
+            assert_eq!(SpinStrategy::parse(Some("invalid")), SpinStrategy::Hint);
+            assert_eq!(SpinStrategy::parse(None), SpinStrategy::Hint);
+        }
+
+        #[test]
+        fn round_trip() {
+            for strategy in [
+                SpinStrategy::Hint,
+                SpinStrategy::Yield,
+                SpinStrategy::Backoff,
+            ] {
+                assert_eq!(SpinStrategy::from_u8(strategy.to_u8()), strategy);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "panic-on-long-spin", debug_assertions))]
+mod long_spin_tests {
+    use crate::RawSpinlock;
+    use lock_api::RawMutex;
+
+    #[test]
+    #[should_panic(expected = "spinlock spun too long")]
+    fn panic_on_long_spin() {
+        let lock = RawSpinlock::INIT;
+        lock.lock();
+        lock.lock(); // never succeeds
+    }
+}
+
+#[cfg(all(test, feature = "dynamic-relax"))]
+mod dynamic_tests {
+    extern crate std;
+
+    use super::*;
+    use crate::Spinlock;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::{sync::Arc, thread, time::Duration};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_relax() {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        hint::spin_loop();
+    }
+
+    #[test]
+    fn custom_relax_fn_is_called() {
+        set_relax_fn(counting_relax);
+
+        let spinlock = Arc::new(Spinlock::new(0));
+        let guard = spinlock.lock();
+        let waiter = {
+            let spinlock = spinlock.clone();
+            thread::spawn(move || *spinlock.lock() += 1)
+        };
+
+        thread::sleep(Duration::from_millis(10));
+        drop(guard);
+        waiter.join().unwrap();
+
+        assert_eq!(*spinlock.lock(), 1);
+        assert!(CALLS.load(Ordering::Relaxed) > 0);
+    }
+}
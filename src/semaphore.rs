@@ -0,0 +1,142 @@
+use crate::relax::relax;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A counting semaphore based on busy-waiting.
+///
+/// The semaphore holds a number of permits. [`acquire`](Self::acquire) spins until a permit is
+/// available and takes it, [`release`](Self::release) returns a permit. Acquiring a permit has
+/// `Acquire` semantics and releasing it has `Release` semantics, so writes before a `release`
+/// are visible to the thread that acquires the permit next.
+///
+/// ## Releasing Extra Permits
+///
+/// The semaphore doesn't remember its initial number of permits, so `release` may be called
+/// without a matching `acquire`. This adds a new permit, which is useful for growing the
+/// number of permits at runtime.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::SpinSemaphore;
+///
+/// let semaphore = SpinSemaphore::new(2);
+/// semaphore.acquire();
+/// assert!(semaphore.try_acquire());
+/// assert!(!semaphore.try_acquire());
+///
+/// semaphore.release();
+/// assert_eq!(semaphore.available_permits(), 1);
+/// ```
+#[derive(Debug)]
+pub struct SpinSemaphore {
+    permits: AtomicUsize,
+}
+
+impl SpinSemaphore {
+    /// Creates a new semaphore with the given number of permits.
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: AtomicUsize::new(permits),
+        }
+    }
+
+    /// Takes a permit, spinning until one is available.
+    pub fn acquire(&self) {
+        while !self.try_acquire() {
+            // Wait until a permit looks available before retrying
+            while self.available_permits() == 0 {
+                relax();
+            }
+        }
+    }
+
+    /// Tries to take a permit without spinning.
+    ///
+    /// Returns `false` if no permit is available.
+    pub fn try_acquire(&self) -> bool {
+        let mut permits = self.permits.load(Ordering::Relaxed);
+        while permits > 0 {
+            match self.permits.compare_exchange_weak(
+                permits,
+                permits - 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(current) => permits = current,
+            }
+        }
+        false
+    }
+
+    /// Returns a permit to the semaphore.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the number of permits overflows a `usize`.
+    pub fn release(&self) {
+        let previous = self.permits.fetch_add(1, Ordering::Release);
+        assert!(previous != usize::MAX, "SpinSemaphore permit overflow");
+    }
+
+    /// Returns the number of currently available permits.
+    ///
+    /// The value is only a snapshot, since other threads may acquire or release permits
+    /// concurrently.
+    pub fn available_permits(&self) -> usize {
+        self.permits.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+    use std::{sync::Arc, thread, time::Duration};
+
+    #[test]
+    fn permits() {
+        const PERMITS: usize = 3;
+
+        let semaphore = SpinSemaphore::new(PERMITS);
+        for _ in 0..PERMITS {
+            semaphore.acquire();
+        }
+        assert!(!semaphore.try_acquire());
+
+        semaphore.release();
+        assert!(semaphore.try_acquire());
+
+        // releasing more than the initial number of permits is allowed
+        for _ in 0..PERMITS + 1 {
+            semaphore.release();
+        }
+        assert_eq!(semaphore.available_permits(), PERMITS + 1);
+    }
+
+    #[test]
+    fn extra_acquirer_waits_for_release() {
+        let semaphore = Arc::new(SpinSemaphore::new(2));
+        semaphore.acquire();
+        semaphore.acquire();
+
+        let acquired = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let semaphore = semaphore.clone();
+            let acquired = acquired.clone();
+            thread::spawn(move || {
+                semaphore.acquire();
+                acquired.store(true, Ordering::Relaxed);
+            })
+        };
+
+        thread::sleep(Duration::from_millis(10));
+        assert!(!acquired.load(Ordering::Relaxed));
+        semaphore.release();
+        waiter.join().unwrap();
+        assert!(acquired.load(Ordering::Relaxed));
+        assert_eq!(semaphore.available_permits(), 0);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// A spinlock for data that is shared with a Unix signal handler.
+///
+/// A signal handler interrupts the thread it runs on. If that thread holds a spinlock and the
+/// handler tries to lock it too, the handler spins forever because the holder can't continue
+/// until the handler returns. This lock prevents the deadlock by construction: its only
+/// acquisition method is [`try_lock`](Self::try_lock), which fails instead of waiting. There is
+/// no blocking `lock` method and, unlike [`NamedSpinlock`](crate::NamedSpinlock), the lock
+/// doesn't dereference to the inner [`Spinlock`].
+///
+/// ## Async-Signal-Safety
+///
+/// `try_lock` and releasing the guard are a single atomic operation each. They don't allocate,
+/// don't call into the operating system, and don't touch thread-local state, so they are
+/// async-signal-safe. This holds for the default features only: the `owner-tracking`,
+/// `timing`, `test-hooks`, `histogram` and `global-stats` features add bookkeeping to every
+/// acquisition. Some of it reads clocks, accesses thread-locals or calls user-provided
+/// functions, which are not async-signal-safe, so these features should stay disabled in
+/// binaries that use this lock from signal handlers.
+///
+/// The code in the critical section must be async-signal-safe as well. A handler that fails to
+/// acquire the lock should skip or defer its work, e.g. by setting an atomic flag that the
+/// interrupted code checks after releasing the lock.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::SignalSafeSpinlock;
+///
+/// static PENDING: SignalSafeSpinlock<u32> = SignalSafeSpinlock::new(0);
+///
+/// // in the signal handler
+/// match PENDING.try_lock() {
+///     Some(mut pending) => *pending += 1,
+///     None => { /* the interrupted code holds the lock, so give up */ }
+/// }
+/// assert_eq!(*PENDING.try_lock().unwrap(), 1);
+/// ```
+///
+/// There is no way to wait for the lock:
+///
+/// ```compile_fail
+/// use spinning_top::SignalSafeSpinlock;
+///
+/// let lock = SignalSafeSpinlock::new(0);
+/// let _guard = lock.lock();
+/// ```
+pub struct SignalSafeSpinlock<T: ?Sized> {
+    lock: Spinlock<T>,
+}
+
+impl<T> SignalSafeSpinlock<T> {
+    /// Creates a new unlocked `SignalSafeSpinlock`.
+    pub const fn new(val: T) -> Self {
+        Self {
+            lock: Spinlock::new(val),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: ?Sized> SignalSafeSpinlock<T> {
+    /// Tries to acquire the lock without waiting.
+    ///
+    /// Returns `None` if the lock is held, either by another thread or by the code that the
+    /// signal handler interrupted.
+    pub fn try_lock(&self) -> Option<SignalSafeSpinlockGuard<'_, T>> {
+        self.lock
+            .try_lock()
+            .map(|guard| SignalSafeSpinlockGuard { guard })
+    }
+
+    /// Returns whether the lock is currently held.
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_locked()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// The mutable borrow statically guarantees that no guard exists.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.lock.get_mut()
+    }
+}
+
+impl<T: Default> Default for SignalSafeSpinlock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SignalSafeSpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignalSafeSpinlock")
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+/// A RAII guard for the data of a [`SignalSafeSpinlock`].
+///
+/// Unlike a [`SpinlockGuard`], this guard doesn't give access to the lock it belongs to, so it
+/// can't be used to temporarily unlock and then wait for the lock again.
+pub struct SignalSafeSpinlockGuard<'a, T: ?Sized> {
+    guard: SpinlockGuard<'a, T>,
+}
+
+impl<T: ?Sized> Deref for SignalSafeSpinlockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for SignalSafeSpinlockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SignalSafeSpinlockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_lock_only() {
+        let lock = SignalSafeSpinlock::new(1);
+        let mut guard = lock.try_lock().unwrap();
+        *guard += 1;
+        assert!(lock.is_locked());
+        // a signal handler interrupting the holder gives up instead of deadlocking
+        assert!(lock.try_lock().is_none());
+        drop(guard);
+        assert!(!lock.is_locked());
+        assert_eq!(*lock.try_lock().unwrap(), 2);
+        assert_eq!(lock.into_inner(), 2);
+    }
+}
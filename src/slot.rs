@@ -0,0 +1,100 @@
+use crate::Spinlock;
+use alloc::boxed::Box;
+use core::{
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// Returns the spinlock stored in `slot`, installing a new one created by `init` if the slot is
+/// empty (null).
+///
+/// This supports lazily creating per-key locks, e.g. for the shards of a map. If multiple
+/// threads find the slot empty at the same time, each of them calls `init`, but only the first
+/// one installs its spinlock. The other threads drop their spinlocks again and return the
+/// installed one, so all callers end up with the same instance.
+///
+/// The installed spinlock is never freed by this function. The owner of the slot is responsible
+/// for freeing it, e.g. through `Box::from_raw` when the slot is dropped. A non-null pointer in
+/// the slot must point to a valid spinlock as long as the slot is borrowed, also if it was
+/// stored by other means than this function.
+///
+/// Requires the `alloc` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use core::{ptr, sync::atomic::AtomicPtr};
+/// use spinning_top::{get_or_init_lock, Spinlock};
+///
+/// let slot = AtomicPtr::new(ptr::null_mut());
+/// *get_or_init_lock(&slot, || Box::new(Spinlock::new(0))).lock() += 1;
+/// assert_eq!(*get_or_init_lock(&slot, || unreachable!()).lock(), 1);
+///
+/// // free the spinlock installed in the slot
+/// drop(unsafe { Box::from_raw(slot.into_inner()) });
+/// ```
+pub fn get_or_init_lock<T>(
+    slot: &AtomicPtr<Spinlock<T>>,
+    init: impl FnOnce() -> Box<Spinlock<T>>,
+) -> &Spinlock<T> {
+    let mut lock = slot.load(Ordering::Acquire);
+    if lock.is_null() {
+        let new = Box::into_raw(init());
+        match slot.compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => lock = new,
+            Err(installed) => {
+                // SAFETY: `new` was created by `Box::into_raw` above and was never shared
+                drop(unsafe { Box::from_raw(new) });
+                lock = installed;
+            }
+        }
+    }
+    // SAFETY: non-null pointers in the slot point to an installed spinlock, which is never
+    // freed while the slot is borrowed
+    unsafe { &*lock }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use std::{
+        sync::{Arc, Barrier},
+        thread,
+        vec::Vec,
+    };
+
+    #[test]
+    fn concurrent_init_installs_once() {
+        const THREADS: usize = 4;
+
+        let slot = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let inits = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let slot = slot.clone();
+                let inits = inits.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    let lock = get_or_init_lock(&slot, || {
+                        inits.fetch_add(1, Ordering::Relaxed);
+                        Box::new(Spinlock::new(0))
+                    });
+                    *lock.lock() += 1;
+                    lock as *const Spinlock<i32> as usize
+                })
+            })
+            .collect();
+        let locks: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+
+        assert!(locks.iter().all(|&lock| lock == locks[0]));
+        assert!(inits.load(Ordering::Relaxed) >= 1);
+
+        let lock = unsafe { Box::from_raw(slot.swap(ptr::null_mut(), Ordering::Relaxed)) };
+        assert_eq!(lock.into_inner(), THREADS as i32);
+    }
+}
@@ -3,11 +3,75 @@
 // and
 // https://github.com/mvdnes/spin-rs/tree/7516c8037d3d15712ba4d8499ab075e97a19d778
 
+use crate::relax::SpinWait;
+#[cfg(feature = "track-caller")]
+use core::panic::Location;
+#[cfg(all(feature = "track-caller", debug_assertions))]
+use core::sync::atomic::AtomicPtr;
+#[cfg(feature = "lock-tag")]
+use core::sync::atomic::AtomicU32;
+#[cfg(feature = "timing")]
+use core::sync::atomic::AtomicU64;
+#[cfg(any(
+    feature = "owner-tracking",
+    all(feature = "guard-count", debug_assertions)
+))]
+use core::sync::atomic::AtomicUsize;
 use core::{
-    hint,
+    mem::ManuallyDrop,
+    ptr,
     sync::atomic::{AtomicBool, Ordering},
+    task::Poll,
 };
-use lock_api::{GuardSend, RawMutex};
+#[cfg(feature = "no-send-guards")]
+use lock_api::GuardNoSend;
+#[cfg(not(feature = "no-send-guards"))]
+use lock_api::GuardSend;
+use lock_api::RawMutex;
+
+/// The value of `RawSpinlock::tag` while the holder has no tag.
+#[cfg(feature = "lock-tag")]
+const NO_TAG: u32 = u32::MAX;
+
+/// The guard marker of the raw locks of this crate whose guards may be released on any thread.
+#[cfg(not(feature = "no-send-guards"))]
+pub(crate) type DefaultGuardMarker = GuardSend;
+/// The guard marker of the raw locks of this crate whose guards may be released on any thread.
+///
+/// The `no-send-guards` feature makes the guards `!Send` for single-threaded deployments.
+#[cfg(feature = "no-send-guards")]
+pub(crate) type DefaultGuardMarker = GuardNoSend;
+
+/// The ordering used for acquiring the lock.
+#[cfg(not(feature = "single-core"))]
+const ACQUIRE: Ordering = Ordering::Acquire;
+/// The ordering used for releasing the lock.
+#[cfg(not(feature = "single-core"))]
+const RELEASE: Ordering = Ordering::Release;
+
+// With the `single-core` feature, there is no other core to synchronize with, so we don't need
+// the hardware fences of `Acquire` and `Release`. The compiler fences in `acquire_fence` and
+// `release_fence` still prevent the compiler from moving accesses out of the critical section,
+// which is required when the lock is shared with interrupt handlers.
+#[cfg(feature = "single-core")]
+const ACQUIRE: Ordering = Ordering::Relaxed;
+#[cfg(feature = "single-core")]
+const RELEASE: Ordering = Ordering::Relaxed;
+
+#[inline]
+fn acquire_fence() {
+    #[cfg(feature = "single-core")]
+    core::sync::atomic::compiler_fence(Ordering::Acquire);
+}
+
+// The `strict-barrier` feature adds the compiler fence before the unlock store in all
+// configurations. The `Release` store already prevents the compiler from moving accesses past
+// it, so this is only a defense against codegen bugs on exotic targets.
+#[inline]
+fn release_fence() {
+    #[cfg(any(feature = "single-core", feature = "strict-barrier"))]
+    core::sync::atomic::compiler_fence(Ordering::Release);
+}
 
 /// Provides mutual exclusion based on spinning on an `AtomicBool`.
 ///
@@ -23,40 +87,509 @@ use lock_api::{GuardSend, RawMutex};
 /// assert_eq!(lock.try_lock(), false); // can't be locked a second time
 /// unsafe { lock.unlock(); } // unlock it
 /// assert_eq!(lock.try_lock(), true); // now it can be locked again
+/// ```
+///
+/// ## Single-Core Targets
+///
+/// With the `single-core` feature, the lock uses `Relaxed` atomic operations combined with
+/// compiler fences instead of `Acquire` and `Release` operations. This avoids the cost of
+/// hardware memory barriers on single-core bare-metal targets.
+///
+/// **This is only sound if the lock is never accessed concurrently from multiple cores.** The
+/// compiler fences still order the accesses with respect to interrupt handlers on the same core,
+/// but they provide no synchronization between different cores or hardware threads. Enabling
+/// the feature in a program that uses the lock from more than one core leads to data races.
+///
+/// ## Strict Barrier
+///
+/// The `strict-barrier` feature inserts an explicit compiler fence before the store that
+/// releases the lock. This is normally unnecessary, because the `Release` ordering of the store
+/// already prevents the compiler from moving memory accesses of the critical section past it.
+/// It is a defensive option for weakly-ordered targets with less mature code generation, e.g.
+/// some RISC-V or Xtensa microcontrollers built with aggressive LTO, where the generated code
+/// should be audited anyway. It has no effect on the hardware memory ordering.
+///
+/// ## Guards Without `Send`
+///
+/// By default, guards can be sent to other threads and released there. For strictly
+/// single-threaded deployments, e.g. interrupt-driven firmware without an operating system,
+/// the `no-send-guards` feature makes the guards of this lock and of the other raw locks of
+/// this crate `!Send`, so the compiler rejects moving a guard to another thread. The feature
+/// applies to the whole dependency graph, so enabling it breaks crates that rely on sendable
+/// guards. Only enable it in the final binary, not in libraries.
+///
+#[cfg_attr(feature = "no-send-guards", doc = "```compile_fail")]
+#[cfg_attr(not(feature = "no-send-guards"), doc = "```")]
+/// use spinning_top::{Spinlock, SpinlockGuard};
+///
+/// fn assert_send<T: Send>(_: &T) {}
+///
+/// let spinlock = Spinlock::new(0);
+/// let guard: SpinlockGuard<'_, i32> = spinlock.lock();
+/// // only compiles without the `no-send-guards` feature
+/// assert_send(&guard);
+/// ```
+///
+/// This also applies to the guards of this crate that don't wrap a `lock_api` guard, e.g. the
+/// guard returned by [`map_split`](crate::map_split):
+///
+#[cfg_attr(feature = "no-send-guards", doc = "```compile_fail")]
+#[cfg_attr(not(feature = "no-send-guards"), doc = "```")]
+/// use spinning_top::{map_split, Spinlock};
+///
+/// fn assert_send<T: Send>(_: &T) {}
+///
+/// let spinlock = Spinlock::new((0, 1));
+/// let guard = map_split(spinlock.lock(), |(a, b)| (a, b));
+/// // only compiles without the `no-send-guards` feature
+/// assert_send(&guard);
+/// ```
 #[derive(Debug)]
 pub struct RawSpinlock {
     /// Whether the spinlock is locked.
     locked: AtomicBool,
+    /// The caller location of the last acquisition through `lock_tracked`, or null.
+    #[cfg(all(feature = "track-caller", debug_assertions))]
+    last_locked_at: AtomicPtr<Location<'static>>,
+    /// The identity of the thread holding the lock, or `0`.
+    #[cfg(feature = "owner-tracking")]
+    owner: AtomicUsize,
+    /// The number of guards that were acquired and not released yet.
+    #[cfg(all(feature = "guard-count", debug_assertions))]
+    live_guards: AtomicUsize,
+    /// Set when `lock` had to spin, cleared by `clear_contention_hint`.
+    #[cfg(feature = "contention-hint")]
+    contended: AtomicBool,
+    /// The timestamp of the last unlock for handoff latency reporting, or `0`.
+    #[cfg(feature = "timing")]
+    last_unlock: AtomicU64,
+    /// The context tag of the current holder, or [`NO_TAG`].
+    #[cfg(feature = "lock-tag")]
+    tag: AtomicU32,
 }
 
 impl RawSpinlock {
+    /// Creates a raw lock in the given state, without going through `lock` and `unlock`.
+    ///
+    /// Only available in tests, e.g. for starting in the locked state.
+    #[cfg(test)]
+    pub(crate) const fn with_state(locked: bool) -> Self {
+        Self {
+            locked: AtomicBool::new(locked),
+            #[cfg(all(feature = "track-caller", debug_assertions))]
+            last_locked_at: AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "owner-tracking")]
+            owner: AtomicUsize::new(0),
+            #[cfg(all(feature = "guard-count", debug_assertions))]
+            live_guards: AtomicUsize::new(if locked { 1 } else { 0 }),
+            #[cfg(feature = "contention-hint")]
+            contended: AtomicBool::new(false),
+            #[cfg(feature = "timing")]
+            last_unlock: AtomicU64::new(0),
+            #[cfg(feature = "lock-tag")]
+            tag: AtomicU32::new(NO_TAG),
+        }
+    }
+
     // Can fail to lock even if the spinlock is not locked. May be more efficient than `try_lock`
     // when called in a loop.
     fn try_lock_weak(&self) -> bool {
+        #[cfg(feature = "test-hooks")]
+        crate::hooks::before_cas();
         // The Orderings are the same as try_lock, and are still correct here.
-        self.locked
-            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+        let locked = self
+            .locked
+            .compare_exchange_weak(false, true, ACQUIRE, Ordering::Relaxed)
+            .is_ok();
+        #[cfg(feature = "test-hooks")]
+        crate::hooks::after_cas();
+        if locked {
+            self.acquired();
+        }
+        locked
+    }
+
+    /// Tries to acquire the lock through a strong `compare_exchange` with the given orderings.
+    ///
+    /// This is the acquisition attempt of `try_lock` and its variants, including the calls of
+    /// the `test-hooks` callbacks around it.
+    #[inline]
+    fn try_lock_strong(&self, success: Ordering, failure: Ordering) -> bool {
+        #[cfg(feature = "test-hooks")]
+        crate::hooks::before_cas();
+        let locked = self
+            .locked
+            .compare_exchange(false, true, success, failure)
+            .is_ok();
+        #[cfg(feature = "test-hooks")]
+        crate::hooks::after_cas();
+        if locked {
+            self.acquired();
+        }
+        locked
+    }
+
+    /// Called after every successful acquisition.
+    #[inline]
+    fn acquired(&self) {
+        acquire_fence();
+        #[cfg(feature = "owner-tracking")]
+        self.owner
+            .store(crate::owner::current_thread(), Ordering::Relaxed);
+        #[cfg(all(feature = "guard-count", debug_assertions))]
+        self.live_guards.fetch_add(1, Ordering::Relaxed);
+        // written before the release of the lock, so the acquire above makes it visible
+        #[cfg(feature = "timing")]
+        if self.last_unlock.load(Ordering::Relaxed) != 0 {
+            crate::timing::report_handoff(self.last_unlock.swap(0, Ordering::Relaxed));
+        }
+    }
+
+    /// Tries to acquire the lock like [`RawMutex::try_lock`], but uses `Acquire` ordering
+    /// even if the attempt fails.
+    ///
+    /// The regular `try_lock` uses a `Relaxed` ordering on failure, since no critical data is
+    /// accessed when the lock is not acquired. This variant is useful when the caller wants to
+    /// coordinate with lock holders without acquiring the lock: a failed attempt reads the
+    /// state written by the current holder's `compare_exchange`, which continues the release
+    /// sequence of the previous `unlock`. Thus all writes that happened before that `unlock`
+    /// are visible after this method returns, regardless of whether the lock was acquired.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use lock_api::RawMutex;
+    /// let lock = spinning_top::RawSpinlock::INIT;
+    /// assert_eq!(lock.try_lock_acquire_on_fail(), true); // lock it
+    /// assert_eq!(lock.try_lock_acquire_on_fail(), false); // can't be locked a second time
+    /// unsafe { lock.unlock(); }
+    /// ```
+    pub fn try_lock_acquire_on_fail(&self) -> bool {
+        self.try_lock_strong(Ordering::Acquire, Ordering::Acquire)
+    }
+
+    /// Tries to acquire the lock through an unconditional `swap` instead of a
+    /// `compare_exchange`.
+    ///
+    /// The swap always stores `true` and the lock is acquired if the previous value was
+    /// `false`. Storing `true` into a held lock doesn't change its state, so the semantics are
+    /// the same as those of [`RawMutex::try_lock`].
+    ///
+    /// ## Performance
+    ///
+    /// A swap is a single read-modify-write without a comparison, e.g. `xchg` on x86 instead of
+    /// `lock cmpxchg`. On some x86 CPUs, this makes an uncontended acquisition slightly
+    /// cheaper: in a single-threaded loop of acquisitions and releases on an Intel Xeon, the
+    /// swap took about 7ns compared to about 10ns for the `compare_exchange`.
+    ///
+    /// Under contention, `try_lock` usually wins. A swap on a held lock still writes to it,
+    /// so every failed attempt takes the cache line exclusively and slows down the lock holder
+    /// and all other waiters, while a failed `compare_exchange` can be served from a shared
+    /// cache line on many architectures. When calling this method in a loop, only retry after
+    /// [`is_locked`](RawMutex::is_locked) returned `false`, like [`lock`](RawMutex::lock) does.
+    /// Measure on the target hardware before switching.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use lock_api::RawMutex;
+    /// let lock = spinning_top::RawSpinlock::INIT;
+    /// assert!(lock.try_lock_swap());
+    /// assert!(!lock.try_lock_swap());
+    /// unsafe { lock.unlock() };
+    /// assert!(lock.try_lock_swap());
+    /// ```
+    pub fn try_lock_swap(&self) -> bool {
+        #[cfg(feature = "test-hooks")]
+        crate::hooks::before_cas();
+        let locked = !self.locked.swap(true, ACQUIRE);
+        #[cfg(feature = "test-hooks")]
+        crate::hooks::after_cas();
+        if locked {
+            self.acquired();
+        }
+        locked
+    }
+
+    /// Polls the lock once for a cooperative scheduler.
+    ///
+    /// Returns [`Poll::Ready`] if the lock was acquired by this call and [`Poll::Pending`] if
+    /// it is held. This is [`RawMutex::try_lock`] in the shape of [`core::task::Poll`], for
+    /// `no_std` schedulers that drive tasks in a run loop without an async executor: on
+    /// `Pending`, the task yields to the other tasks, one of which eventually releases the lock.
+    /// No waker is registered, so the scheduler has to poll again on its own.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::task::Poll;
+    /// use lock_api::RawMutex;
+    ///
+    /// let lock = spinning_top::RawSpinlock::INIT;
+    /// assert_eq!(lock.poll_lock(), Poll::Ready(()));
+    /// assert_eq!(lock.poll_lock(), Poll::Pending);
+    /// unsafe { lock.unlock() };
+    /// ```
+    pub fn poll_lock(&self) -> Poll<()> {
+        if self.try_lock() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Tries to acquire the lock with the given orderings for the underlying `compare_exchange`.
+    ///
+    /// This is a low-level escape hatch for composing the lock with custom fences, e.g. to use
+    /// `SeqCst` instead of the default `Acquire` ordering on success. The orderings must satisfy
+    /// the following constraints, which are checked with debug assertions:
+    ///
+    /// - `success` must be `Acquire`, `AcqRel`, or `SeqCst`. A weaker ordering would not make the
+    ///   writes of the previous lock holder visible.
+    /// - `failure` must not be `Release` or `AcqRel`, since a failed `compare_exchange` is only a
+    ///   load.
+    /// - `success` must be at least as strong as `failure`, i.e. if `failure` is `SeqCst`,
+    ///   `success` must be `SeqCst` too.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use core::sync::atomic::Ordering;
+    /// use lock_api::RawMutex;
+    ///
+    /// let lock = spinning_top::RawSpinlock::INIT;
+    /// assert!(lock.try_lock_ordered(Ordering::SeqCst, Ordering::Relaxed));
+    /// assert!(!lock.try_lock_ordered(Ordering::SeqCst, Ordering::SeqCst));
+    /// unsafe { lock.unlock() };
+    /// ```
+    pub fn try_lock_ordered(&self, success: Ordering, failure: Ordering) -> bool {
+        debug_assert!(
+            matches!(
+                success,
+                Ordering::Acquire | Ordering::AcqRel | Ordering::SeqCst
+            ),
+            "the success ordering of a lock acquisition must include `Acquire`"
+        );
+        debug_assert!(
+            !matches!(failure, Ordering::Release | Ordering::AcqRel),
+            "the failure ordering must not include `Release`"
+        );
+        debug_assert!(
+            failure != Ordering::SeqCst || success == Ordering::SeqCst,
+            "the success ordering must be at least as strong as the failure ordering"
+        );
+
+        let locked = self
+            .locked
+            .compare_exchange(false, true, success, failure)
+            .is_ok();
+        if locked {
+            self.acquired();
+        }
+        locked
+    }
+
+    /// Returns a numeric identity of this lock, based on its address.
+    ///
+    /// The identity is stable for the lifetime of the lock, which makes it useful for logging or
+    /// for building wait-for graphs. It is only unique among locks that are alive at the same
+    /// time, since the address of a dropped lock can be reused.
+    pub fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Acquires the lock, halting the core with `WFE` instead of spinning while it is held.
+    ///
+    /// On AArch64, every check of the lock word arms the exclusive monitor through a
+    /// load-exclusive instruction. If the lock is held, the core executes `WFE` and sleeps until
+    /// it receives an event. The store of `unlock` clears the monitor, which generates such an
+    /// event, and `unlock` additionally executes `SEV` to wake all waiting cores. This saves
+    /// power compared to spinning, e.g. on battery-powered devices, at the cost of a slightly
+    /// higher wake-up latency. `WFE` may also return for unrelated events, in which case the
+    /// lock is simply checked again.
+    ///
+    /// On all other architectures, this is equivalent to [`lock`](RawMutex::lock).
+    ///
+    /// Requires the `wfe` feature.
+    #[cfg(feature = "wfe")]
+    pub fn lock_wfe(&self) {
+        while !self.try_lock() {
+            #[cfg(target_arch = "aarch64")]
+            wfe::wait_while_locked(&self.locked);
+            #[cfg(not(target_arch = "aarch64"))]
+            while self.is_locked() {
+                crate::relax::relax();
+            }
+        }
+    }
+
+    /// Returns whether the current thread holds this lock, i.e. whether locking it would
+    /// deadlock.
+    ///
+    /// The answer is precise for the current thread: it is `true` if and only if the lock was
+    /// acquired by the current thread and not released since. The owner is only recorded on
+    /// acquisition, so if a guard is sent to another thread, that thread is not considered its
+    /// owner. Whether *another* thread holds the lock can change at any time, so use
+    /// [`is_locked`](RawMutex::is_locked) for that, which is inherently racy.
+    ///
+    /// Requires the `owner-tracking` feature.
+    #[cfg(feature = "owner-tracking")]
+    pub fn would_deadlock(&self) -> bool {
+        let current = crate::owner::current_thread();
+        // only the current thread writes its own identity, so this can't be a stale value
+        current != 0 && self.owner.load(Ordering::Relaxed) == current
+    }
+
+    /// Returns the context tag of the current holder of the lock.
+    ///
+    /// The tag is set by [`SpinlockExt::lock_tagged`](crate::SpinlockExt::lock_tagged) and
+    /// cleared on release, so this returns `None` if the lock is free or if it was acquired
+    /// without a tag. It is meant for crash handlers that report which subsystem held each
+    /// lock. The value is a snapshot without synchronization: the lock may be released or
+    /// acquired again right after reading it.
+    ///
+    /// Requires the `lock-tag` feature.
+    #[cfg(feature = "lock-tag")]
+    pub fn current_tag(&self) -> Option<u32> {
+        match self.tag.load(Ordering::Relaxed) {
+            NO_TAG => None,
+            tag => Some(tag),
+        }
+    }
+
+    /// Records the context tag of the current holder.
+    ///
+    /// The caller must make sure that `tag` is not the reserved [`NO_TAG`].
+    #[cfg(feature = "lock-tag")]
+    pub(crate) fn set_tag(&self, tag: u32) {
+        debug_assert_ne!(tag, NO_TAG);
+        self.tag.store(tag, Ordering::Relaxed);
+    }
+
+    /// Returns whether [`lock`](RawMutex::lock) had to spin since the hint was last cleared.
+    ///
+    /// The flag is set on the slow path of `lock` and only cleared by
+    /// [`clear_contention_hint`](Self::clear_contention_hint), e.g. periodically by the caller.
+    /// The uncontended fast path never touches it. This is a heuristic for choosing a strategy
+    /// before locking: the lock may have become contended or uncontended since the flag was
+    /// written.
+    ///
+    /// Requires the `contention-hint` feature.
+    #[cfg(feature = "contention-hint")]
+    pub fn contention_hint(&self) -> ContentionLevel {
+        if self.contended.load(Ordering::Relaxed) {
+            ContentionLevel::Contended
+        } else {
+            ContentionLevel::Uncontended
+        }
+    }
+
+    /// Resets the [`contention_hint`](Self::contention_hint) to
+    /// [`Uncontended`](ContentionLevel::Uncontended).
+    ///
+    /// Requires the `contention-hint` feature.
+    #[cfg(feature = "contention-hint")]
+    pub fn clear_contention_hint(&self) {
+        self.contended.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns the number of guards that were acquired and not released yet.
+    ///
+    /// The count is incremented on every acquisition and decremented on every release, so it is
+    /// `0` or `1` for a correctly used lock. A guard that is leaked, e.g. through `mem::forget`,
+    /// is never released, so the count stays at `1` forever. A watchdog can use this to detect
+    /// a leaked guard, e.g. if the count stays at `1` for an unusually long time.
+    ///
+    /// The count is only maintained when debug assertions are enabled. In release builds, this
+    /// always returns `None`.
+    ///
+    /// Requires the `guard-count` feature.
+    #[cfg(feature = "guard-count")]
+    pub fn live_guard_count(&self) -> Option<usize> {
+        #[cfg(debug_assertions)]
+        return Some(self.live_guards.load(Ordering::Relaxed));
+        #[cfg(not(debug_assertions))]
+        None
+    }
+
+    /// Returns the caller location of the last acquisition through
+    /// [`SpinlockExt::lock_tracked`](crate::SpinlockExt::lock_tracked).
+    ///
+    /// Returns `None` if the lock was never acquired that way. The location is only recorded
+    /// when debug assertions are enabled, so this always returns `None` in release builds.
+    ///
+    /// Requires the `track-caller` feature.
+    #[cfg(feature = "track-caller")]
+    pub fn last_locked_at(&self) -> Option<&'static Location<'static>> {
+        #[cfg(debug_assertions)]
+        {
+            let location = self.last_locked_at.load(Ordering::Relaxed);
+            // SAFETY: non-null values are only stored by `record_location` from a `&'static`
+            unsafe { location.as_ref() }
+        }
+        #[cfg(not(debug_assertions))]
+        None
+    }
+
+    /// Records the caller location of an acquisition, if debug assertions are enabled.
+    #[cfg(feature = "track-caller")]
+    pub(crate) fn record_location(&self, location: &'static Location<'static>) {
+        #[cfg(debug_assertions)]
+        self.last_locked_at
+            .store(location as *const _ as *mut _, Ordering::Relaxed);
+        #[cfg(not(debug_assertions))]
+        let _ = location;
     }
 }
 
 unsafe impl RawMutex for RawSpinlock {
     const INIT: RawSpinlock = RawSpinlock {
         locked: AtomicBool::new(false),
+        #[cfg(all(feature = "track-caller", debug_assertions))]
+        last_locked_at: AtomicPtr::new(ptr::null_mut()),
+        #[cfg(feature = "owner-tracking")]
+        owner: AtomicUsize::new(0),
+        #[cfg(all(feature = "guard-count", debug_assertions))]
+        live_guards: AtomicUsize::new(0),
+        #[cfg(feature = "contention-hint")]
+        contended: AtomicBool::new(false),
+        #[cfg(feature = "timing")]
+        last_unlock: AtomicU64::new(0),
+        #[cfg(feature = "lock-tag")]
+        tag: AtomicU32::new(NO_TAG),
     };
 
-    // A spinlock guard can be sent to another thread and unlocked there
-    type GuardMarker = GuardSend;
+    // A spinlock guard can be sent to another thread and unlocked there, unless the
+    // `no-send-guards` feature is enabled
+    type GuardMarker = DefaultGuardMarker;
 
     fn lock(&self) {
-        while !self.try_lock_weak() {
+        if self.try_lock_weak() {
+            #[cfg(feature = "histogram")]
+            crate::histogram::record(0);
+            return;
+        }
+
+        #[cfg(feature = "global-stats")]
+        crate::global_stats::record_contention();
+        #[cfg(feature = "contention-hint")]
+        self.contended.store(true, Ordering::Relaxed);
+
+        let mut spin_wait = SpinWait::new();
+        loop {
             // Wait until the lock looks unlocked before retrying
             // Code from https://github.com/mvdnes/spin-rs/commit/d3e60d19adbde8c8e9d3199c7c51e51ee5a20bf6
             while self.is_locked() {
                 // Tell the CPU that we're inside a busy-wait loop
-                hint::spin_loop();
+                spin_wait.spin();
+            }
+            if self.try_lock_weak() {
+                break;
             }
         }
+
+        #[cfg(feature = "histogram")]
+        crate::histogram::record(spin_wait.spins());
     }
 
     fn try_lock(&self) -> bool {
@@ -69,13 +602,24 @@ unsafe impl RawMutex for RawSpinlock {
         // The second Ordering argument specfies the ordering when the compare_exchange
         // fails. Since we don't access any critical data if we fail to acquire the lock,
         // we can use a Relaxed ordering in this case.
-        self.locked
-            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+        self.try_lock_strong(ACQUIRE, Ordering::Relaxed)
     }
 
     unsafe fn unlock(&self) {
-        self.locked.store(false, Ordering::Release);
+        #[cfg(feature = "owner-tracking")]
+        self.owner.store(0, Ordering::Relaxed);
+        #[cfg(feature = "lock-tag")]
+        self.tag.store(NO_TAG, Ordering::Relaxed);
+        #[cfg(all(feature = "guard-count", debug_assertions))]
+        self.live_guards.fetch_sub(1, Ordering::Relaxed);
+        #[cfg(feature = "timing")]
+        if let Some(timestamp) = crate::timing::handoff_timestamp() {
+            self.last_unlock.store(timestamp, Ordering::Relaxed);
+        }
+        release_fence();
+        self.locked.store(false, RELEASE);
+        #[cfg(all(feature = "wfe", target_arch = "aarch64"))]
+        wfe::send_event();
     }
 
     fn is_locked(&self) -> bool {
@@ -84,6 +628,54 @@ unsafe impl RawMutex for RawSpinlock {
     }
 }
 
+#[cfg(all(feature = "wfe", target_arch = "aarch64"))]
+mod wfe {
+    use core::{arch::asm, sync::atomic::AtomicBool};
+
+    /// Sleeps until an event arrives if `locked` is set.
+    #[inline]
+    pub(super) fn wait_while_locked(locked: &AtomicBool) {
+        let state: u32;
+        // SAFETY: the load-exclusive reads an initialized `AtomicBool`, so it is a valid atomic
+        // load that also arms the exclusive monitor for the lock word
+        unsafe {
+            asm!(
+                "ldaxrb {state:w}, [{addr}]",
+                addr = in(reg) locked.as_ptr(),
+                state = out(reg) state,
+                options(nostack, preserves_flags),
+            );
+        }
+        if state != 0 {
+            // SAFETY: `wfe` only halts the core until the next event
+            unsafe { asm!("wfe", options(nomem, nostack, preserves_flags)) };
+        } else {
+            // SAFETY: `clrex` only clears the exclusive monitor
+            unsafe { asm!("clrex", options(nomem, nostack, preserves_flags)) };
+        }
+    }
+
+    /// Wakes all cores that are waiting in `wait_while_locked`.
+    #[inline]
+    pub(super) fn send_event() {
+        // SAFETY: `sev` only signals an event to all cores
+        unsafe { asm!("sev", options(nomem, nostack, preserves_flags)) };
+    }
+}
+
+/// The recent contention of a lock, as reported by
+/// [`RawSpinlock::contention_hint`](RawSpinlock::contention_hint).
+///
+/// Requires the `contention-hint` feature.
+#[cfg(feature = "contention-hint")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentionLevel {
+    /// No acquisition had to spin since the hint was last cleared.
+    Uncontended,
+    /// At least one acquisition had to spin since the hint was last cleared.
+    Contended,
+}
+
 /// A mutual exclusion (Mutex) type based on busy-waiting.
 ///
 /// Calling `lock` (or `try_lock`) on this type returns a [`SpinlockGuard`], which
@@ -133,6 +725,46 @@ unsafe impl RawMutex for RawSpinlock {
 ///     assert_eq!(*data, 1);
 /// }
 /// ```
+///
+/// ## Construction From a Raw Lock
+///
+/// The [`from_raw`](lock_api::Mutex::from_raw) function creates a `Spinlock` from an existing
+/// [`RawSpinlock`] and a value. It is a `const` function, so it can be used in statics:
+///
+/// ```rust
+/// use lock_api::RawMutex;
+/// use spinning_top::{RawSpinlock, Spinlock};
+///
+/// static DATA: Spinlock<u32> = Spinlock::from_raw(RawSpinlock::INIT, 0);
+///
+/// *DATA.lock() += 1;
+/// assert_eq!(*DATA.lock(), 1);
+/// ```
+///
+/// ## Owned Guards
+///
+/// With the `arc_lock` feature, a `Spinlock` wrapped in an `Arc` can be locked through
+/// `lock_arc` and `try_lock_arc`. These return an `ArcSpinlockGuard`, which holds its own clone
+/// of the `Arc` instead of borrowing the lock. The guard is therefore
+/// `'static` (if `T` is) and can be moved into spawned tasks or stored in long-lived structures.
+///
+/// Since the guard keeps the `Arc` alive, the lock is not dropped while an owned guard exists,
+/// even if all other `Arc` handles are gone.
+///
+/// ```rust
+/// # #[cfg(feature = "arc_lock")] {
+/// use spinning_top::Spinlock;
+/// use std::sync::Arc;
+///
+/// let spinlock = Arc::new(Spinlock::new(0));
+/// let mut guard = spinlock.try_lock_arc().unwrap();
+/// assert!(spinlock.try_lock_arc().is_none());
+///
+/// drop(spinlock);
+/// // the guard is still valid because it keeps the lock alive
+/// *guard += 1;
+/// # }
+/// ```
 pub type Spinlock<T> = lock_api::Mutex<RawSpinlock, T>;
 
 /// A RAII guard that frees the spinlock when it goes out of scope.
@@ -161,6 +793,20 @@ pub type Spinlock<T> = lock_api::Mutex<RawSpinlock, T>;
 /// assert!(spinlock.try_lock().is_some());
 pub type SpinlockGuard<'a, T> = lock_api::MutexGuard<'a, RawSpinlock, T>;
 
+/// A RAII guard returned by [`SpinlockGuard::map`](lock_api::MutexGuard::map), giving access to
+/// a part of the locked data.
+///
+/// Like [`SpinlockGuard`], it frees the spinlock when it goes out of scope.
+pub type MappedSpinlockGuard<'a, T> = lock_api::MappedMutexGuard<'a, RawSpinlock, T>;
+
+/// An owned RAII guard returned by [`Spinlock::lock_arc`](lock_api::Mutex::lock_arc) and
+/// [`Spinlock::try_lock_arc`](lock_api::Mutex::try_lock_arc).
+///
+/// Unlike [`SpinlockGuard`], this guard holds a clone of the `Arc` that the lock is stored in,
+/// so it has no lifetime parameter. Requires the `arc_lock` feature.
+#[cfg(feature = "arc_lock")]
+pub type ArcSpinlockGuard<T> = lock_api::ArcMutexGuard<RawSpinlock, T>;
+
 /// Create an unlocked `Spinlock` in a `const` context.
 ///
 /// ## Example
@@ -171,13 +817,132 @@ pub type SpinlockGuard<'a, T> = lock_api::MutexGuard<'a, RawSpinlock, T>;
 /// static SPINLOCK: Spinlock<i32> = const_spinlock(42);
 /// ```
 pub const fn const_spinlock<T>(val: T) -> Spinlock<T> {
-    Spinlock::const_new(<RawSpinlock as lock_api::RawMutex>::INIT, val)
+    Spinlock::from_raw(<RawSpinlock as lock_api::RawMutex>::INIT, val)
+}
+
+/// Locks the `Spinlock` behind a raw pointer.
+///
+/// This is useful if the lock is embedded in a structure that is managed by foreign code, e.g. a
+/// C-allocated struct, and only a raw pointer to it is available.
+///
+/// ## Safety
+///
+/// - `ptr` must be non-null, properly aligned, and point to an initialized `Spinlock<T>`.
+/// - The pointee must stay valid and must not be moved until the returned guard is dropped.
+///   The guard has a `'static` lifetime, so the compiler can't check this.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{lock_ptr, Spinlock};
+///
+/// let spinlock = Box::into_raw(Box::new(Spinlock::new(0)));
+/// *unsafe { lock_ptr(spinlock) } += 1;
+///
+/// let spinlock = unsafe { Box::from_raw(spinlock) };
+/// assert_eq!(spinlock.into_inner(), 1);
+/// ```
+pub unsafe fn lock_ptr<T: ?Sized>(ptr: *const Spinlock<T>) -> SpinlockGuard<'static, T> {
+    (*ptr).lock()
+}
+
+/// Consumes a `Spinlock`, returning the underlying data.
+///
+/// This is a free-function form of [`Spinlock::into_inner`](lock_api::Mutex::into_inner). Since
+/// the lock is taken by value, no other references to it can exist, so the data is returned
+/// without locking or spinning. This also means that the lock may be in any state that is
+/// reachable without outstanding guards, e.g. freshly created or locked and released again.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{into_inner, Spinlock};
+///
+/// let spinlock = Spinlock::new(vec![1]);
+/// spinlock.lock().push(2);
+/// assert_eq!(into_inner(spinlock), [1, 2]);
+/// ```
+pub fn into_inner<T>(lock: Spinlock<T>) -> T {
+    lock.into_inner()
+}
+
+/// Decomposes a `Spinlock` into its raw lock and its data.
+///
+/// Together with [`from_raw_parts`], this separates a lock from the value it guards, e.g. to
+/// move the value to another lock or to place the raw lock at a specific offset of a
+/// `#[repr(C)]` structure. Like [`into_inner`], this doesn't lock: no guard can exist since the
+/// lock is taken by value.
+///
+/// The raw lock keeps its state. It is unlocked unless a guard was leaked, e.g. through
+/// `mem::forget`, in which case it stays locked forever.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{from_raw_parts, into_raw_parts, Spinlock};
+///
+/// let spinlock = Spinlock::new(String::from("data"));
+/// let (raw, data) = into_raw_parts(spinlock);
+/// let spinlock = from_raw_parts(raw, data.len());
+/// assert_eq!(*spinlock.lock(), 4);
+/// ```
+pub fn into_raw_parts<T>(lock: Spinlock<T>) -> (RawSpinlock, T) {
+    let lock = ManuallyDrop::new(lock);
+    // SAFETY: the lock is owned and never used or dropped again, so both parts are moved out
+    // of it exactly once
+    unsafe { (ptr::read(lock.raw()), ptr::read(lock.data_ptr())) }
+}
+
+/// Assembles a `Spinlock` from a raw lock and data.
+///
+/// This is the counterpart of [`into_raw_parts`] and a free-function form of
+/// [`Spinlock::from_raw`](lock_api::Mutex::from_raw). The raw lock must be in a state that is
+/// consistent with the data: if it is locked, e.g. because it was taken from a lock with a
+/// leaked guard, the new `Spinlock` is locked as well and can't be acquired.
+pub const fn from_raw_parts<T>(raw: RawSpinlock, data: T) -> Spinlock<T> {
+    Spinlock::from_raw(raw, data)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(all(feature = "guard-count", debug_assertions))]
+    #[test]
+    fn live_guard_count() {
+        let spinlock = Spinlock::new(0);
+        let raw = unsafe { spinlock.raw() };
+        assert_eq!(raw.live_guard_count(), Some(0));
+
+        let guard = spinlock.lock();
+        assert_eq!(raw.live_guard_count(), Some(1));
+        drop(guard);
+        drop(spinlock.try_lock().unwrap());
+        assert_eq!(raw.live_guard_count(), Some(0));
+
+        // a leaked guard is never released
+        core::mem::forget(spinlock.lock());
+        assert_eq!(raw.live_guard_count(), Some(1));
+        assert!(spinlock.try_lock().is_none());
+        assert_eq!(raw.live_guard_count(), Some(1));
+    }
+
+    #[test]
+    fn with_state_locked() {
+        let lock = RawSpinlock::with_state(true);
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock());
+        unsafe { lock.unlock() };
+        assert!(!lock.is_locked());
+        assert!(lock.try_lock());
+
+        let spinlock = Spinlock::from_raw(RawSpinlock::with_state(true), 0);
+        assert!(spinlock.is_locked());
+        assert!(spinlock.try_lock().is_none());
+        unsafe { spinlock.force_unlock() };
+        assert_eq!(*spinlock.lock(), 0);
+    }
+
     #[test]
     fn create_and_lock() {
         let spinlock = Spinlock::new(42);
@@ -186,6 +951,235 @@ mod tests {
         assert_eq!(*data.unwrap(), 42);
     }
 
+    #[test]
+    fn raw_parts_roundtrip() {
+        let spinlock = Spinlock::new(1);
+        *spinlock.lock() += 1;
+        let (raw, data) = into_raw_parts(spinlock);
+        assert!(!raw.is_locked());
+        assert_eq!(data, 2);
+
+        let spinlock = from_raw_parts(raw, data * 10);
+        let guard = spinlock.lock();
+        assert!(spinlock.try_lock().is_none());
+        assert_eq!(*guard, 20);
+        drop(guard);
+
+        // a leaked guard stays leaked across the decomposition
+        core::mem::forget(spinlock.lock());
+        let (raw, data) = into_raw_parts(spinlock);
+        assert!(raw.is_locked());
+        assert!(from_raw_parts(raw, data).try_lock().is_none());
+    }
+
+    #[cfg(feature = "lock-tag")]
+    #[test]
+    fn tag_cleared_on_release() {
+        use crate::SpinlockExt;
+
+        let spinlock = Spinlock::new(0);
+        let raw = unsafe { spinlock.raw() };
+        assert_eq!(raw.current_tag(), None);
+
+        let guard = spinlock.lock_tagged(7);
+        assert_eq!(raw.current_tag(), Some(7));
+        drop(guard);
+        assert_eq!(raw.current_tag(), None);
+
+        // an untagged acquisition doesn't report a stale tag
+        let _guard = spinlock.lock();
+        assert_eq!(spinlock.current_tag(), None);
+    }
+
+    #[cfg(all(feature = "lock-tag", feature = "std"))]
+    #[test]
+    fn reserved_tag_leaves_lock_free() {
+        use crate::SpinlockExt;
+        use core::cell::Cell;
+        use std::{
+            boxed::Box,
+            panic::{self, AssertUnwindSafe},
+        };
+
+        static SPINLOCK: Spinlock<i32> = Spinlock::new(0);
+        std::thread_local! {
+            static LOCKED_AT_PANIC: Cell<Option<bool>> = const { Cell::new(None) };
+        }
+
+        // records whether the lock is held when the panic starts, before unwinding
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            LOCKED_AT_PANIC.with(|locked| locked.set(Some(SPINLOCK.is_locked())));
+            previous(info);
+        }));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| SPINLOCK.lock_tagged(u32::MAX)));
+        assert!(result.is_err());
+        assert_eq!(LOCKED_AT_PANIC.with(Cell::get), Some(false));
+        assert_eq!(SPINLOCK.current_tag(), None);
+    }
+
+    #[test]
+    fn from_raw() {
+        static SPINLOCK: Spinlock<i32> = Spinlock::from_raw(RawSpinlock::INIT, 42);
+        assert!(!SPINLOCK.is_locked());
+        assert_eq!(*SPINLOCK.lock(), 42);
+    }
+
+    #[test]
+    fn id() {
+        let lock1 = RawSpinlock::INIT;
+        let lock2 = RawSpinlock::INIT;
+        assert_ne!(lock1.id(), lock2.id());
+        assert_eq!(lock1.id(), lock1.id());
+        lock1.lock();
+        assert_eq!(lock1.id(), lock1.id());
+    }
+
+    #[cfg(feature = "arc_lock")]
+    #[test]
+    fn try_lock_arc() {
+        extern crate std;
+        use std::sync::Arc;
+
+        let spinlock = Arc::new(Spinlock::new(0));
+        let guard = spinlock.try_lock_arc().unwrap();
+        assert!(spinlock.try_lock_arc().is_none());
+        drop(guard);
+
+        let mut guard: ArcSpinlockGuard<i32> = spinlock.try_lock_arc().unwrap();
+        drop(spinlock);
+        *guard += 1;
+
+        // the guard is 'static, so it can be moved into another thread
+        #[cfg(not(feature = "no-send-guards"))]
+        {
+            let value = std::thread::spawn(move || *guard).join().unwrap();
+            assert_eq!(value, 1);
+        }
+        #[cfg(feature = "no-send-guards")]
+        assert_eq!(*guard, 1);
+    }
+
+    #[test]
+    fn into_inner_fresh() {
+        let spinlock = Spinlock::new(42);
+        assert_eq!(spinlock.into_inner(), 42);
+        assert_eq!(into_inner(Spinlock::new(42)), 42);
+    }
+
+    #[test]
+    fn into_inner_after_unlock() {
+        let spinlock = Spinlock::new(1);
+        *spinlock.lock() += 1;
+        assert!(!spinlock.is_locked());
+        assert_eq!(into_inner(spinlock), 2);
+    }
+
+    #[test]
+    fn into_inner_generic() {
+        fn teardown<T>(lock: Spinlock<T>) -> T {
+            into_inner(lock)
+        }
+
+        assert_eq!(teardown(Spinlock::new("a")), "a");
+        assert_eq!(teardown(Spinlock::new(())), ());
+    }
+
+    #[test]
+    fn try_lock_ordered() {
+        use core::sync::atomic::Ordering::*;
+
+        let combinations = [
+            (Acquire, Relaxed),
+            (Acquire, Acquire),
+            (AcqRel, Acquire),
+            (SeqCst, Relaxed),
+            (SeqCst, Acquire),
+            (SeqCst, SeqCst),
+        ];
+        for &(success, failure) in &combinations {
+            let lock = RawSpinlock::INIT;
+            assert!(lock.try_lock_ordered(success, failure));
+            assert!(!lock.try_lock_ordered(success, failure));
+            unsafe { lock.unlock() };
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "at least as strong")]
+    fn try_lock_ordered_invalid() {
+        let lock = RawSpinlock::INIT;
+        lock.try_lock_ordered(Ordering::Acquire, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn lock_through_pointer() {
+        extern crate std;
+        use std::boxed::Box;
+
+        let ptr = Box::into_raw(Box::new(Spinlock::new(1)));
+        {
+            let mut guard = unsafe { lock_ptr(ptr) };
+            assert!(unsafe { &*ptr }.is_locked());
+            *guard += 1;
+        }
+        let spinlock = unsafe { Box::from_raw(ptr) };
+        assert!(!spinlock.is_locked());
+        assert_eq!(spinlock.into_inner(), 2);
+    }
+
+    #[test]
+    fn try_lock_swap_grants_once() {
+        let lock = RawSpinlock::INIT;
+        assert!(lock.try_lock_swap());
+        assert!(lock.is_locked());
+        // neither variant acquires a held lock, and the failed swap keeps it locked
+        assert!(!lock.try_lock_swap());
+        assert!(!lock.try_lock());
+        assert!(lock.is_locked());
+        unsafe { lock.unlock() };
+        assert!(!lock.is_locked());
+        assert!(lock.try_lock_swap());
+        unsafe { lock.unlock() };
+    }
+
+    #[test]
+    fn try_lock_swap_mutual_exclusion() {
+        extern crate std;
+        use core::sync::atomic::AtomicUsize;
+        use std::{sync::Arc, thread, vec::Vec};
+
+        const THREADS: usize = 3;
+        const ITERATIONS: usize = 1000;
+
+        let lock = Arc::new(RawSpinlock::INIT);
+        let acquisitions = Arc::new(AtomicUsize::new(0));
+        let holders = Arc::new(AtomicUsize::new(0));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let lock = lock.clone();
+                let acquisitions = acquisitions.clone();
+                let holders = holders.clone();
+                thread::spawn(move || {
+                    for _ in 0..ITERATIONS {
+                        if lock.try_lock_swap() {
+                            assert_eq!(holders.fetch_add(1, Ordering::Relaxed), 0);
+                            acquisitions.fetch_add(1, Ordering::Relaxed);
+                            holders.fetch_sub(1, Ordering::Relaxed);
+                            unsafe { lock.unlock() };
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert!(acquisitions.load(Ordering::Relaxed) > 0);
+        assert!(!lock.is_locked());
+    }
+
     #[test]
     fn mutual_exclusion() {
         let spinlock = Spinlock::new(1);
@@ -215,4 +1209,111 @@ mod tests {
         core::mem::drop(data3);
         assert!(spinlock3.try_lock().is_some());
     }
+
+    #[cfg(feature = "wfe")]
+    #[test]
+    fn lock_wfe_mutual_exclusion() {
+        extern crate std;
+        use std::{sync::Arc, thread, vec::Vec};
+
+        const THREADS: usize = 2;
+        const INCREMENTS: usize = 1000;
+
+        let spinlock = Arc::new(Spinlock::new(0));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let spinlock = spinlock.clone();
+                thread::spawn(move || {
+                    for _ in 0..INCREMENTS {
+                        *crate::SpinlockExt::lock_wfe(&*spinlock) += 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*spinlock.lock(), THREADS * INCREMENTS);
+    }
+
+    #[cfg(feature = "strict-barrier")]
+    #[test]
+    fn strict_barrier_mutual_exclusion() {
+        let spinlock = Spinlock::new(0);
+        *spinlock.lock() += 1;
+        let guard = spinlock.lock();
+        assert!(spinlock.try_lock().is_none());
+        drop(guard);
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[cfg(feature = "single-core")]
+    #[test]
+    fn single_core_mutual_exclusion() {
+        let lock = RawSpinlock::INIT;
+        lock.lock();
+        assert!(lock.is_locked());
+        assert!(!lock.try_lock());
+        unsafe { lock.unlock() };
+        assert!(!lock.is_locked());
+        assert!(lock.try_lock());
+        unsafe { lock.unlock() };
+
+        let spinlock = Spinlock::new(0);
+        *spinlock.lock() += 1;
+        let guard = spinlock.lock();
+        assert!(spinlock.try_lock().is_none());
+        drop(guard);
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[test]
+    fn try_lock_acquire_on_fail_synchronizes() {
+        extern crate std;
+
+        use core::{hint, sync::atomic::AtomicUsize};
+        use std::{sync::Arc, thread};
+
+        struct Shared {
+            lock: RawSpinlock,
+            payload: AtomicUsize,
+            relocked: AtomicBool,
+            done: AtomicBool,
+        }
+
+        let shared = Arc::new(Shared {
+            lock: RawSpinlock::INIT,
+            payload: AtomicUsize::new(0),
+            relocked: AtomicBool::new(false),
+            done: AtomicBool::new(false),
+        });
+
+        let writer = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                shared.lock.lock();
+                shared.payload.store(42, Ordering::Relaxed);
+                unsafe { shared.lock.unlock() };
+
+                // take the lock again, so that the reader's attempt fails
+                shared.lock.lock();
+                shared.relocked.store(true, Ordering::Relaxed);
+                while !shared.done.load(Ordering::Relaxed) {
+                    hint::spin_loop();
+                }
+                unsafe { shared.lock.unlock() };
+            })
+        };
+
+        while !shared.relocked.load(Ordering::Relaxed) {
+            hint::spin_loop();
+        }
+        assert!(!shared.lock.try_lock_acquire_on_fail());
+        // the failed attempt synchronized with the writer's first unlock
+        assert_eq!(shared.payload.load(Ordering::Relaxed), 42);
+        shared.done.store(true, Ordering::Relaxed);
+
+        writer.join().unwrap();
+        assert!(shared.lock.try_lock_acquire_on_fail());
+    }
 }
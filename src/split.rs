@@ -0,0 +1,158 @@
+use crate::{RawSpinlock, SpinlockGuard};
+use core::{fmt, marker::PhantomData, mem};
+use lock_api::RawMutex;
+
+/// Splits a [`SpinlockGuard`] into mutable borrows of two disjoint parts of the guarded data.
+///
+/// The closure `f` selects the two parts, e.g. two fields of a struct. Since both references
+/// are derived from the same `&mut T`, the borrow checker guarantees that they don't overlap.
+/// The returned [`SplitSpinlockGuard`] holds the lock until it is dropped.
+///
+/// If `f` panics, the lock is released.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{map_split, Spinlock};
+///
+/// struct Stats {
+///     hits: u32,
+///     misses: u32,
+/// }
+///
+/// let spinlock = Spinlock::new(Stats { hits: 0, misses: 0 });
+/// let mut split = map_split(spinlock.lock(), |s| (&mut s.hits, &mut s.misses));
+/// let (hits, misses) = split.parts_mut();
+/// *hits += 1;
+/// *misses += *hits;
+/// drop(split);
+/// assert_eq!(spinlock.lock().misses, 1);
+/// ```
+pub fn map_split<'a, T: ?Sized, U: ?Sized, V: ?Sized>(
+    mut guard: SpinlockGuard<'a, T>,
+    f: impl FnOnce(&mut T) -> (&mut U, &mut V),
+) -> SplitSpinlockGuard<'a, U, V> {
+    let (left, right) = f(&mut guard);
+    let (left, right) = (left as *mut U, right as *mut V);
+    // SAFETY: the raw lock is only used to unlock it when the split guard is dropped, which
+    // takes over the responsibility of the original guard
+    let raw = unsafe { SpinlockGuard::mutex(&guard).raw() };
+    mem::forget(guard);
+    SplitSpinlockGuard {
+        raw,
+        left,
+        right,
+        _marker: PhantomData,
+    }
+}
+
+/// A RAII guard for two disjoint parts of the data protected by a spinlock.
+///
+/// Created by [`map_split`]. Both parts can be borrowed at the same time through
+/// [`parts_mut`](Self::parts_mut). The lock is released when this guard is dropped.
+///
+/// The two parts share a single guard because the lock may only be released once both parts
+/// are no longer used. Tracking this for two independent guards would require a shared counter,
+/// which the lock doesn't have.
+pub struct SplitSpinlockGuard<'a, U: ?Sized, V: ?Sized> {
+    raw: &'a RawSpinlock,
+    left: *mut U,
+    right: *mut V,
+    _marker: PhantomData<(&'a mut U, &'a mut V)>,
+}
+
+// SAFETY: the guard behaves like a pair of mutable references. Releasing the lock from another
+// thread is allowed for `RawSpinlock`, unless the `no-send-guards` feature is enabled.
+#[cfg(not(feature = "no-send-guards"))]
+unsafe impl<U: ?Sized + Send, V: ?Sized + Send> Send for SplitSpinlockGuard<'_, U, V> {}
+// SAFETY: the guard only hands out shared references through `&self`
+unsafe impl<U: ?Sized + Sync, V: ?Sized + Sync> Sync for SplitSpinlockGuard<'_, U, V> {}
+
+impl<U: ?Sized, V: ?Sized> SplitSpinlockGuard<'_, U, V> {
+    /// Returns shared references to both parts.
+    pub fn parts(&self) -> (&U, &V) {
+        // SAFETY: the parts are disjoint and valid while the lock is held
+        unsafe { (&*self.left, &*self.right) }
+    }
+
+    /// Returns mutable references to both parts.
+    pub fn parts_mut(&mut self) -> (&mut U, &mut V) {
+        // SAFETY: the parts are disjoint and valid while the lock is held
+        unsafe { (&mut *self.left, &mut *self.right) }
+    }
+}
+
+impl<U: ?Sized, V: ?Sized> Drop for SplitSpinlockGuard<'_, U, V> {
+    fn drop(&mut self) {
+        // SAFETY: the lock was acquired by the guard passed to `map_split`
+        unsafe { self.raw.unlock() };
+    }
+}
+
+impl<U: ?Sized + fmt::Debug, V: ?Sized + fmt::Debug> fmt::Debug for SplitSpinlockGuard<'_, U, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (left, right) = self.parts();
+        f.debug_tuple("SplitSpinlockGuard")
+            .field(&left)
+            .field(&right)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::Spinlock;
+    use std::{string::String, vec, vec::Vec};
+
+    struct Data {
+        name: String,
+        values: Vec<u32>,
+    }
+
+    #[test]
+    fn split_fields() {
+        let spinlock = Spinlock::new(Data {
+            name: String::from("a"),
+            values: vec![1],
+        });
+
+        let mut split = map_split(spinlock.lock(), |d| (&mut d.name, &mut d.values));
+        assert!(spinlock.is_locked());
+        let (name, values) = split.parts_mut();
+        name.push('b');
+        values.push(name.len() as u32);
+        assert_eq!(split.parts(), (&String::from("ab"), &vec![1, 2]));
+        drop(split);
+
+        assert!(!spinlock.is_locked());
+        let data = spinlock.lock();
+        assert_eq!(data.name, "ab");
+        assert_eq!(data.values, [1, 2]);
+    }
+
+    #[test]
+    fn split_panic_releases_lock() {
+        let spinlock = Spinlock::new((1, 2));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map_split(spinlock.lock(), |_| -> (&mut i32, &mut i32) {
+                panic!("split")
+            })
+        }));
+        assert!(result.is_err());
+        assert!(!spinlock.is_locked());
+    }
+
+    #[test]
+    fn split_tuple() {
+        let spinlock = Spinlock::new((1, [2, 3]));
+        let mut split = map_split(spinlock.lock(), |(a, b)| (a, &mut b[..]));
+        let (a, b) = split.parts_mut();
+        *a += b.iter().sum::<i32>();
+        b[0] = 0;
+        drop(split);
+        assert_eq!(*spinlock.lock(), (6, [0, 3]));
+    }
+}
@@ -0,0 +1,456 @@
+//! Selectable busy-wait strategies for [`StrategySpinlock`].
+
+use crate::{relax::relax, spinlock::DefaultGuardMarker, RawSpinlock};
+use core::{fmt, marker::PhantomData};
+use lock_api::RawMutex;
+
+/// Decides how a waiting thread spins between two acquisition attempts.
+///
+/// A new instance is created through [`Default`] for every contended acquisition of a
+/// [`RawStrategySpinlock`], so strategies can keep per-acquisition state, e.g. a backoff step.
+pub trait RelaxStrategy: Default {
+    /// Waits once after the lock was observed to be held.
+    fn relax(&mut self);
+}
+
+/// Issues a single busy-wait hint per iteration.
+///
+/// This is the strategy used by [`RawSpinlock`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Spin;
+
+impl RelaxStrategy for Spin {
+    #[inline]
+    fn relax(&mut self) {
+        relax();
+    }
+}
+
+/// Issues an exponentially growing burst of busy-wait hints per iteration.
+///
+/// The `k`-th failed check of the lock is followed by `2^k` back-to-back
+/// [`spin_loop`](core::hint::spin_loop) hints, up to a maximum of
+/// [`MAX_BURST`](Self::MAX_BURST) hints.
+///
+/// On x86, the hint is the `PAUSE` instruction. Its latency varies a lot between
+/// microarchitectures, from around 10 cycles on older Intel CPUs to more than 100 cycles since
+/// Skylake. Executing several `PAUSE` instructions back-to-back keeps the waiting core idle for
+/// longer than a loop that re-reads the lock after every instruction. This reduces the traffic on
+/// the cache line of the lock, which speeds up the handover to the next owner, and lowers the
+/// power consumption of waiting cores. Growing the burst size exponentially keeps the latency low
+/// for short waits while adapting to long waits on any microarchitecture.
+///
+/// The downside is a higher wake-up latency: a waiter may only notice that the lock was released
+/// after finishing its current burst.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExponentialPause {
+    shift: u32,
+}
+
+impl ExponentialPause {
+    /// The maximum number of busy-wait hints issued per iteration.
+    pub const MAX_BURST: u32 = 1 << Self::MAX_SHIFT;
+
+    const MAX_SHIFT: u32 = 6;
+}
+
+impl RelaxStrategy for ExponentialPause {
+    #[inline]
+    fn relax(&mut self) {
+        for _ in 0..1u32 << self.shift {
+            relax();
+        }
+        if self.shift < Self::MAX_SHIFT {
+            self.shift += 1;
+        }
+    }
+}
+
+/// Spins for an exponentially growing number of CPU cycles per iteration, measured through the
+/// timestamp counter.
+///
+/// The `k`-th failed check of the lock is followed by a wait of `INITIAL * 2^k` cycles, up to
+/// `MAX` cycles. The wait issues busy-wait hints until the timestamp counter, read through the
+/// `RDTSC` instruction, advanced by the target number of cycles. Unlike the number of `PAUSE`
+/// instructions of [`ExponentialPause`], whose latency varies between microarchitectures, this
+/// gives the same backoff timing on all x86 CPUs. Both bounds can be configured through the
+/// const parameters.
+///
+/// ## Caveats
+///
+/// - On CPUs without an invariant TSC, which is rare since the Nehalem and Bulldozer
+///   generations, the counter rate changes with the clock frequency, so the waits are shorter
+///   while the core is throttled.
+/// - The counters of different cores or sockets are not necessarily synchronized. If the thread
+///   is migrated to another core while waiting, the observed delta may be off, so a single wait
+///   can end early or take longer. A backwards jump is treated as the end of the wait, so the
+///   thread can't get stuck.
+/// - `RDTSC` itself takes around 20 to 40 cycles, so targets below that are rounded up.
+///
+/// Requires the `tsc-backoff` feature and an x86 or x86_64 target.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{StrategySpinlock, TscBackoff};
+///
+/// // start with 256 cycles and back off up to 16384 cycles
+/// let spinlock = StrategySpinlock::<_, TscBackoff<256, 16384>>::new(0);
+/// *spinlock.lock() += 1;
+/// assert_eq!(*spinlock.lock(), 1);
+/// ```
+#[cfg(all(
+    feature = "tsc-backoff",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+#[derive(Debug, Clone, Copy)]
+pub struct TscBackoff<const INITIAL: u64 = 128, const MAX: u64 = 8192> {
+    target: u64,
+}
+
+#[cfg(all(
+    feature = "tsc-backoff",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+impl<const INITIAL: u64, const MAX: u64> Default for TscBackoff<INITIAL, MAX> {
+    fn default() -> Self {
+        Self { target: INITIAL }
+    }
+}
+
+#[cfg(all(
+    feature = "tsc-backoff",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+impl<const INITIAL: u64, const MAX: u64> RelaxStrategy for TscBackoff<INITIAL, MAX> {
+    #[inline]
+    fn relax(&mut self) {
+        let start = rdtsc();
+        loop {
+            relax();
+            let now = rdtsc();
+            // a counter that went backwards after a migration ends the wait
+            if now < start || now - start >= self.target {
+                break;
+            }
+        }
+        self.target = self.target.saturating_mul(2).min(MAX);
+    }
+}
+
+/// Reads the timestamp counter of the current core.
+#[cfg(all(
+    feature = "tsc-backoff",
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+#[inline]
+fn rdtsc() -> u64 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::_rdtsc;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::_rdtsc;
+
+    // SAFETY: `RDTSC` is available on all x86 CPUs supported by Rust
+    unsafe { _rdtsc() }
+}
+
+/// Spins for a number of iterations, then yields the time slice on every further iteration.
+///
+/// Spinning is cheapest for short waits, while yielding lets the lock holder run if it was
+/// preempted, e.g. when there are more threads than cores. The number of spin iterations before
+/// yielding is given by [`spin_yield_threshold`].
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct SpinThenYield {
+    remaining_spins: u32,
+}
+
+#[cfg(feature = "std")]
+impl SpinThenYield {
+    /// The threshold that is used if the `auto-tune` feature is disabled.
+    pub const DEFAULT_THRESHOLD: u32 = 100;
+}
+
+#[cfg(feature = "std")]
+impl Default for SpinThenYield {
+    fn default() -> Self {
+        Self {
+            remaining_spins: spin_yield_threshold(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl RelaxStrategy for SpinThenYield {
+    #[inline]
+    fn relax(&mut self) {
+        if self.remaining_spins > 0 {
+            self.remaining_spins -= 1;
+            relax();
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// Returns the number of spin iterations of [`SpinThenYield`] before it starts yielding.
+///
+/// Without the `auto-tune` feature, this is [`SpinThenYield::DEFAULT_THRESHOLD`].
+///
+/// With the `auto-tune` feature, the threshold is calibrated once, on the first call, by
+/// measuring the cost of a spin iteration and of a `yield_now` call. The threshold is the
+/// number of spin iterations that take as long as a yield, so a waiter spends at most about
+/// twice the optimal time before it yields. The calibration takes a few microseconds; later
+/// calls only read the cached result.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn spin_yield_threshold() -> u32 {
+    #[cfg(feature = "auto-tune")]
+    return auto_tune::threshold();
+
+    #[cfg(not(feature = "auto-tune"))]
+    SpinThenYield::DEFAULT_THRESHOLD
+}
+
+#[cfg(feature = "auto-tune")]
+mod auto_tune {
+    use std::{hint, sync::OnceLock, thread, time::Instant};
+
+    const SPINS: u32 = 256;
+    const YIELDS: u32 = 4;
+    pub(super) const MAX_THRESHOLD: u32 = 100_000;
+
+    static THRESHOLD: OnceLock<u32> = OnceLock::new();
+
+    pub(super) fn threshold() -> u32 {
+        *THRESHOLD.get_or_init(calibrate)
+    }
+
+    #[cfg(test)]
+    pub(super) fn cached() -> Option<u32> {
+        THRESHOLD.get().copied()
+    }
+
+    fn calibrate() -> u32 {
+        let start = Instant::now();
+        for _ in 0..SPINS {
+            hint::spin_loop();
+        }
+        let spin = start.elapsed() / SPINS;
+
+        let start = Instant::now();
+        for _ in 0..YIELDS {
+            thread::yield_now();
+        }
+        let yield_ = start.elapsed() / YIELDS;
+
+        // a spin iteration can be faster than the resolution of the clock
+        let threshold = yield_.as_nanos() / spin.as_nanos().max(1);
+        threshold.clamp(1, MAX_THRESHOLD.into()) as u32
+    }
+}
+
+/// A [`RawSpinlock`] that spins according to the [`RelaxStrategy`] `R`.
+pub struct RawStrategySpinlock<R: RelaxStrategy = Spin> {
+    inner: RawSpinlock,
+    _strategy: PhantomData<fn() -> R>,
+}
+
+unsafe impl<R: RelaxStrategy> RawMutex for RawStrategySpinlock<R> {
+    const INIT: Self = Self {
+        inner: RawSpinlock::INIT,
+        _strategy: PhantomData,
+    };
+
+    // A spinlock guard can be sent to another thread and unlocked there, unless the
+    // `no-send-guards` feature is enabled
+    type GuardMarker = DefaultGuardMarker;
+
+    fn lock(&self) {
+        if self.inner.try_lock() {
+            return;
+        }
+        // only constructed for contended acquisitions, since creating it may be expensive
+        let mut strategy = R::default();
+        loop {
+            // Wait until the lock looks unlocked before retrying
+            while self.inner.is_locked() {
+                strategy.relax();
+            }
+            if self.inner.try_lock() {
+                return;
+            }
+        }
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.inner.try_lock()
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.inner.unlock();
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+}
+
+impl<R: RelaxStrategy> fmt::Debug for RawStrategySpinlock<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawStrategySpinlock")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// A spinlock that spins according to the [`RelaxStrategy`] `R`.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::{ExponentialPause, StrategySpinlock};
+///
+/// let spinlock = StrategySpinlock::<_, ExponentialPause>::new(0);
+/// *spinlock.lock() += 1;
+/// assert_eq!(*spinlock.lock(), 1);
+/// ```
+pub type StrategySpinlock<T, R = Spin> = lock_api::Mutex<RawStrategySpinlock<R>, T>;
+
+/// A RAII guard returned by [`StrategySpinlock::lock`](lock_api::Mutex::lock).
+pub type StrategySpinlockGuard<'a, T, R = Spin> =
+    lock_api::MutexGuard<'a, RawStrategySpinlock<R>, T>;
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread, vec::Vec};
+
+    #[test]
+    fn exponential_pause_burst_is_capped() {
+        let mut strategy = ExponentialPause::default();
+        for _ in 0..2 * ExponentialPause::MAX_SHIFT {
+            strategy.relax();
+        }
+        assert_eq!(strategy.shift, ExponentialPause::MAX_SHIFT);
+    }
+
+    fn mutual_exclusion<R: RelaxStrategy + 'static>() {
+        const THREADS: usize = 4;
+        const INCREMENTS: usize = 1000;
+
+        let spinlock = Arc::new(StrategySpinlock::<_, R>::new(0));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let spinlock = spinlock.clone();
+                thread::spawn(move || {
+                    for _ in 0..INCREMENTS {
+                        let mut guard = spinlock.lock();
+                        let value = *guard;
+                        *guard = value + 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(*spinlock.lock(), THREADS * INCREMENTS);
+    }
+
+    #[test]
+    fn mutual_exclusion_spin() {
+        mutual_exclusion::<Spin>();
+    }
+
+    #[test]
+    fn mutual_exclusion_exponential_pause() {
+        mutual_exclusion::<ExponentialPause>();
+    }
+
+    #[cfg(all(
+        feature = "tsc-backoff",
+        any(target_arch = "x86", target_arch = "x86_64")
+    ))]
+    #[test]
+    fn mutual_exclusion_tsc_backoff() {
+        mutual_exclusion::<TscBackoff>();
+        mutual_exclusion::<TscBackoff<1, 1>>();
+    }
+
+    #[cfg(all(
+        feature = "tsc-backoff",
+        any(target_arch = "x86", target_arch = "x86_64")
+    ))]
+    #[test]
+    fn tsc_backoff_waits_target_cycles() {
+        let mut strategy = TscBackoff::<1000, 4000>::default();
+        let mut short_waits = 0;
+        for expected in [1000, 2000, 4000, 4000] {
+            assert_eq!(strategy.target, expected);
+            let start = rdtsc();
+            strategy.relax();
+            let elapsed = rdtsc().wrapping_sub(start);
+            // a wait only ends early if the thread was migrated to a core whose counter is
+            // behind, which is unlikely to happen more than once. There is no upper bound since
+            // the thread may be preempted while waiting.
+            if elapsed < expected {
+                short_waits += 1;
+            }
+        }
+        assert!(short_waits <= 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn mutual_exclusion_spin_then_yield() {
+        mutual_exclusion::<SpinThenYield>();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn spin_then_yield_counts_down() {
+        let mut strategy = SpinThenYield { remaining_spins: 2 };
+        strategy.relax();
+        strategy.relax();
+        assert_eq!(strategy.remaining_spins, 0);
+        strategy.relax();
+        assert_eq!(strategy.remaining_spins, 0);
+    }
+
+    #[cfg(feature = "auto-tune")]
+    #[test]
+    fn auto_tuned_threshold() {
+        use std::{sync::Barrier, time::Duration};
+
+        // the strategy, and thus the calibration, is only created for contended acquisitions
+        let spinlock = Arc::new(StrategySpinlock::<_, SpinThenYield>::new(0));
+        let barrier = Arc::new(Barrier::new(2));
+        let holder = {
+            let spinlock = spinlock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let _guard = spinlock.lock();
+                barrier.wait();
+                thread::sleep(Duration::from_millis(10));
+            })
+        };
+        barrier.wait();
+        *spinlock.lock() += 1;
+        holder.join().unwrap();
+
+        let threshold = auto_tune::cached().unwrap();
+        assert!(threshold > 0);
+        assert!(threshold <= auto_tune::MAX_THRESHOLD);
+        assert_eq!(spin_yield_threshold(), threshold);
+    }
+}
@@ -0,0 +1,249 @@
+//! Measurement of critical section durations, enabled by the `timing` feature.
+
+use crate::SpinlockGuard;
+use core::{
+    convert::TryFrom,
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+static HOLD_TIME_SINK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static HANDOFF_SINK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Registers a function that receives the hold duration of every [`TimedSpinlockGuard`].
+///
+/// The function is called after the lock was released, so it may lock the spinlock again. It
+/// replaces any previously registered function. By default, hold durations are discarded.
+///
+/// Requires the `timing` feature.
+pub fn set_hold_time_sink(sink: fn(Duration)) {
+    HOLD_TIME_SINK.store(sink as *mut (), Ordering::Relaxed);
+}
+
+fn hold_time_sink() -> Option<fn(Duration)> {
+    let sink = HOLD_TIME_SINK.load(Ordering::Relaxed);
+    if sink.is_null() {
+        None
+    } else {
+        // SAFETY: non-null values are only stored by `set_hold_time_sink`
+        Some(unsafe { core::mem::transmute::<*mut (), fn(Duration)>(sink) })
+    }
+}
+
+/// Registers a function that receives the handoff latency of every [`RawSpinlock`] acquisition.
+///
+/// The handoff latency is the time between an unlock and the next successful acquisition of
+/// the same lock. For a contended lock, it shows how quickly a waiter notices the release, which
+/// is a proxy for the propagation delay between cores. For an uncontended lock, it is the time
+/// the lock sat free. The function replaces any previously registered function.
+///
+/// Unlock timestamps are only recorded while a function is registered, so the first acquisition
+/// of a lock after registering reports nothing. The function is called by the acquiring thread
+/// while it holds the lock, so it must not lock the same spinlock again.
+///
+/// ## Precision
+///
+/// The measurement is based on [`Instant`], so its resolution is that of the platform's
+/// monotonic clock, and reading the clock adds some overhead (tens of nanoseconds on common
+/// platforms) to every unlock. The latency is measured from just before the releasing store
+/// to just after the acquiring thread observed the release, so it includes the spin loop delay
+/// of the waiter and any time the acquiring thread was preempted. Values below about a
+/// microsecond should thus be treated as noise.
+///
+/// Requires the `timing` feature.
+///
+/// [`RawSpinlock`]: crate::RawSpinlock
+pub fn set_handoff_sink(sink: fn(Duration)) {
+    HANDOFF_SINK.store(sink as *mut (), Ordering::Relaxed);
+}
+
+fn handoff_sink() -> Option<fn(Duration)> {
+    let sink = HANDOFF_SINK.load(Ordering::Relaxed);
+    if sink.is_null() {
+        None
+    } else {
+        // SAFETY: non-null values are only stored by `set_handoff_sink`
+        Some(unsafe { core::mem::transmute::<*mut (), fn(Duration)>(sink) })
+    }
+}
+
+/// The reference point of the unlock timestamps.
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// Returns the timestamp to store on unlock, or `None` if no handoff sink is registered.
+///
+/// The timestamp is never `0`, which marks a missing timestamp.
+pub(crate) fn handoff_timestamp() -> Option<u64> {
+    handoff_sink()?;
+    let nanos = epoch().elapsed().as_nanos();
+    Some(u64::try_from(nanos).unwrap_or(u64::MAX).max(1))
+}
+
+/// Reports the time since `unlocked_at`, a timestamp of [`handoff_timestamp`].
+pub(crate) fn report_handoff(unlocked_at: u64) {
+    if let Some(sink) = handoff_sink() {
+        let now = epoch().elapsed();
+        sink(now.saturating_sub(Duration::from_nanos(unlocked_at)));
+    }
+}
+
+/// A [`SpinlockGuard`] that measures how long the lock is held.
+///
+/// Created by [`SpinlockExt::lock_timed`](crate::SpinlockExt::lock_timed). When the guard is
+/// dropped, the lock is released and the hold duration is reported to the function registered
+/// through [`set_hold_time_sink`].
+///
+/// Guards created by [`SpinlockExt::lock_max_hold`](crate::SpinlockExt::lock_max_hold)
+/// additionally panic on drop if the lock was held for longer than the given maximum. This check
+/// is only performed when debug assertions are enabled.
+///
+/// Requires the `timing` feature.
+pub struct TimedSpinlockGuard<'a, T: ?Sized> {
+    guard: ManuallyDrop<SpinlockGuard<'a, T>>,
+    acquired: Instant,
+    #[cfg(debug_assertions)]
+    max_hold: Option<Duration>,
+}
+
+impl<'a, T: ?Sized> TimedSpinlockGuard<'a, T> {
+    pub(crate) fn new(guard: SpinlockGuard<'a, T>, max_hold: Option<Duration>) -> Self {
+        // the maximum hold time is only checked with debug assertions
+        #[cfg(not(debug_assertions))]
+        let _ = max_hold;
+
+        Self {
+            guard: ManuallyDrop::new(guard),
+            acquired: Instant::now(),
+            #[cfg(debug_assertions)]
+            max_hold,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for TimedSpinlockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for TimedSpinlockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for TimedSpinlockGuard<'_, T> {
+    fn drop(&mut self) {
+        let held = self.acquired.elapsed();
+        // SAFETY: the guard is not used after this point
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if let Some(sink) = hold_time_sink() {
+            sink(held);
+        }
+
+        #[cfg(debug_assertions)]
+        if let Some(max_hold) = self.max_hold {
+            // don't turn an unwinding panic into an abort
+            if held > max_hold && !std::thread::panicking() {
+                panic!(
+                    "spinlock was held for {:?}, longer than the maximum of {:?}",
+                    held, max_hold
+                );
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for TimedSpinlockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Spinlock, SpinlockExt};
+    use core::{cell::Cell, sync::atomic::AtomicU64};
+    use std::thread;
+
+    static MAX_HOLD_NANOS: AtomicU64 = AtomicU64::new(0);
+
+    // keeps the maximum so that the other tests in this module can't overwrite the measurement
+    fn record(held: Duration) {
+        MAX_HOLD_NANOS.fetch_max(held.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn reports_hold_duration() {
+        set_hold_time_sink(record);
+
+        let spinlock = Spinlock::new(0);
+        let mut guard = spinlock.lock_timed();
+        *guard += 1;
+        thread::sleep(Duration::from_millis(20));
+        drop(guard);
+
+        assert!(!spinlock.is_locked());
+        let held = Duration::from_nanos(MAX_HOLD_NANOS.load(Ordering::Relaxed));
+        assert!(held >= Duration::from_millis(20));
+        assert!(held < Duration::from_secs(5));
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    std::thread_local! {
+        static LAST_HANDOFF: Cell<Option<Duration>> = const { Cell::new(None) };
+    }
+
+    // the sink runs on the acquiring thread, so other tests can't overwrite the measurement
+    fn record_handoff(latency: Duration) {
+        LAST_HANDOFF.with(|last| last.set(Some(latency)));
+    }
+
+    #[test]
+    fn reports_handoff_latency() {
+        set_handoff_sink(record_handoff);
+
+        let spinlock = Spinlock::new(0);
+        *spinlock.lock() += 1;
+        LAST_HANDOFF.with(|last| last.set(None));
+        thread::sleep(Duration::from_millis(20));
+        *spinlock.lock() += 1;
+
+        let latency = LAST_HANDOFF.with(|last| last.take()).unwrap();
+        assert!(latency >= Duration::from_millis(20));
+        assert!(latency < Duration::from_secs(5));
+
+        // every unlock is reported once
+        assert!(spinlock.try_lock().is_some());
+        assert!(LAST_HANDOFF.with(|last| last.take()).unwrap() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn max_hold_within_limit() {
+        let spinlock = Spinlock::new(0);
+        *spinlock.lock_max_hold(Duration::from_secs(5)) += 1;
+        assert_eq!(*spinlock.lock(), 1);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "longer than the maximum")]
+    fn max_hold_exceeded() {
+        let spinlock = Spinlock::new(0);
+        let _guard = spinlock.lock_max_hold(Duration::from_millis(1));
+        thread::sleep(Duration::from_millis(20));
+    }
+}
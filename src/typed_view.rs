@@ -0,0 +1,174 @@
+use crate::RawSpinlock;
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+use lock_api::RawMutex;
+
+/// Treats a bare [`RawSpinlock`] as protecting the data behind `ptr`.
+///
+/// This is useful for initialization sequences where a raw lock guards a static region that is
+/// not initialized yet: the region is first initialized under the raw lock, and afterwards it is
+/// accessed through the returned [`TypedView`], which locks the raw lock before dereferencing
+/// the pointer.
+///
+/// ## Safety
+///
+/// For the whole lifetime `'a` of the returned view, the caller must ensure that:
+///
+/// - `ptr` is non-null, properly aligned, and points to an initialized value of type `T`.
+/// - The pointee is only accessed while `raw` is locked, including accesses that don't go
+///   through the view. In particular, `raw` must not be used to protect any other data.
+/// - If the view is shared between threads, `T` must be [`Send`], just like for a
+///   [`Spinlock`](crate::Spinlock).
+///
+/// ## Example
+///
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use lock_api::RawMutex;
+/// use spinning_top::{assume_init, RawSpinlock};
+///
+/// let raw = RawSpinlock::INIT;
+/// let mut region = MaybeUninit::<u32>::uninit();
+///
+/// // initialize the region under the raw lock
+/// raw.lock();
+/// region.write(1);
+/// unsafe { raw.unlock() };
+///
+/// let view = unsafe { assume_init(&raw, region.as_mut_ptr()) };
+/// *view.lock() += 1;
+/// assert_eq!(*view.lock(), 2);
+/// ```
+pub unsafe fn assume_init<T: ?Sized>(raw: &RawSpinlock, ptr: *mut T) -> TypedView<'_, T> {
+    TypedView {
+        raw,
+        ptr,
+        _marker: PhantomData,
+    }
+}
+
+/// A lock-aware view of data that is protected by a bare [`RawSpinlock`].
+///
+/// Created by [`assume_init`].
+pub struct TypedView<'a, T: ?Sized> {
+    raw: &'a RawSpinlock,
+    ptr: *mut T,
+    _marker: PhantomData<&'a mut T>,
+}
+
+// SAFETY: like `Spinlock<T>`, the view only hands out access to the data while the lock is held
+unsafe impl<T: ?Sized + Send> Send for TypedView<'_, T> {}
+// SAFETY: like `Spinlock<T>`, the view only hands out access to the data while the lock is held
+unsafe impl<T: ?Sized + Send> Sync for TypedView<'_, T> {}
+
+impl<'a, T: ?Sized> TypedView<'a, T> {
+    /// Locks the raw lock, spinning until it is available.
+    pub fn lock(&self) -> TypedViewGuard<'_, T> {
+        self.raw.lock();
+        TypedViewGuard {
+            raw: self.raw,
+            ptr: self.ptr,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Tries to lock the raw lock without spinning.
+    pub fn try_lock(&self) -> Option<TypedViewGuard<'_, T>> {
+        if self.raw.try_lock() {
+            Some(TypedViewGuard {
+                raw: self.raw,
+                ptr: self.ptr,
+                _marker: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the underlying raw lock.
+    pub fn raw(&self) -> &'a RawSpinlock {
+        self.raw
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for TypedView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedView")
+            .field("raw", &self.raw)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A RAII guard returned by [`TypedView::lock`].
+///
+/// Unlocks the raw lock when dropped.
+pub struct TypedViewGuard<'a, T: ?Sized> {
+    raw: &'a RawSpinlock,
+    ptr: *mut T,
+    _marker: PhantomData<&'a mut T>,
+}
+
+// SAFETY: `RawSpinlock` can be unlocked from another thread, unless the `no-send-guards`
+// feature is enabled
+#[cfg(not(feature = "no-send-guards"))]
+unsafe impl<T: ?Sized + Send> Send for TypedViewGuard<'_, T> {}
+// SAFETY: the guard only hands out shared references through `&self`
+unsafe impl<T: ?Sized + Sync> Sync for TypedViewGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for TypedViewGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the lock is held and `assume_init` requires the pointer to be valid
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T: ?Sized> DerefMut for TypedViewGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the lock is held and `assume_init` requires the pointer to be valid
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<T: ?Sized> Drop for TypedViewGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the lock was acquired when this guard was created
+        unsafe { self.raw.unlock() };
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for TypedViewGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::MaybeUninit;
+
+    #[test]
+    fn initialize_then_view() {
+        let raw = RawSpinlock::INIT;
+        let mut region = MaybeUninit::<u32>::uninit();
+
+        raw.lock();
+        region.write(42);
+        unsafe { raw.unlock() };
+
+        let view = unsafe { assume_init(&raw, region.as_mut_ptr()) };
+        {
+            let mut guard = view.lock();
+            assert!(raw.is_locked());
+            assert!(view.try_lock().is_none());
+            *guard += 1;
+        }
+        assert!(!raw.is_locked());
+        assert_eq!(*view.try_lock().unwrap(), 43);
+    }
+}
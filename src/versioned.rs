@@ -0,0 +1,314 @@
+use crate::{Spinlock, SpinlockGuard};
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A [`Spinlock`] for data that is read often and updated rarely.
+///
+/// Every update through [`write`](Self::write) or [`update`](Self::update) increments a version
+/// counter. Readers remember the version of the snapshot they hold and call
+/// [`read_if_newer`](Self::read_if_newer), which only takes the lock and clones the data if an
+/// update happened in the meantime. In the common case that nothing changed, a read costs a
+/// single atomic load and never touches the lock.
+///
+/// ## Consistency
+///
+/// Snapshots are always cloned while holding the lock, so a reader never observes a partially
+/// written value, regardless of how many fields `T` has. Cloning without the lock, as a seqlock
+/// does, is not possible for arbitrary `Clone` types: the writer might free memory that the
+/// reader's `clone` implementation is still following, e.g. the buffer of a `String`.
+///
+/// The version is a `u64`, so a reader holding an old version can't mistake a wrapped-around
+/// counter for an unchanged one. This type is therefore only available on targets with 64-bit
+/// atomics.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::VersionedSpinlock;
+///
+/// let config = VersionedSpinlock::new(String::from("v1"));
+/// let (mut snapshot, mut version) = config.read();
+/// assert!(config.read_if_newer(version).is_none());
+///
+/// config.write(String::from("v2"));
+/// if let Some((new, new_version)) = config.read_if_newer(version) {
+///     snapshot = new;
+///     version = new_version;
+/// }
+/// assert_eq!(snapshot, "v2");
+/// assert_eq!(version, config.version());
+/// ```
+pub struct VersionedSpinlock<T: ?Sized> {
+    /// Incremented while holding the lock after every update.
+    version: AtomicU64,
+    lock: Spinlock<T>,
+}
+
+impl<T> VersionedSpinlock<T> {
+    /// Creates a new `VersionedSpinlock` with version `0`.
+    pub const fn new(val: T) -> Self {
+        Self {
+            version: AtomicU64::new(0),
+            lock: Spinlock::new(val),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+
+    /// Replaces the data with `val` and increments the version.
+    pub fn write(&self, val: T) {
+        self.update(|data| *data = val);
+    }
+}
+
+impl<T: ?Sized> VersionedSpinlock<T> {
+    /// Returns the current version.
+    ///
+    /// The version starts at `0` and is incremented after every update. It is a `u64`, so it
+    /// doesn't wrap around in practice and an old version never matches again.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Modifies the data through `f` and increments the version.
+    ///
+    /// The version is incremented even if `f` doesn't change the data. If `f` panics, the
+    /// version is incremented as well before the lock is released, since `f` may have modified
+    /// the data partially. This way, readers never keep a snapshot that differs from the data.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut guard = self.lock_dirty_tracked();
+        // increments the version on drop, also during unwinding
+        DirtyTrackedGuard::mark_dirty(&mut guard);
+        f(&mut guard)
+    }
+
+    /// Locks the data and increments the version on release only if the data was mutated.
+    ///
+    /// The returned guard gives shared access to the data freely. A mutable borrow through
+    /// [`DerefMut`] marks it as dirty, and only a dirty guard increments the version when it is
+    /// dropped. This avoids spurious version bumps for critical sections that only read the
+    /// data, so readers don't clone an unchanged snapshot through
+    /// [`read_if_newer`](Self::read_if_newer).
+    ///
+    /// Mutations through interior mutability, e.g. of a `Cell` inside `T`, don't go through
+    /// `DerefMut` and thus don't mark the guard as dirty. Use
+    /// [`DirtyTrackedGuard::mark_dirty`] for them.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use spinning_top::VersionedSpinlock;
+    ///
+    /// let lock = VersionedSpinlock::new(vec![1, 2]);
+    /// // a read-only critical section keeps the version
+    /// assert_eq!(lock.lock_dirty_tracked().len(), 2);
+    /// assert_eq!(lock.version(), 0);
+    ///
+    /// lock.lock_dirty_tracked().push(3);
+    /// assert_eq!(lock.version(), 1);
+    /// ```
+    pub fn lock_dirty_tracked(&self) -> DirtyTrackedGuard<'_, T> {
+        DirtyTrackedGuard {
+            guard: self.lock.lock(),
+            version: &self.version,
+            dirty: false,
+        }
+    }
+}
+
+impl<T: Clone> VersionedSpinlock<T> {
+    /// Returns a clone of the data together with its version.
+    pub fn read(&self) -> (T, u64) {
+        let guard = self.lock.lock();
+        // only updated while the lock is held, so it matches the data
+        let version = self.version.load(Ordering::Relaxed);
+        (guard.clone(), version)
+    }
+
+    /// Returns a clone of the data if its version differs from `version`.
+    ///
+    /// Returns `None` without taking the lock if the data wasn't updated since `version`.
+    pub fn read_if_newer(&self, version: u64) -> Option<(T, u64)> {
+        if self.version() == version {
+            None
+        } else {
+            Some(self.read())
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for VersionedSpinlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedSpinlock")
+            .field("version", &self.version())
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+/// A guard returned by [`VersionedSpinlock::lock_dirty_tracked`].
+///
+/// Increments the version of the lock when it is dropped, before the lock is released, but only
+/// if the data was borrowed mutably or the guard was marked as dirty.
+pub struct DirtyTrackedGuard<'a, T: ?Sized> {
+    guard: SpinlockGuard<'a, T>,
+    version: &'a AtomicU64,
+    dirty: bool,
+}
+
+impl<T: ?Sized> DirtyTrackedGuard<'_, T> {
+    /// Returns whether the version will be incremented when the guard is dropped.
+    ///
+    /// This is an associated function to avoid conflicts with methods of `T`. Call it as
+    /// `DirtyTrackedGuard::is_dirty(&guard)`.
+    pub fn is_dirty(this: &Self) -> bool {
+        this.dirty
+    }
+
+    /// Marks the data as modified, e.g. after a mutation through interior mutability.
+    pub fn mark_dirty(this: &mut Self) {
+        this.dirty = true;
+    }
+}
+
+impl<T: ?Sized> Deref for DirtyTrackedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for DirtyTrackedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for DirtyTrackedGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.dirty {
+            // the lock is still held, it is released when the `guard` field is dropped afterwards
+            let version = self.version.load(Ordering::Relaxed);
+            self.version.store(version + 1, Ordering::Release);
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for DirtyTrackedGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{sync::Arc, thread, vec, vec::Vec};
+
+    #[test]
+    fn versions() {
+        let lock = VersionedSpinlock::new(1);
+        assert_eq!(lock.read(), (1, 0));
+        assert!(lock.read_if_newer(0).is_none());
+
+        lock.write(2);
+        assert_eq!(lock.update(|v| *v * 10), 20);
+        assert_eq!(lock.version(), 2);
+        assert_eq!(lock.read_if_newer(0), Some((2, 2)));
+        assert!(lock.read_if_newer(2).is_none());
+        assert_eq!(lock.into_inner(), 2);
+    }
+
+    #[test]
+    fn lock_dirty_tracked_read_only() {
+        let lock = VersionedSpinlock::new(vec![1, 2]);
+        {
+            let guard = lock.lock_dirty_tracked();
+            assert_eq!(guard.iter().sum::<i32>(), 3);
+            assert!(!DirtyTrackedGuard::is_dirty(&guard));
+            assert!(lock.lock.is_locked());
+        }
+        assert!(!lock.lock.is_locked());
+        assert_eq!(lock.version(), 0);
+        assert!(lock.read_if_newer(0).is_none());
+    }
+
+    #[test]
+    fn lock_dirty_tracked_mutation() {
+        let lock = VersionedSpinlock::new(vec![1, 2]);
+        {
+            let mut guard = lock.lock_dirty_tracked();
+            guard.push(3);
+            assert!(DirtyTrackedGuard::is_dirty(&guard));
+            // the version is only incremented when the guard is dropped
+            assert_eq!(lock.version(), 0);
+        }
+        assert_eq!(lock.read(), (vec![1, 2, 3], 1));
+
+        let mut guard = lock.lock_dirty_tracked();
+        DirtyTrackedGuard::mark_dirty(&mut guard);
+        drop(guard);
+        assert_eq!(lock.version(), 2);
+    }
+
+    #[test]
+    fn update_panic_releases_lock() {
+        let lock = VersionedSpinlock::new(1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            lock.update(|value| {
+                *value = 2;
+                panic!("update")
+            })
+        }));
+        assert!(result.is_err());
+        assert!(!lock.lock.is_locked());
+        // the partial update is visible to readers of the old version
+        assert_eq!(lock.read_if_newer(0), Some((2, 1)));
+    }
+
+    #[test]
+    fn readers_see_consistent_snapshots() {
+        const UPDATES: u64 = 100;
+        const READERS: usize = 2;
+
+        // all elements of the vector are equal to its version
+        let lock = Arc::new(VersionedSpinlock::new(vec![0; 16]));
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| {
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    let mut version = u64::MAX;
+                    while version != UPDATES {
+                        if let Some((snapshot, new_version)) = lock.read_if_newer(version) {
+                            assert!(snapshot.iter().all(|&v| v == new_version));
+                            version = new_version;
+                        }
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+
+        for _ in 0..UPDATES {
+            lock.update(|data| {
+                for v in data.iter_mut() {
+                    *v += 1;
+                }
+            });
+            thread::yield_now();
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
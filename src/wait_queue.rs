@@ -0,0 +1,403 @@
+use crate::Spinlock;
+use core::{cell::Cell, fmt, marker::PhantomPinned, mem, pin::Pin, ptr};
+
+/// An intrusive FIFO queue of waiting threads, for building blocking primitives without
+/// allocation.
+///
+/// Each waiter provides its own [`WaitNode`], typically pinned on its stack, and calls
+/// [`wait`](Self::wait) with a `park` callback that blocks the current thread. Notifiers call
+/// [`notify_one`](Self::notify_one) or [`notify_all`](Self::notify_all) with an `unpark`
+/// callback that receives the data of the dequeued nodes, e.g. a thread handle. The queue itself
+/// is protected by a [`Spinlock`], which is only held for the list operations and the `unpark`
+/// calls, never while parking.
+///
+/// The `park` callback may return before the node was notified, e.g. on a spurious wakeup.
+/// Therefore, an `unpark` call that happens before the matching `park` call must make that
+/// `park` return, like `std::thread::park` does.
+///
+/// ## Example
+///
+/// ```rust
+/// use core::pin::pin;
+/// use spinning_top::{WaitNode, WaitQueue};
+/// use std::thread::{self, Thread};
+///
+/// let queue = WaitQueue::<Thread>::new();
+/// thread::scope(|s| {
+///     s.spawn(|| {
+///         let node = pin!(WaitNode::new(thread::current()));
+///         assert!(queue.wait(node, |_| {
+///             thread::park();
+///             true
+///         }));
+///     });
+///
+///     // wake the waiter once it is queued
+///     while !queue.notify_one(|thread| thread.unpark()) {
+///         thread::yield_now();
+///     }
+/// });
+/// ```
+pub struct WaitQueue<T> {
+    list: Spinlock<List<T>>,
+}
+
+struct List<T> {
+    head: *const WaitNode<T>,
+    tail: *const WaitNode<T>,
+    len: usize,
+}
+
+// SAFETY: the list only points to nodes whose data is accessed by other threads through `&T`,
+// which is allowed because `WaitQueue` is only `Send` and `Sync` if `T: Sync`.
+unsafe impl<T: Sync> Send for List<T> {}
+
+/// A node of a [`WaitQueue`] that is provided by a waiting thread.
+///
+/// The node stores data that is passed to the `unpark` callback when the node is notified,
+/// e.g. a handle of the waiting thread. The links to the neighboring nodes are only accessed
+/// while the queue's lock is held.
+pub struct WaitNode<T> {
+    data: T,
+    prev: Cell<*const WaitNode<T>>,
+    next: Cell<*const WaitNode<T>>,
+    notified: Cell<bool>,
+    _pinned: PhantomPinned,
+}
+
+impl<T> WaitNode<T> {
+    /// Creates a new node that isn't part of a queue.
+    pub const fn new(data: T) -> Self {
+        Self {
+            data,
+            prev: Cell::new(ptr::null()),
+            next: Cell::new(ptr::null()),
+            notified: Cell::new(false),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Returns the data stored in the node.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T> WaitQueue<T> {
+    /// Creates a new empty queue.
+    pub const fn new() -> Self {
+        Self {
+            list: Spinlock::new(List {
+                head: ptr::null(),
+                tail: ptr::null(),
+                len: 0,
+            }),
+        }
+    }
+
+    /// Appends `node` to the queue and parks until it is notified.
+    ///
+    /// The `park` callback is called with the node's data to block the current thread. It
+    /// returns `false` if it timed out, in which case the node removes itself from the queue
+    /// and this method returns `false`. If it returns `true` without the node being notified,
+    /// e.g. on a spurious wakeup, it is called again. Returns `true` once the node was notified,
+    /// also if the notification raced with a timeout.
+    ///
+    /// The node is only part of the queue while this method runs, so it can be reused for
+    /// multiple calls. This also holds if `park` panics: the node is removed from the queue
+    /// while unwinding.
+    pub fn wait(&self, node: Pin<&mut WaitNode<T>>, mut park: impl FnMut(&T) -> bool) -> bool {
+        let node: &WaitNode<T> = node.into_ref().get_ref();
+        {
+            let mut list = self.list.lock();
+            node.notified.set(false);
+            node.prev.set(list.tail);
+            node.next.set(ptr::null());
+            if list.tail.is_null() {
+                list.head = node;
+            } else {
+                // SAFETY: queued nodes stay valid while they are in the list
+                unsafe { (*list.tail).next.set(node) };
+            }
+            list.tail = node;
+            list.len += 1;
+        }
+
+        // removes the node from the queue if `park` panics
+        let dequeue = Dequeue { queue: self, node };
+        loop {
+            let timed_out = !park(&node.data);
+
+            // notifiers set the flag and call `unpark` while holding the lock, so the node
+            // stays valid until they are done with it
+            let mut list = self.list.lock();
+            if node.notified.get() {
+                mem::forget(dequeue);
+                return true;
+            }
+            if timed_out {
+                // SAFETY: the node was not notified, so it is still in the list
+                unsafe { list.unlink(node) };
+                mem::forget(dequeue);
+                return false;
+            }
+        }
+    }
+
+    /// Dequeues the oldest waiting node, if any, and calls `unpark` with its data.
+    ///
+    /// Returns whether a node was notified. The `unpark` callback is called while the queue's
+    /// lock is held, so it should be short.
+    pub fn notify_one(&self, unpark: impl FnOnce(&T)) -> bool {
+        let mut list = self.list.lock();
+        let head = list.head;
+        if head.is_null() {
+            return false;
+        }
+        // SAFETY: queued nodes stay valid while they are in the list and the lock is held
+        unsafe {
+            list.unlink(head);
+            (*head).notified.set(true);
+            unpark(&(*head).data);
+        }
+        true
+    }
+
+    /// Dequeues all waiting nodes in FIFO order and calls `unpark` with the data of each of them.
+    ///
+    /// Returns the number of notified nodes. The `unpark` callback is called while the queue's
+    /// lock is held, so it should be short.
+    pub fn notify_all(&self, mut unpark: impl FnMut(&T)) -> usize {
+        let mut notified = 0;
+        while self.notify_one(&mut unpark) {
+            notified += 1;
+        }
+        notified
+    }
+
+    /// Returns the number of waiting nodes.
+    pub fn len(&self) -> usize {
+        self.list.lock().len
+    }
+
+    /// Returns whether no nodes are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Removes a node that wasn't notified from the queue when it is dropped.
+struct Dequeue<'a, T> {
+    queue: &'a WaitQueue<T>,
+    node: &'a WaitNode<T>,
+}
+
+impl<T> Drop for Dequeue<'_, T> {
+    fn drop(&mut self) {
+        let mut list = self.queue.list.lock();
+        if !self.node.notified.get() {
+            // SAFETY: the node was not notified, so it is still in the list
+            unsafe { list.unlink(self.node) };
+        }
+    }
+}
+
+impl<T> List<T> {
+    /// Removes `node` from the list.
+    ///
+    /// ## Safety
+    ///
+    /// `node` must be part of this list.
+    unsafe fn unlink(&mut self, node: *const WaitNode<T>) {
+        let prev = (*node).prev.replace(ptr::null());
+        let next = (*node).next.replace(ptr::null());
+        if prev.is_null() {
+            self.head = next;
+        } else {
+            (*prev).next.set(next);
+        }
+        if next.is_null() {
+            self.tail = prev;
+        } else {
+            (*next).prev.set(prev);
+        }
+        self.len -= 1;
+    }
+}
+
+// SAFETY: the nodes are only accessed while holding the lock, and their data only through `&T`
+unsafe impl<T: Sync> Send for WaitQueue<T> {}
+unsafe impl<T: Sync> Sync for WaitQueue<T> {}
+
+impl<T> Default for WaitQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for WaitQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaitQueue")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WaitNode<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaitNode")
+            .field("data", &self.data)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::pin::pin;
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::Mutex,
+        thread::{self, Thread},
+        vec::Vec,
+    };
+
+    fn park() -> impl FnMut(&(usize, Thread)) -> bool {
+        |_| {
+            thread::park();
+            true
+        }
+    }
+
+    #[test]
+    fn fifo_order() {
+        const WAITERS: usize = 3;
+        let queue = WaitQueue::new();
+        let woken = Mutex::new(Vec::new());
+
+        thread::scope(|s| {
+            for id in 0..WAITERS {
+                let queue = &queue;
+                s.spawn(move || {
+                    let node = pin!(WaitNode::new((id, thread::current())));
+                    assert!(queue.wait(node, park()));
+                });
+                // enqueue the waiters one after another
+                while queue.len() <= id {
+                    thread::yield_now();
+                }
+            }
+
+            for _ in 0..WAITERS {
+                assert!(queue.notify_one(|(id, thread)| {
+                    woken.lock().unwrap().push(*id);
+                    thread.unpark();
+                }));
+            }
+            assert!(queue.is_empty());
+            assert!(!queue.notify_one(|_| unreachable!()));
+        });
+
+        assert_eq!(*woken.lock().unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn timeout_removes_node() {
+        let queue = WaitQueue::new();
+        let mut node = pin!(WaitNode::new(1));
+
+        let mut calls = 0;
+        let notified = queue.wait(node.as_mut(), |data| {
+            assert_eq!(*data, 1);
+            calls += 1;
+            // simulate a spurious wakeup before timing out
+            calls < 2
+        });
+        assert!(!notified);
+        assert_eq!(calls, 2);
+        assert!(queue.is_empty());
+
+        // the node can be reused
+        assert!(!queue.wait(node, |_| false));
+        assert_eq!(queue.notify_all(|_| unreachable!()), 0);
+    }
+
+    #[test]
+    fn timeout_in_the_middle() {
+        let queue = WaitQueue::new();
+        let woken = Mutex::new(Vec::new());
+
+        thread::scope(|s| {
+            for id in 0..3 {
+                let queue = &queue;
+                s.spawn(move || {
+                    let node = pin!(WaitNode::new((id, thread::current())));
+                    if id == 1 {
+                        // times out after being unparked by the main thread
+                        assert!(!queue.wait(node, |_| {
+                            thread::park();
+                            false
+                        }));
+                    } else {
+                        assert!(queue.wait(node, park()));
+                    }
+                });
+                while queue.len() <= id {
+                    thread::yield_now();
+                }
+            }
+
+            // let the second waiter time out and remove itself
+            let middle = {
+                let list = queue.list.lock();
+                // SAFETY: the nodes stay valid while they are queued and the lock is held
+                unsafe { (*(*list.head).next.get()).data.1.clone() }
+            };
+            middle.unpark();
+            while queue.len() > 2 {
+                thread::yield_now();
+            }
+
+            let notified = queue.notify_all(|(id, thread)| {
+                woken.lock().unwrap().push(*id);
+                thread.unpark();
+            });
+            assert_eq!(notified, 2);
+        });
+
+        assert_eq!(*woken.lock().unwrap(), [0, 2]);
+    }
+
+    #[test]
+    fn panicking_park_removes_node() {
+        let queue = WaitQueue::new();
+
+        thread::scope(|s| {
+            let queue = &queue;
+            s.spawn(move || {
+                let node = pin!(WaitNode::new((0, thread::current())));
+                assert!(queue.wait(node, park()));
+            });
+            while queue.is_empty() {
+                thread::yield_now();
+            }
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                let node = pin!(WaitNode::new((1, thread::current())));
+                queue.wait(node, |_| panic!("park failed"))
+            }));
+            assert!(result.is_err());
+            assert_eq!(queue.len(), 1);
+
+            // the list doesn't point to the unwound node anymore
+            assert!(queue.notify_one(|(id, thread)| {
+                assert_eq!(*id, 0);
+                thread.unpark();
+            }));
+            assert!(queue.is_empty());
+            assert!(!queue.notify_one(|_| unreachable!()));
+        });
+    }
+}
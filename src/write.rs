@@ -0,0 +1,153 @@
+//! A spinlock-protected writer, enabled by the `std` feature.
+
+use crate::Spinlock;
+use core::{fmt, ops::Deref};
+use std::io::{self, IoSlice, Write};
+
+/// A [`Spinlock`] around a writer that implements [`Write`] itself.
+///
+/// Like for [`std::io::Stdout`], `Write` is also implemented for shared references, so a
+/// `&WriteSpinlock` can be passed to `write!` and `writeln!` directly. Every call of a `Write`
+/// method locks the writer only for the duration of that call. Calls that write a whole buffer
+/// or formatted message, i.e. `write_all` and `write_fmt`, hold the lock until they are done,
+/// so concurrent messages are not interleaved.
+///
+/// The wrapper dereferences to the inner [`Spinlock`], so the writer can also be locked
+/// explicitly to write multiple messages without interruption.
+///
+/// Requires the `std` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// use spinning_top::WriteSpinlock;
+/// use std::io::Write;
+///
+/// static LOG: WriteSpinlock<Vec<u8>> = WriteSpinlock::new(Vec::new());
+///
+/// writeln!(&LOG, "answer: {}", 42).unwrap();
+/// assert_eq!(*LOG.lock(), b"answer: 42\n");
+/// ```
+pub struct WriteSpinlock<W: ?Sized> {
+    lock: Spinlock<W>,
+}
+
+impl<W> WriteSpinlock<W> {
+    /// Creates a new unlocked `WriteSpinlock`.
+    pub const fn new(writer: W) -> Self {
+        Self {
+            lock: Spinlock::new(writer),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.lock.into_inner()
+    }
+}
+
+impl<W: ?Sized + Write> Write for &WriteSpinlock<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.lock.lock().write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.lock.lock().write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.lock.lock().flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.lock.lock().write_all(buf)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> io::Result<()> {
+        self.lock.lock().write_fmt(args)
+    }
+}
+
+impl<W: ?Sized + Write> Write for WriteSpinlock<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (&*self).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        (&*self).write_all(buf)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> io::Result<()> {
+        (&*self).write_fmt(args)
+    }
+}
+
+impl<W: ?Sized> Deref for WriteSpinlock<W> {
+    type Target = Spinlock<W>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lock
+    }
+}
+
+impl<W: ?Sized + fmt::Debug> fmt::Debug for WriteSpinlock<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteSpinlock")
+            .field("lock", &&self.lock)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread, vec::Vec};
+
+    #[test]
+    fn write_to_vec() {
+        let mut writer = WriteSpinlock::new(Vec::new());
+        write!(&writer, "a{}", 1).unwrap();
+        (&writer).write_all(b"b").unwrap();
+        writer.write_all(b"c").unwrap();
+        writeln!(writer).unwrap();
+        writer.flush().unwrap();
+        assert!(!writer.is_locked());
+        assert_eq!(writer.into_inner(), b"a1bc\n");
+    }
+
+    #[test]
+    fn messages_not_interleaved() {
+        const THREADS: usize = 2;
+        const LINES: usize = 50;
+
+        let writer = Arc::new(WriteSpinlock::new(Vec::new()));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|id| {
+                let writer = writer.clone();
+                thread::spawn(move || {
+                    for _ in 0..LINES {
+                        writeln!(&*writer, "{} {} {}", id, id, id).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let output = std::string::String::from_utf8(writer.lock().clone()).unwrap();
+        assert_eq!(output.lines().count(), THREADS * LINES);
+        for line in output.lines() {
+            let id = &line[..1];
+            assert_eq!(line, [id, id, id].join(" "));
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Tests the selection of the spin strategy through the `SPINNING_TOP_STRATEGY` environment
+//! variable.
+//!
+//! The strategy is cached on first use, so this test needs its own process.
+
+#![cfg(feature = "env-config")]
+
+use spinning_top::{default_spin_strategy, SpinStrategy, Spinlock};
+use std::{
+    sync::{Arc, Barrier},
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn strategy_from_environment() {
+    std::env::set_var("SPINNING_TOP_STRATEGY", "yield");
+
+    // spin on a held lock, which reads the variable
+    let spinlock = Arc::new(Spinlock::new(0));
+    let barrier = Arc::new(Barrier::new(2));
+    let holder = {
+        let spinlock = spinlock.clone();
+        let barrier = barrier.clone();
+        thread::spawn(move || {
+            let _guard = spinlock.lock();
+            barrier.wait();
+            thread::sleep(Duration::from_millis(10));
+        })
+    };
+    barrier.wait();
+    *spinlock.lock() += 1;
+    holder.join().unwrap();
+
+    // later changes have no effect
+    std::env::set_var("SPINNING_TOP_STRATEGY", "backoff");
+    assert_eq!(default_spin_strategy(), SpinStrategy::Yield);
+}
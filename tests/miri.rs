@@ -0,0 +1,49 @@
+//! Threaded lock/unlock test that is small enough to run under Miri.
+//!
+//! Run it with `cargo +nightly miri test --test miri --features std`. Miri checks the accesses
+//! to the protected data for data races, so this verifies that the lock actually synchronizes
+//! the threads. The iteration counts are kept low because Miri is slow; the test also runs as a
+//! regular integration test.
+
+use spinning_top::Spinlock;
+use std::{sync::Arc, thread};
+
+const THREADS: usize = 3;
+const ITERATIONS: usize = 20;
+
+#[test]
+fn lock_unlock_across_threads() {
+    // a non-atomic payload, so that a broken lock is reported as a data race by Miri
+    let spinlock = Arc::new(Spinlock::new(0usize));
+    let threads: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let spinlock = spinlock.clone();
+            thread::spawn(move || {
+                for _ in 0..ITERATIONS {
+                    *spinlock.lock() += 1;
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(*spinlock.lock(), THREADS * ITERATIONS);
+}
+
+#[test]
+fn contended_handoff() {
+    let spinlock = Arc::new(Spinlock::new(Vec::new()));
+    let mut guard = spinlock.lock();
+    let waiter = {
+        let spinlock = spinlock.clone();
+        // spins until the main thread releases the lock
+        thread::spawn(move || spinlock.lock().push(2))
+    };
+
+    guard.push(1);
+    drop(guard);
+    waiter.join().unwrap();
+    assert_eq!(*spinlock.lock(), [1, 2]);
+}
@@ -0,0 +1,79 @@
+//! Regression test for the memory orderings of the spinlock.
+//!
+//! The `Acquire` ordering when locking and the `Release` ordering when unlocking are what make
+//! writes inside one critical section visible in the next critical section. This test is a
+//! message-passing test that fails if these orderings are weakened, e.g. to `Relaxed`. Please
+//! don't "simplify" the orderings of `RawSpinlock` without understanding why this test exists.
+//!
+//! Weakened orderings are only observable on weakly-ordered architectures such as ARM or POWER.
+//! On x86, the hardware provides acquire/release semantics for all loads and stores, so the test
+//! passes there regardless (unless the compiler reorders the accesses).
+
+use spinning_top::Spinlock;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+const ROUNDS: usize = 1000;
+const SLOTS: usize = 8;
+
+struct Shared {
+    lock: Spinlock<()>,
+    /// Written and read only while `lock` is held.
+    ///
+    /// The payload uses `Relaxed` atomics instead of plain memory so that a broken lock leads
+    /// to an assertion failure instead of undefined behavior. Relaxed accesses don't provide any
+    /// ordering on their own, so all ordering guarantees must come from the lock.
+    payload: [AtomicUsize; SLOTS],
+    /// The last round that the writer completed, i.e. unlocked.
+    ///
+    /// This is intentionally `Relaxed` too: seeing a round number doesn't synchronize with the
+    /// writer, it only tells the reader that the writer's unlock was already performed. Since
+    /// the reader's subsequent lock reads the unlocked state written by that unlock (or a later
+    /// one), the `Release`/`Acquire` pair on the lock word is the only thing that guarantees
+    /// that the payload of the round is visible to the reader.
+    round: AtomicUsize,
+}
+
+#[test]
+fn unlock_publishes_writes_to_next_locker() {
+    let shared = Arc::new(Shared {
+        lock: Spinlock::new(()),
+        payload: Default::default(),
+        round: AtomicUsize::new(0),
+    });
+
+    let writer = {
+        let shared = shared.clone();
+        thread::spawn(move || {
+            for round in 1..=ROUNDS {
+                let guard = shared.lock.lock();
+                for slot in &shared.payload {
+                    slot.store(round, Ordering::Relaxed);
+                }
+                drop(guard);
+                shared.round.store(round, Ordering::Relaxed);
+            }
+        })
+    };
+
+    for round in 1..=ROUNDS {
+        while shared.round.load(Ordering::Relaxed) < round {
+            thread::yield_now();
+        }
+        let guard = shared.lock.lock();
+        for slot in &shared.payload {
+            // the writer may already be in a later round, but it may never be observed in an
+            // earlier one
+            let value = slot.load(Ordering::Relaxed);
+            assert!(value >= round, "stale payload {} in round {}", value, round);
+        }
+        drop(guard);
+    }
+
+    writer.join().unwrap();
+}